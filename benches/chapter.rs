@@ -0,0 +1,62 @@
+//! Times expanding a chapter with many directives into the same crate, with one resolver shared by
+//! the whole chapter like the preprocessor does, and with a new resolver for each directive.
+//!
+//! Run with `cargo bench --bench chapter`.
+
+use std::{convert::TryFrom, hint::black_box, time::Instant};
+
+use mdbook_rust_doc::{expand_chapter, CrateRoots, ExpandOptions, Resolver};
+
+const PATHS: &[&str] = &[
+    "test_crate::crustaceans::Crab",
+    "test_crate::crustaceans::Crab::num_legs",
+    "test_crate::crustaceans::Crab::scuttle",
+    "test_crate::crustaceans::Crab::greet",
+    "test_crate::crustaceans::CookedCrab::1",
+    "test_crate::crustaceans::LobsterColor::Albino",
+    "test_crate::crustaceans::cook",
+    "test_crate::crab_behavior::Speak",
+];
+
+fn crate_roots() -> CrateRoots {
+    let test_crate = format!("test_crate={}/test-crate", env!("CARGO_MANIFEST_DIR"));
+    CrateRoots::try_from(vec![test_crate]).unwrap()
+}
+
+fn main() {
+    let options = ExpandOptions::default();
+    for repeats in [1, 4, 16] {
+        let directives: Vec<String> = (0..repeats)
+            .flat_map(|_| {
+                PATHS
+                    .iter()
+                    .map(|path| format!("{{{{ #rustdoc {} }}}}", path))
+            })
+            .collect();
+        let chapter = directives.join("\n\n");
+        let iterations = 20;
+
+        let started = Instant::now();
+        for _ in 0..iterations {
+            let resolver = Resolver::new(crate_roots());
+            black_box(expand_chapter(&resolver, &options, black_box(&chapter)).unwrap());
+        }
+        let shared = started.elapsed();
+
+        let started = Instant::now();
+        for _ in 0..iterations {
+            for directive in &directives {
+                let resolver = Resolver::new(crate_roots());
+                black_box(expand_chapter(&resolver, &options, black_box(directive)).unwrap());
+            }
+        }
+        let separate = started.elapsed();
+
+        println!(
+            "{:>3} directives: {:>8.0} µs with a shared resolver, {:>8.0} µs with one per directive",
+            directives.len(),
+            shared.as_micros() as f64 / iterations as f64,
+            separate.as_micros() as f64 / iterations as f64
+        );
+    }
+}
@@ -0,0 +1,37 @@
+//! Times descending through long paths one segment at a time, like the resolver does.
+//!
+//! Run with `cargo bench --bench rust_path`.
+
+use std::{hint::black_box, str::FromStr, time::Instant};
+
+use mdbook_rust_doc::RustPath;
+
+/// Walk every segment of `path` with `head_tail`, returning how many there were.
+fn descend(path: &RustPath) -> usize {
+    let mut depth = 1;
+    let mut rest = path.head_tail().1;
+    while let Some(path) = rest {
+        depth += 1;
+        rest = path.head_tail().1;
+    }
+    depth
+}
+
+fn main() {
+    for depth in [4, 16, 64, 256] {
+        let segments: Vec<String> = (0..depth).map(|i| format!("segment_{}", i)).collect();
+        let path = RustPath::from_str(&segments.join("::")).unwrap();
+        let iterations = 100_000 / depth;
+
+        let started = Instant::now();
+        for _ in 0..iterations {
+            assert_eq!(descend(black_box(&path)), depth);
+        }
+        let elapsed = started.elapsed();
+        println!(
+            "head_tail over {:>3} segments: {:>8.0} ns per path",
+            depth,
+            elapsed.as_nanos() as f64 / iterations as f64
+        );
+    }
+}
@@ -0,0 +1,245 @@
+use anyhow::{Context, Result};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    cell::{Cell, RefCell},
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+/// Resolved docs saved to disk between runs, so that they don't need to be resolved again until
+/// one of the source files they came from changes.
+///
+/// Each run of the preprocessor is a new process, even during `mdbook serve`, so without this every
+/// source file is parsed again on every reload. Files are compared by a hash of their contents, so
+/// a checkout that only changes modification times keeps the cache, and an edit always expires it.
+///
+/// Entries that aren't used in a run are dropped when the cache is saved, so that the chapters of
+/// earlier edits don't pile up.
+#[derive(Debug)]
+pub(crate) struct DiskCache {
+    path: PathBuf,
+    data: RefCell<CacheData>,
+    is_dirty: Cell<bool>,
+    /// The keys of the entries that were looked up or saved in this run.
+    used: RefCell<HashSet<String>>,
+    /// The hash of each file that has been checked in this run, or `None` if it couldn't be read.
+    file_hashes: RefCell<HashMap<PathBuf, Option<String>>>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CacheData {
+    /// Describes the configuration that the entries were resolved with. If it changes, all of the
+    /// entries are thrown away.
+    fingerprint: String,
+    entries: HashMap<String, CacheEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheEntry {
+    value: serde_json::Value,
+    /// The source files that were read to resolve the doc, and the hashes of their contents.
+    files: Vec<(PathBuf, String)>,
+}
+
+impl DiskCache {
+    /// Load the cache at `path`, starting from empty if it doesn't exist, can't be read, or was
+    /// made with a different `fingerprint`.
+    pub(crate) fn load(path: PathBuf, fingerprint: String) -> Self {
+        let data = std::fs::read(&path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<CacheData>(&bytes).ok())
+            .filter(|data| data.fingerprint == fingerprint)
+            .unwrap_or_else(|| CacheData {
+                fingerprint,
+                entries: HashMap::new(),
+            });
+        Self {
+            path,
+            data: RefCell::new(data),
+            is_dirty: Cell::new(false),
+            used: RefCell::new(HashSet::new()),
+            file_hashes: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// The value saved for `key` and the source files it was resolved from, if there is one and
+    /// none of those files have changed.
+    pub(crate) fn get<T: DeserializeOwned>(&self, key: &str) -> Option<(T, Vec<PathBuf>)> {
+        let data = self.data.borrow();
+        let entry = data.entries.get(key)?;
+        let is_fresh = entry
+            .files
+            .iter()
+            .all(|(file, hash)| self.file_hash(file).as_ref() == Some(hash));
+        if is_fresh {
+            let files = entry.files.iter().map(|(file, _)| file.clone()).collect();
+            let value = serde_json::from_value(entry.value.clone()).ok()?;
+            self.used.borrow_mut().insert(key.to_string());
+            Some((value, files))
+        } else {
+            None
+        }
+    }
+
+    /// Save `value` for `key`, along with the source `files` that it was resolved from.
+    pub(crate) fn insert<T: Serialize>(&self, key: String, value: &T, files: Vec<PathBuf>) {
+        // Don't save an entry that can't be checked later.
+        let files = match files
+            .into_iter()
+            .map(|file| self.file_hash(&file).map(|hash| (file, hash)))
+            .collect::<Option<Vec<_>>>()
+        {
+            Some(files) => files,
+            None => return,
+        };
+        let value = match serde_json::to_value(value) {
+            Ok(value) => value,
+            Err(_) => return,
+        };
+        self.used.borrow_mut().insert(key.clone());
+        self.data
+            .borrow_mut()
+            .entries
+            .insert(key, CacheEntry { value, files });
+        self.is_dirty.set(true);
+    }
+
+    /// Replace the set of keys used so far with `used`, returning the keys it had. This lets a
+    /// lookup find out which entries the lookups inside of it used.
+    pub(crate) fn replace_used(&self, used: HashSet<String>) -> HashSet<String> {
+        self.used.replace(used)
+    }
+
+    /// Count the entries with `keys` as used in this run, like those that a reused entry was made
+    /// from, so that they are kept.
+    pub(crate) fn mark_used(&self, keys: impl IntoIterator<Item = String>) {
+        self.used.borrow_mut().extend(keys);
+    }
+
+    /// Write the cache back to disk without the entries that weren't used in this run, if
+    /// anything changed.
+    pub(crate) fn save(&self) -> Result<()> {
+        {
+            let used = self.used.borrow();
+            let mut data = self.data.borrow_mut();
+            let count = data.entries.len();
+            data.entries.retain(|key, _| used.contains(key));
+            if data.entries.len() != count {
+                self.is_dirty.set(true);
+            }
+        }
+        if !self.is_dirty.get() {
+            return Ok(());
+        }
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent).context(format!(
+                "Creating cache directory {}",
+                parent.to_string_lossy()
+            ))?;
+        }
+        let bytes = serde_json::to_vec(&*self.data.borrow())?;
+        std::fs::write(&self.path, bytes)
+            .context(format!("Writing cache {}", self.path.to_string_lossy()))?;
+        self.is_dirty.set(false);
+        Ok(())
+    }
+}
+
+impl DiskCache {
+    /// The hash of the contents of `file`, or `None` if it can't be read. Each file is only read
+    /// once per run, since the source files don't change while the book is being built.
+    fn file_hash(&self, file: &Path) -> Option<String> {
+        self.file_hashes
+            .borrow_mut()
+            .entry(file.to_owned())
+            .or_insert_with(|| std::fs::read(file).ok().map(|bytes| content_hash(&bytes)))
+            .clone()
+    }
+}
+
+/// A hash of `bytes` that is the same in every build and on every platform, unlike the hashers in
+/// the standard library, so that it can be saved to disk. This is 64-bit FNV-1a.
+pub(crate) fn content_hash(bytes: &[u8]) -> String {
+    let hash = bytes.iter().fold(0xcbf2_9ce4_8422_2325_u64, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    });
+    format!("{:016x}", hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DiskCache;
+    use crate::test_support::TempDir;
+    use std::{fs::File, time::SystemTime};
+
+    #[test]
+    fn test_entries_expire_when_files_change() {
+        let dir = TempDir::new("cache-expiry");
+        dir.write("source.rs", "/// A crab.\nstruct Crab;");
+        let source = dir.path().join("source.rs");
+        let cache_path = dir.path().join("cache.json");
+
+        let cache = DiskCache::load(cache_path.clone(), "config".to_string());
+        cache.insert(
+            "doc crab".to_string(),
+            &Some("A crab.".to_string()),
+            vec![source.clone()],
+        );
+        cache.save().unwrap();
+
+        let cache = DiskCache::load(cache_path.clone(), "config".to_string());
+        assert_eq!(
+            cache.get("doc crab"),
+            Some((Some("A crab.".to_string()), vec![source.clone()]))
+        );
+        assert_eq!(cache.get::<Option<String>>("doc shrimp"), None);
+        let other_config = DiskCache::load(cache_path.clone(), "other config".to_string());
+        assert_eq!(other_config.get::<Option<String>>("doc crab"), None);
+
+        // Only the contents of the files matter, not when they were last touched.
+        File::options()
+            .write(true)
+            .open(&source)
+            .unwrap()
+            .set_modified(SystemTime::UNIX_EPOCH)
+            .unwrap();
+        let cache = DiskCache::load(cache_path.clone(), "config".to_string());
+        assert!(cache.get::<Option<String>>("doc crab").is_some());
+        std::fs::write(&source, "/// A blue crab.\nstruct Crab;").unwrap();
+        File::options()
+            .write(true)
+            .open(&source)
+            .unwrap()
+            .set_modified(SystemTime::UNIX_EPOCH)
+            .unwrap();
+        let cache = DiskCache::load(cache_path.clone(), "config".to_string());
+        assert_eq!(cache.get::<Option<String>>("doc crab"), None);
+    }
+
+    #[test]
+    fn test_unused_entries_are_dropped() {
+        let dir = TempDir::new("cache-pruning");
+        dir.write("source.rs", "/// A crab.\nstruct Crab;");
+        let source = dir.path().join("source.rs");
+        let cache_path = dir.path().join("cache.json");
+
+        let cache = DiskCache::load(cache_path.clone(), "config".to_string());
+        cache.insert("chapter old".to_string(), &1, vec![source.clone()]);
+        cache.insert("chapter kept".to_string(), &2, vec![source.clone()]);
+        cache.save().unwrap();
+
+        // A run that only uses one of the entries keeps only that one.
+        let cache = DiskCache::load(cache_path.clone(), "config".to_string());
+        assert_eq!(
+            cache.get::<i32>("chapter kept").map(|(value, _)| value),
+            Some(2)
+        );
+        cache.save().unwrap();
+        let cache = DiskCache::load(cache_path.clone(), "config".to_string());
+        assert_eq!(cache.get::<i32>("chapter old"), None);
+        assert_eq!(
+            cache.get::<i32>("chapter kept").map(|(value, _)| value),
+            Some(2)
+        );
+    }
+}
@@ -0,0 +1,222 @@
+use anyhow::{anyhow, Result};
+use quote::ToTokens;
+use std::{collections::BTreeSet, convert::TryFrom};
+use syn::{Lit, Meta, NestedMeta, Path};
+
+/// The `cfg` options that are considered active when reading source code.
+///
+/// This is used to decide which `#[cfg_attr(...)]` attributes apply. Options are either bare names,
+/// like `unix` or `doc`, or key-value pairs, like `feature = "serde"`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CfgSet {
+    names: BTreeSet<String>,
+    key_values: BTreeSet<(String, String)>,
+}
+
+impl CfgSet {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Mark the bare option `name` as active.
+    pub fn enable(&mut self, name: impl Into<String>) {
+        self.names.insert(name.into());
+    }
+
+    /// Mark the key-value option `key = "value"` as active.
+    pub fn enable_key_value(&mut self, key: impl Into<String>, value: impl Into<String>) {
+        self.key_values.insert((key.into(), value.into()));
+    }
+
+    /// Whether no options are active, as when none are configured.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty() && self.key_values.is_empty()
+    }
+
+    /// Evaluate a `cfg` predicate, such as the first argument of `#[cfg_attr(...)]`.
+    ///
+    /// Predicates that aren't understood are treated as inactive.
+    #[must_use]
+    pub fn is_active(&self, predicate: &Meta) -> bool {
+        match predicate {
+            Meta::Path(path) => path
+                .get_ident()
+                .is_some_and(|ident| self.names.contains(&ident.to_string())),
+            Meta::NameValue(name_value) => match (&name_value.lit, name_value.path.get_ident()) {
+                (Lit::Str(value), Some(key)) => {
+                    self.key_values.contains(&(key.to_string(), value.value()))
+                }
+                _ => false,
+            },
+            Meta::List(list) => {
+                let mut args = list.nested.iter().map(|nested| match nested {
+                    NestedMeta::Meta(meta) => self.is_active(meta),
+                    NestedMeta::Lit(_) => false,
+                });
+                if list.path.is_ident("all") {
+                    args.all(|active| active)
+                } else if list.path.is_ident("any") {
+                    args.any(|active| active)
+                } else if list.path.is_ident("not") && list.nested.len() == 1 {
+                    !args.all(|active| active)
+                } else {
+                    false
+                }
+            }
+        }
+    }
+}
+
+/// Describe a `cfg` predicate in words, the way docs.rs does for `#[doc(cfg(...))]`, like
+/// ``crate feature `serde` and non-`windows` `` for `all(feature = "serde", not(windows))`.
+#[must_use]
+pub fn describe(predicate: &Meta) -> String {
+    describe_nested(predicate, false)
+}
+
+/// Describe `predicate`, wrapping combinations of several predicates in parentheses if it is
+/// `nested` in another combination.
+fn describe_nested(predicate: &Meta, nested: bool) -> String {
+    match predicate {
+        Meta::Path(path) => format!("`{}`", path_to_string(path)),
+        Meta::NameValue(name_value) => match &name_value.lit {
+            Lit::Str(value) if name_value.path.is_ident("feature") => {
+                format!("crate feature `{}`", value.value())
+            }
+            Lit::Str(value) => format!(
+                "`{} = \"{}\"`",
+                path_to_string(&name_value.path),
+                value.value()
+            ),
+            _ => format!("`{}`", predicate.to_token_stream()),
+        },
+        Meta::List(list) => {
+            let args: Vec<String> = list
+                .nested
+                .iter()
+                .map(|nested| match nested {
+                    NestedMeta::Meta(meta) => describe_nested(meta, true),
+                    NestedMeta::Lit(lit) => format!("`{}`", lit.to_token_stream()),
+                })
+                .collect();
+            let separator = if list.path.is_ident("all") {
+                " and "
+            } else if list.path.is_ident("any") {
+                " or "
+            } else if list.path.is_ident("not") && args.len() == 1 {
+                return format!("non-{}", args[0]);
+            } else {
+                return format!("`{}`", predicate.to_token_stream());
+            };
+            let described = args.join(separator);
+            if nested && args.len() > 1 {
+                format!("({})", described)
+            } else {
+                described
+            }
+        }
+    }
+}
+
+fn path_to_string(path: &Path) -> String {
+    path.segments
+        .iter()
+        .map(|segment| segment.ident.to_string())
+        .collect::<Vec<_>>()
+        .join("::")
+}
+
+impl TryFrom<Vec<String>> for CfgSet {
+    type Error = anyhow::Error;
+
+    fn try_from(values: Vec<String>) -> Result<Self, Self::Error> {
+        let mut rv = Self::new();
+        for value in values {
+            if let Some((key, value)) = value.split_once('=') {
+                let value = value.trim();
+                let value = value
+                    .strip_prefix('"')
+                    .and_then(|v| v.strip_suffix('"'))
+                    .unwrap_or(value);
+                rv.enable_key_value(key.trim(), value);
+            } else if value.trim().is_empty() {
+                return Err(anyhow!("Empty cfg options are not allowed"));
+            } else {
+                rv.enable(value.trim());
+            }
+        }
+        Ok(rv)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{describe, CfgSet};
+    use std::convert::TryFrom;
+    use syn::{parse_quote, Meta};
+
+    fn test_cfg() -> CfgSet {
+        CfgSet::try_from(vec!["unix".to_string(), r#"feature = "serde""#.to_string()]).unwrap()
+    }
+
+    #[test]
+    fn test_names() {
+        let active: Meta = parse_quote!(unix);
+        let inactive: Meta = parse_quote!(windows);
+        assert!(test_cfg().is_active(&active));
+        assert!(!test_cfg().is_active(&inactive));
+    }
+
+    #[test]
+    fn test_key_values() {
+        let active: Meta = parse_quote!(feature = "serde");
+        let inactive: Meta = parse_quote!(feature = "json");
+        assert!(test_cfg().is_active(&active));
+        assert!(!test_cfg().is_active(&inactive));
+    }
+
+    #[test]
+    fn test_combinators() {
+        let all: Meta = parse_quote!(all(unix, feature = "serde"));
+        let any: Meta = parse_quote!(any(windows, feature = "serde"));
+        let not: Meta = parse_quote!(not(windows));
+        let nested: Meta = parse_quote!(all(unix, not(any(windows, feature = "json"))));
+        let failing: Meta = parse_quote!(all(unix, windows));
+        assert!(test_cfg().is_active(&all));
+        assert!(test_cfg().is_active(&any));
+        assert!(test_cfg().is_active(&not));
+        assert!(test_cfg().is_active(&nested));
+        assert!(!test_cfg().is_active(&failing));
+    }
+
+    #[test]
+    fn test_is_empty() {
+        assert!(CfgSet::new().is_empty());
+        assert!(!test_cfg().is_empty());
+    }
+
+    #[test]
+    fn test_describe() {
+        let cases: Vec<(Meta, &str)> = vec![
+            (parse_quote!(unix), "`unix`"),
+            (parse_quote!(feature = "serde"), "crate feature `serde`"),
+            (
+                parse_quote!(target_os = "linux"),
+                r#"`target_os = "linux"`"#,
+            ),
+            (
+                parse_quote!(all(feature = "serde", not(windows))),
+                "crate feature `serde` and non-`windows`",
+            ),
+            (
+                parse_quote!(any(all(unix, feature = "serde"), windows)),
+                "(`unix` and crate feature `serde`) or `windows`",
+            ),
+        ];
+        for (predicate, expected) in cases {
+            assert_eq!(describe(&predicate), expected);
+        }
+    }
+}
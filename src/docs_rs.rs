@@ -0,0 +1,158 @@
+//! Docs for crates that aren't available locally, read from the rustdoc JSON that docs.rs builds.
+//!
+//! This needs the `docs-rs` feature, and `curl` and `gzip` on the `PATH`. Nothing is downloaded
+//! unless a crate is listed in [`ResolveOptions::docs_rs`](crate::ResolveOptions::docs_rs).
+
+use anyhow::{anyhow, ensure, Context, Result};
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    process::{Command, Stdio},
+    str::FromStr,
+};
+
+use crate::domain::RustPath;
+
+/// A crate whose docs are read from docs.rs, like `serde=1.0.200` or `anyhow=latest`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DocsRsCrate {
+    /// The name that paths use for the crate.
+    pub name: String,
+    /// The version of the crate to read, or `latest`.
+    pub version: String,
+}
+
+impl FromStr for DocsRsCrate {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (name, version) = s.split_once('=').ok_or_else(|| {
+            anyhow!(
+                "Invalid docs.rs crate `{}`, expected a name and version like `serde=1.0.200`",
+                s
+            )
+        })?;
+        Ok(Self {
+            name: name.trim().to_string(),
+            version: version.trim().to_string(),
+        })
+    }
+}
+
+/// The docs of every item in a crate that has a path of its own, like modules, types, traits, and
+/// functions. Associated items, like methods, don't have their own paths in rustdoc's JSON.
+#[derive(Debug)]
+pub(crate) struct DocsRsIndex {
+    /// The docs of each item, by its path without the crate name, like `de::Deserialize`. The
+    /// crate's root module has an empty path.
+    docs: HashMap<String, String>,
+}
+
+impl DocsRsIndex {
+    /// Download the rustdoc JSON of `krate` from docs.rs.
+    pub(crate) fn fetch(krate: &DocsRsCrate) -> Result<Self> {
+        let url = format!(
+            "https://docs.rs/crate/{}/{}/json.gz",
+            krate.name, krate.version
+        );
+        let json = download_gzip(&url)?;
+        Self::from_json(&json).context(format!("Reading rustdoc JSON from {}", url))
+    }
+
+    fn from_json(json: &str) -> Result<Self> {
+        let json: Value = serde_json::from_str(json)?;
+        let (paths, index) = match (json["paths"].as_object(), json["index"].as_object()) {
+            (Some(paths), Some(index)) => (paths, index),
+            _ => return Err(anyhow!("Missing `paths` or `index`")),
+        };
+        let mut docs = HashMap::new();
+        for (id, summary) in paths {
+            // Other crates' items are in `paths` too, for linking to them.
+            if summary["crate_id"] != 0 {
+                continue;
+            }
+            let segments: Vec<&str> = match summary["path"].as_array() {
+                Some(segments) => segments.iter().filter_map(Value::as_str).collect(),
+                None => continue,
+            };
+            if let Some(item) = index.get(id) {
+                let item_docs = item["docs"].as_str().unwrap_or("").to_string();
+                docs.insert(segments.get(1..).unwrap_or(&[]).join("::"), item_docs);
+            }
+        }
+        Ok(Self { docs })
+    }
+
+    /// The docs of the item at `path`, whose first segment is the crate's name.
+    pub(crate) fn docs(&self, path: &RustPath) -> Option<&str> {
+        let item_path = path
+            .head_tail()
+            .1
+            .map_or(String::new(), |tail| tail.to_string());
+        self.docs.get(&item_path).map(String::as_str)
+    }
+}
+
+/// Download the gzipped file at `url`, and return its decompressed text.
+fn download_gzip(url: &str) -> Result<String> {
+    let mut curl = Command::new("curl")
+        .args(["--silent", "--show-error", "--fail", "--location", url])
+        .stdout(Stdio::piped())
+        .spawn()
+        .context("Running curl")?;
+    let curl_out = curl.stdout.take().expect("Bug: curl's stdout isn't piped");
+    let gzip = Command::new("gzip")
+        .arg("--decompress")
+        .stdin(curl_out)
+        .output()
+        .context("Running gzip")?;
+    ensure!(curl.wait()?.success(), "Downloading {} failed", url);
+    ensure!(gzip.status.success(), "Decompressing {} failed", url);
+    String::from_utf8(gzip.stdout).context(format!("Reading {}", url))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DocsRsCrate, DocsRsIndex};
+    use crate::RustPath;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_from_json() {
+        let json = r#"{
+            "root": "0:0",
+            "index": {
+                "0:0": { "name": "crabs", "docs": "Crabs from docs.rs." },
+                "0:1": { "name": "Claw", "docs": "A claw." },
+                "0:2": { "name": "pinch", "docs": null },
+                "1:5": { "name": "Vec", "docs": "A vec." }
+            },
+            "paths": {
+                "0:0": { "crate_id": 0, "path": ["crabs"], "kind": "module" },
+                "0:1": { "crate_id": 0, "path": ["crabs", "parts", "Claw"], "kind": "struct" },
+                "0:2": { "crate_id": 0, "path": ["crabs", "pinch"], "kind": "function" },
+                "1:5": { "crate_id": 1, "path": ["alloc", "vec", "Vec"], "kind": "struct" }
+            }
+        }"#;
+        let index = DocsRsIndex::from_json(json).unwrap();
+        let docs = |path: &str| index.docs(&RustPath::from_str(path).unwrap());
+        // The crate can be registered under another name than its own.
+        assert_eq!(docs("crustacea"), Some("Crabs from docs.rs."));
+        assert_eq!(docs("crustacea::parts::Claw"), Some("A claw."));
+        assert_eq!(docs("crustacea::pinch"), Some(""));
+        assert_eq!(docs("crustacea::vec::Vec"), None);
+        assert!(DocsRsIndex::from_json("{}").is_err());
+    }
+
+    #[test]
+    fn test_parse_crate() {
+        assert_eq!(
+            DocsRsCrate::from_str("serde = 1.0.200").unwrap(),
+            DocsRsCrate {
+                name: "serde".to_string(),
+                version: "1.0.200".to_string()
+            }
+        );
+        assert!(DocsRsCrate::from_str("serde").is_err());
+    }
+}
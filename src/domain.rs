@@ -1,130 +1,825 @@
-use anyhow::{anyhow, Context, Result};
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use log::warn;
 use serde::Deserialize;
-use std::{collections::HashMap, convert::TryFrom, fmt::Display, path::PathBuf, str::FromStr};
+use std::{
+    collections::HashMap,
+    convert::TryFrom,
+    fmt::Display,
+    path::{Component, Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+};
 
-#[derive(Debug, Clone, PartialEq)]
+/// A path to an item, like `my_crate::some_mod::Crab`.
+///
+/// Generic arguments, like the `<u8>` in `Vec::<u8>::new`, and the trait of a qualified path, like
+/// the `Speak` in `<Crab as Speak>::greet`, are kept aside so that the segments are plain names.
+/// They aren't needed to find an item, and aren't included when the path is displayed.
+///
+/// A path can also end in `#` and the name of a trait, like `my_crate::Crab#Speak`, to refer to
+/// the block that implements that trait for the type, rather than to the type itself. Unlike
+/// the parts above, that changes what the path refers to, so it is displayed.
+///
+/// The segments are shared between a path and the parts of it returned by
+/// [`RustPath::head_tail`] and [`RustPath::parent`], so descending into a path one segment at a
+/// time doesn't copy the rest of it each time.
+#[derive(Debug, Clone)]
 pub struct RustPath {
-    head: String,
-    tail: Option<Vec<String>>,
+    /// The segments of the path that this one was taken from, of which this path is
+    /// `start..end`.
+    segments: Arc<[String]>,
+    start: usize,
+    end: usize,
+    /// The index of each segment that has generic arguments, and the arguments with their `<>`.
+    generics: Vec<(usize, String)>,
+    qualified_trait: Option<String>,
+    /// The trait after a `#` at the end of the path, for a path to a trait impl block.
+    impl_trait: Option<String>,
 }
 
 impl RustPath {
+    /// A path made of `segments`, which must not be empty.
+    fn from_segments(segments: Vec<String>) -> Self {
+        debug_assert!(!segments.is_empty(), "Bug: a RustPath needs a segment");
+        Self {
+            end: segments.len(),
+            segments: segments.into(),
+            start: 0,
+            generics: vec![],
+            qualified_trait: None,
+            impl_trait: None,
+        }
+    }
+
+    fn segment_slice(&self) -> &[String] {
+        &self.segments[self.start..self.end]
+    }
+
+    /// A path with the single segment `head`, usually a crate name, to build on with
+    /// [`push`](Self::push) and [`join`](Self::join).
+    #[must_use]
+    pub fn new(head: impl Into<String>) -> Self {
+        Self::from_segments(vec![head.into()])
+    }
+
+    /// The path with `segment` added to the end, like `my_crate::Crab` for `my_crate` and `Crab`.
+    /// The impl trait of the path, if it has one, is kept.
+    #[must_use]
+    pub fn push(self, segment: impl Into<String>) -> Self {
+        let mut segments = self.segment_slice().to_vec();
+        segments.push(segment.into());
+        Self {
+            generics: self.generics,
+            qualified_trait: self.qualified_trait,
+            impl_trait: self.impl_trait,
+            ..Self::from_segments(segments)
+        }
+    }
+
+    /// The path with every segment of `other` added to the end. The qualified trait of `other`
+    /// isn't kept, since it only makes sense at the start of a path, but its impl trait is.
+    #[must_use]
+    pub fn join(&self, other: &RustPath) -> Self {
+        let offset = self.end - self.start;
+        let mut segments = self.segment_slice().to_vec();
+        segments.extend_from_slice(other.segment_slice());
+        let generics = self
+            .generics
+            .iter()
+            .cloned()
+            .chain(
+                other
+                    .generics
+                    .iter()
+                    .map(|(idx, args)| (idx + offset, args.clone())),
+            )
+            .collect();
+        Self {
+            generics,
+            qualified_trait: self.qualified_trait.clone(),
+            impl_trait: other.impl_trait.clone(),
+            ..Self::from_segments(segments)
+        }
+    }
+
+    /// Each segment of the path, from first to last.
+    pub fn segments(&self) -> impl Iterator<Item = &str> {
+        self.segment_slice().iter().map(String::as_str)
+    }
+
+    /// Split the path into its first segment and the rest. Generic arguments aren't kept in the
+    /// rest of the path.
     #[must_use]
     pub fn head_tail(&self) -> (&str, Option<Self>) {
-        match &self.tail {
-            None => (self.head.as_str(), None),
-            Some(vec) if vec.is_empty() => (self.head.as_str(), None),
-            Some(vec) if vec.len() == 1 => (
-                self.head.as_str(),
-                Some(Self {
-                    head: vec[0].to_string(),
-                    tail: None,
-                }),
-            ),
-            Some(vec) => (
-                self.head.as_str(),
-                Some(Self {
-                    head: vec[0].to_string(),
-                    tail: Some(vec[1..].to_owned()),
-                }),
-            ),
+        let tail = (self.end - self.start > 1).then(|| Self {
+            segments: Arc::clone(&self.segments),
+            start: self.start + 1,
+            end: self.end,
+            generics: vec![],
+            qualified_trait: None,
+            impl_trait: None,
+        });
+        (self.segments[self.start].as_str(), tail)
+    }
+
+    /// The path without its final segment, or `None` if it only has one segment.
+    #[must_use]
+    pub fn parent(&self) -> Option<Self> {
+        let len = self.end - self.start;
+        (len > 1).then(|| Self {
+            segments: Arc::clone(&self.segments),
+            start: self.start,
+            end: self.end - 1,
+            generics: self
+                .generics
+                .iter()
+                .filter(|(idx, _)| *idx < len - 1)
+                .cloned()
+                .collect(),
+            qualified_trait: self.qualified_trait.clone(),
+            impl_trait: None,
+        })
+    }
+
+    /// The path inside of the crate `name`, like `my_crate::net::Stream` for `net::Stream`.
+    #[must_use]
+    pub fn in_crate(&self, name: &str) -> Self {
+        Self {
+            qualified_trait: self.qualified_trait.clone(),
+            ..RustPath::new(name).join(self)
+        }
+    }
+
+    /// The same path, but starting from the crate `head` instead.
+    #[must_use]
+    pub fn with_head(&self, head: &str) -> Self {
+        let mut segments = self.segment_slice().to_vec();
+        segments[0] = head.to_string();
+        Self {
+            generics: self.generics.clone(),
+            qualified_trait: self.qualified_trait.clone(),
+            impl_trait: self.impl_trait.clone(),
+            ..Self::from_segments(segments)
         }
     }
+
+    /// The generic arguments written in the path, as pairs of the segment they were on and the
+    /// arguments, like `("Vec", "<u8>")` for `Vec::<u8>::new`.
+    pub fn generics(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.generics
+            .iter()
+            .map(move |(idx, args)| (self.segment_slice()[*idx].as_str(), args.as_str()))
+    }
+
+    /// The trait of a qualified path, like `Speak` for `<Crab as Speak>::greet`.
+    #[must_use]
+    pub fn qualified_trait(&self) -> Option<&str> {
+        self.qualified_trait.as_deref()
+    }
+
+    /// The trait of a path to a trait impl block, like `Speak` for `Crab#Speak`.
+    #[must_use]
+    pub fn impl_trait(&self) -> Option<&str> {
+        self.impl_trait.as_deref()
+    }
+
+    /// The path to the type of a trait impl block, like `Crab` for `Crab#Speak`, or the same path
+    /// if it isn't to an impl block.
+    #[must_use]
+    pub fn without_impl_trait(&self) -> Self {
+        Self {
+            impl_trait: None,
+            ..self.clone()
+        }
+    }
+
+    /// The final segment of the path, usually the name of the item it refers to.
+    #[must_use]
+    pub fn last(&self) -> &str {
+        &self.segments[self.end - 1]
+    }
+}
+
+impl PartialEq for RustPath {
+    fn eq(&self, other: &Self) -> bool {
+        self.segment_slice() == other.segment_slice()
+            && self.generics == other.generics
+            && self.qualified_trait == other.qualified_trait
+            && self.impl_trait == other.impl_trait
+    }
 }
 
 impl FromStr for RustPath {
     type Err = anyhow::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let parts: Vec<_> = s.split("::").map(str::to_string).collect();
-        match parts.len() {
-            0 => Err(anyhow!("Zero length RustPaths are not allowed")),
-            1 => Ok(Self {
-                head: s.to_owned(),
-                tail: None,
-            }),
-            _ => Ok(Self {
-                head: parts[0].to_string(),
-                tail: Some(parts[1..].to_owned()),
-            }),
+        let s = s.trim();
+        let (s, impl_trait) = match s.split_once('#') {
+            Some((type_path, impl_trait)) => {
+                let impl_trait = impl_trait.trim();
+                ensure!(
+                    !impl_trait.is_empty() && !impl_trait.contains('#'),
+                    "Expected a single trait name after the `#` in path {}",
+                    s
+                );
+                (type_path.trim(), Some(impl_trait.to_string()))
+            }
+            None => (s, None),
+        };
+        // A qualified path like `<Crab as Speak>::greet` refers to `Crab::greet`.
+        let (s, qualified_trait) = match s.strip_prefix('<') {
+            Some(rest) => {
+                let close = find_top_level(rest, ">")
+                    .ok_or_else(|| anyhow!("Unclosed `<` in path {}", s))?;
+                let (self_type, qualified_trait) = match find_top_level(&rest[..close], " as ") {
+                    Some(idx) => (&rest[..idx], Some(rest[idx + 4..close].trim().to_string())),
+                    None => (&rest[..close], None),
+                };
+                (
+                    format!("{}{}", self_type.trim(), &rest[close + 1..]),
+                    qualified_trait,
+                )
+            }
+            None => (s.to_string(), None),
+        };
+
+        let mut parts: Vec<String> = vec![];
+        let mut generics = vec![];
+        for part in split_segments(&s) {
+            let part = part.trim();
+            let (name, args) = match part.find('<') {
+                Some(idx) => (&part[..idx], Some(&part[idx..])),
+                None => (part, None),
+            };
+            if let Some(args) = args {
+                ensure!(args.ends_with('>'), "Unclosed `<` in path {}", s);
+            }
+            match (name, args) {
+                // The arguments of a turbofish, like `Vec::<u8>`, belong to the segment before.
+                ("", Some(args)) if !parts.is_empty() => {
+                    generics.push((parts.len() - 1, args.to_string()));
+                }
+                ("", _) if parts.is_empty() && args.is_some() => {
+                    bail!("Generic arguments without a name in path {}", s)
+                }
+                (name, args) => {
+                    if let Some(args) = args {
+                        generics.push((parts.len(), args.to_string()));
+                    }
+                    parts.push(name.to_string());
+                }
+            }
         }
+
+        ensure!(!parts.is_empty(), "Zero length RustPaths are not allowed");
+        Ok(Self {
+            generics,
+            qualified_trait,
+            impl_trait,
+            ..Self::from_segments(parts)
+        })
     }
 }
 
+/// The byte index of the first `pattern` in `s` that isn't inside of `<>` brackets.
+fn find_top_level(s: &str, pattern: &str) -> Option<usize> {
+    let mut depth = 0_usize;
+    for (idx, c) in s.char_indices() {
+        if depth == 0 && s[idx..].starts_with(pattern) {
+            return Some(idx);
+        }
+        match c {
+            '<' => depth += 1,
+            '>' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Split `s` into the segments of a path on each `::` that isn't inside of `<>` brackets.
+///
+/// Paths copied from other doc tools can use `/` instead, like `my_crate/net/TcpStream`, or `.`,
+/// like `my_crate.net.TcpStream`. A `.` only separates segments that are all names, apart from a
+/// tuple field's index at the end like in `CookedCrab.0`, so that something like `1.5` is left
+/// whole.
+fn split_segments(s: &str) -> Vec<&str> {
+    let mut segments = vec![];
+    for part in split_top_level(s, "::") {
+        for part in split_top_level(part, "/") {
+            let pieces: Vec<_> = split_top_level(part, ".")
+                .into_iter()
+                .map(str::trim)
+                .collect();
+            let (last, names) = pieces.split_last().expect("Bug: split returned nothing");
+            let is_name = |piece: &str| {
+                let name = piece.split('<').next().unwrap_or_default();
+                name.starts_with(|c: char| c.is_alphabetic() || c == '_')
+                    && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+            };
+            let is_index = !last.is_empty() && last.chars().all(|c| c.is_ascii_digit());
+            if !names.is_empty()
+                && names.iter().all(|name| is_name(name))
+                && (is_name(last) || is_index)
+            {
+                segments.extend(pieces);
+            } else {
+                segments.push(part);
+            }
+        }
+    }
+    segments
+}
+
+/// Split `s` on each `separator` that isn't inside of `<>` brackets.
+fn split_top_level<'a>(mut s: &'a str, separator: &str) -> Vec<&'a str> {
+    let mut parts = vec![];
+    while let Some(idx) = find_top_level(s, separator) {
+        parts.push(&s[..idx]);
+        s = &s[idx + separator.len()..];
+    }
+    parts.push(s);
+    parts
+}
+
 impl Display for RustPath {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.head)?;
-        if let Some(tail) = &self.tail {
-            write!(f, "::{}", tail.join("::"))?;
+        write!(f, "{}", self.segment_slice().join("::"))?;
+        if let Some(impl_trait) = &self.impl_trait {
+            write!(f, "#{}", impl_trait)?;
         }
         Ok(())
     }
 }
 
-#[derive(Debug)]
-pub struct CrateRoots(HashMap<String, PathBuf>);
+#[derive(Debug, Clone, Default)]
+pub struct CrateRoots(HashMap<String, CrateRoot>);
 
 impl CrateRoots {
+    /// No crates, for registering them one at a time with [`CrateRoots::insert`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register the crate at `path` under `name`, which is what paths to its items start with,
+    /// without writing it as a `name=path` config entry.
+    ///
+    /// Like in the config, `path` is usually the crate's directory, but can also be the library's
+    /// root file, and the crate's `Cargo.toml` is read to find its root file and edition if it has
+    /// one. Registering the same crate under the same name again does nothing.
+    ///
+    /// # Errors
+    /// If `name` can't start a path, the crate can't be read, or `name` is already used for a
+    /// different crate, a descriptive [`anyhow`] will be returned.
+    pub fn insert(&mut self, name: impl Into<String>, path: impl Into<PathBuf>) -> Result<()> {
+        let (name, path) = (name.into(), path.into());
+        check_crate_name(&name, &path.to_string_lossy())?;
+        let (name, root) = read_crate_root(Some(name), &path)?;
+        self.add(name, root)?;
+        Ok(())
+    }
+
+    /// Add `root` under `name`, returning whether the same crate was already there.
+    fn add(&mut self, name: String, root: CrateRoot) -> Result<bool> {
+        match self.0.get(&name) {
+            Some(existing) if *existing == root => Ok(true),
+            Some(existing) => bail!(
+                "The crate name {} is used for both {} and {}",
+                name,
+                existing.dir.to_string_lossy(),
+                root.dir.to_string_lossy()
+            ),
+            None => {
+                self.0.insert(name, root);
+                Ok(false)
+            }
+        }
+    }
+
+    /// The crate called `key`.
+    ///
+    /// Package names like `my-lib` are written `my_lib` in code, so if no crate has exactly that
+    /// name, dashes and underscores are treated the same, and so are upper and lower case. A
+    /// name that matches more than one crate that way doesn't match any of them.
+    #[must_use]
+    pub fn get(&self, key: &str) -> Option<&CrateRoot> {
+        if let Some(root) = self.0.get(key) {
+            return Some(root);
+        }
+        let key = normalize_crate_name(key);
+        let mut matches = self
+            .0
+            .iter()
+            .filter(|(name, _)| normalize_crate_name(name) == key);
+        match (matches.next(), matches.next()) {
+            (Some((_, root)), None) => Some(root),
+            _ => None,
+        }
+    }
+
+    /// Whether a crate called `name` is registered, matching names like [`CrateRoots::get`].
+    #[must_use]
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    /// The names of the registered crates, sorted, which are what paths start with.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.iter().map(|(name, _)| name)
+    }
+
+    /// The crate whose directory `file` is in, or the innermost one for nested crates.
+    pub(crate) fn containing(&self, file: &Path) -> Option<&CrateRoot> {
+        self.0
+            .values()
+            .filter(|root| file.starts_with(&root.dir))
+            .max_by_key(|root| root.dir.components().count())
+    }
+
+    /// Iterate over the crate names and roots, sorted by name.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&str, &CrateRoot)> {
+        let mut entries: Vec<_> = self.0.iter().map(|(k, v)| (k.as_str(), v)).collect();
+        entries.sort_by_key(|(name, _)| *name);
+        entries.into_iter()
+    }
+}
+
+/// Where the source code of a crate lives.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CrateRoot {
+    dir: PathBuf,
+    lib_file: PathBuf,
+    edition: Option<String>,
+    readme: Option<PathBuf>,
+}
+
+impl CrateRoot {
+    /// The directory of the crate, which usually holds its `Cargo.toml`.
+    #[must_use]
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The root source file of the crate's library target, `src/lib.rs` unless its `Cargo.toml`
+    /// says otherwise.
+    #[must_use]
+    pub fn lib_file(&self) -> &Path {
+        &self.lib_file
+    }
+
+    /// The Rust edition of the crate, like `2021`, from its `Cargo.toml`. A `Cargo.toml` that
+    /// doesn't say is edition 2015, like Cargo treats it. This is `None` for crates without a
+    /// `Cargo.toml`, and for ones that inherit their edition from a workspace.
     #[must_use]
-    pub fn get(&self, key: &str) -> Option<&PathBuf> {
-        self.0.get(key)
+    pub fn edition(&self) -> Option<&str> {
+        self.edition.as_deref()
+    }
+
+    /// The crate's README file, from the `readme` field of its `Cargo.toml`. Like Cargo, a crate
+    /// that doesn't say uses the first of `README.md`, `README.txt`, or `README` that exists in its
+    /// directory. This is `None` for crates with `readme = false`, or without any of those files.
+    #[must_use]
+    pub fn readme(&self) -> Option<&Path> {
+        self.readme.as_deref()
     }
 }
 
 #[derive(Debug, Deserialize)]
 struct CargoToml {
     package: CargoTomlPackage,
+    lib: Option<CargoTomlLib>,
 }
 
 #[derive(Debug, Deserialize)]
 struct CargoTomlPackage {
     name: String,
+    /// Usually a string, but a table like `{ workspace = true }` for an inherited edition.
+    edition: Option<toml::Value>,
+    /// A path, a boolean, or a table like `{ workspace = true }` for an inherited README.
+    readme: Option<toml::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CargoTomlLib {
+    name: Option<String>,
+    path: Option<String>,
+}
+
+impl CargoToml {
+    /// Read the `Cargo.toml` in `crate_dir`.
+    fn read(crate_dir: &Path) -> Result<Self> {
+        let cargo_toml_path = crate_dir.join("Cargo.toml");
+        let cargo_toml_bytes = std::fs::read(&cargo_toml_path).context(format!(
+            "Reading cargo toml at {}",
+            cargo_toml_path.to_string_lossy()
+        ))?;
+        toml::from_slice(&cargo_toml_bytes).context(format!(
+            "Parsing cargo.toml at {}",
+            cargo_toml_path.to_string_lossy()
+        ))
+    }
+
+    /// The name that paths use for the crate, which can't contain `-`.
+    fn crate_name(&self) -> String {
+        self.lib
+            .as_ref()
+            .and_then(|lib| lib.name.clone())
+            .unwrap_or_else(|| self.package.name.replace('-', "_"))
+    }
+
+    fn lib_path(&self) -> Option<&str> {
+        self.lib.as_ref().and_then(|lib| lib.path.as_deref())
+    }
+
+    fn edition(&self) -> Option<String> {
+        match &self.package.edition {
+            Some(toml::Value::String(edition)) => Some(edition.clone()),
+            Some(_) => None,
+            None => Some("2015".to_string()),
+        }
+    }
+}
+
+/// The README of the crate in `dir`, given the `readme` field of its `Cargo.toml`, if it has one.
+///
+/// Inherited READMEs are looked for like unset ones, since the workspace's `Cargo.toml` isn't read.
+fn crate_readme(dir: &Path, cargo_toml: Option<&CargoToml>) -> Option<PathBuf> {
+    match cargo_toml.and_then(|cargo_toml| cargo_toml.package.readme.as_ref()) {
+        Some(toml::Value::String(readme)) => Some(join_portable(dir, readme)),
+        Some(toml::Value::Boolean(false)) => None,
+        Some(toml::Value::Boolean(true)) => Some(dir.join("README.md")),
+        _ => ["README.md", "README.txt", "README"]
+            .iter()
+            .map(|name| dir.join(name))
+            .find(|path| path.is_file()),
+    }
 }
 
 impl TryFrom<Vec<String>> for CrateRoots {
     type Error = anyhow::Error;
 
     fn try_from(values: Vec<String>) -> Result<Self, Self::Error> {
-        let rv = values
-            .iter()
-            .map(|s| {
-                if let Some((name, path)) = s.split_once("=") {
-                    let path: PathBuf = shellexpand::tilde(path).to_string().into();
-                    Ok((name.to_string(), path))
-                } else {
-                    let crate_path: PathBuf = shellexpand::tilde(s).to_string().into();
-                    let cargo_toml_path = crate_path.join("Cargo.toml");
-                    let cargo_toml_bytes = std::fs::read(&cargo_toml_path).context(format!(
-                        "Reading cargo toml at {}",
-                        cargo_toml_path.to_string_lossy()
-                    ))?;
-                    let data: CargoToml = toml::from_slice(&cargo_toml_bytes).context(format!(
-                        "Parsing cargo.toml at {}",
-                        cargo_toml_path.to_string_lossy()
-                    ))?;
-
-                    Ok((data.package.name, crate_path))
-                }
-            })
-            .collect::<Result<HashMap<_, _>>>()?;
+        let mut rv = Self::new();
+        for value in &values {
+            let (name, root) = parse_crate_root(value)?;
+            let dir = root.dir.clone();
+            if rv.add(name.clone(), root)? {
+                warn!(
+                    "The crate {} at {} is listed more than once in the rustdoc config",
+                    name,
+                    dir.to_string_lossy()
+                );
+            }
+        }
 
-        Ok(Self(rv))
+        Ok(rv)
     }
 }
 
+/// Parse a crate config entry, either `name=path` or `path`, into the crate's name and its root.
+/// The path is usually the crate's directory, but can also be the library's root file.
+///
+/// If the crate has a `Cargo.toml`, its `[lib]` table is used to find the library's root file and,
+/// if there is no name in the entry, the crate's name. A name in the entry is an alias that always
+/// wins over the one in `Cargo.toml`, since it is what the book's paths start with.
+fn parse_crate_root(value: &str) -> Result<(String, CrateRoot)> {
+    let (name, path) = match value.split_once('=') {
+        Some((name, path)) => {
+            let name = name.trim();
+            check_crate_name(name, path)?;
+            (Some(name.to_string()), path)
+        }
+        None => (None, value),
+    };
+    let path = join_portable(Path::new(""), &shellexpand::tilde(path));
+    read_crate_root(name, &path)
+}
+
+/// Check that `name`, the name given to the crate at `path`, can start a path.
+fn check_crate_name(name: &str, path: &str) -> Result<()> {
+    ensure!(
+        is_identifier(name),
+        "The crate name `{}` for {} can't start a path, use a name like `{}` instead",
+        name,
+        path,
+        name.replace(|c: char| !c.is_alphanumeric() && c != '_', "_")
+    );
+    Ok(())
+}
+
+/// Read the crate at `path`, its directory or the library's root file, into its name and root.
+/// Without a `name`, the crate's name comes from its `Cargo.toml`.
+fn read_crate_root(name: Option<String>, path: &Path) -> Result<(String, CrateRoot)> {
+    if path.is_file() {
+        return parse_entry_file(name, path);
+    }
+    match &name {
+        Some(name) => ensure!(
+            path.is_dir(),
+            "The directory {} for crate {} does not exist",
+            path.to_string_lossy(),
+            name
+        ),
+        None => ensure!(
+            path.is_dir(),
+            "The crate directory {} does not exist",
+            path.to_string_lossy()
+        ),
+    }
+    let dir = canonical_crate_dir(path)?;
+
+    let cargo_toml = match name {
+        Some(_) if !dir.join("Cargo.toml").is_file() => None,
+        _ => Some(CargoToml::read(&dir)?),
+    };
+    let name = match (name, &cargo_toml) {
+        (Some(name), _) => name,
+        (None, Some(cargo_toml)) => cargo_toml.crate_name(),
+        (None, None) => unreachable!("Bug: Cargo.toml is always read for unnamed crates"),
+    };
+    let lib_file = match cargo_toml.as_ref().and_then(CargoToml::lib_path) {
+        Some(lib_path) => join_portable(&dir, lib_path),
+        None => dir.join("src").join("lib.rs"),
+    };
+    let edition = cargo_toml.as_ref().and_then(CargoToml::edition);
+    let readme = crate_readme(&dir, cargo_toml.as_ref());
+    Ok((
+        name,
+        CrateRoot {
+            dir,
+            lib_file,
+            edition,
+            readme,
+        },
+    ))
+}
+
+/// Make the root of a crate from a config entry that names the library's root file, like
+/// `my_lib=../my-lib/src/lib.rs`, for crates whose `Cargo.toml` doesn't say where it is.
+///
+/// The crate's directory is the closest one above the file with a `Cargo.toml`, or else the one
+/// holding the `src` directory the file is in, or else the file's own directory. With a name in
+/// the entry, a `Cargo.toml` that can't be read is ignored, so the crate just has no edition.
+fn parse_entry_file(name: Option<String>, path: &Path) -> Result<(String, CrateRoot)> {
+    let lib_file = path.canonicalize().context(format!(
+        "Resolving crate root file {}",
+        path.to_string_lossy()
+    ))?;
+    let file_dir = lib_file
+        .parent()
+        .expect("Bug: a canonical file path has a parent");
+    let manifest_dir = file_dir
+        .ancestors()
+        .find(|dir| dir.join("Cargo.toml").is_file());
+    let (name, cargo_toml) = match (name, manifest_dir) {
+        (Some(name), _) => (name, manifest_dir.and_then(|dir| CargoToml::read(dir).ok())),
+        (None, Some(manifest_dir)) => {
+            let cargo_toml = CargoToml::read(manifest_dir)?;
+            (cargo_toml.crate_name(), Some(cargo_toml))
+        }
+        (None, None) => bail!(
+            "There is no Cargo.toml to read the name of the crate at {} from, add a name like \
+             `my_crate={}`",
+            path.to_string_lossy(),
+            path.to_string_lossy()
+        ),
+    };
+    let dir = manifest_dir
+        .or_else(|| {
+            file_dir
+                .ancestors()
+                .find(|dir| dir.file_name().is_some_and(|name| name == "src"))
+                .and_then(Path::parent)
+        })
+        .unwrap_or(file_dir)
+        .to_owned();
+    let edition = cargo_toml.as_ref().and_then(CargoToml::edition);
+    let readme = crate_readme(&dir, cargo_toml.as_ref());
+    Ok((
+        name,
+        CrateRoot {
+            dir,
+            lib_file,
+            edition,
+            readme,
+        },
+    ))
+}
+
+/// The form of a crate name used to match names that only differ by dashes, underscores, or case.
+fn normalize_crate_name(name: &str) -> String {
+    name.replace('-', "_").to_lowercase()
+}
+
+/// Whether `name` can be the first segment of a path, like a crate name.
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_')
+        && name != "_"
+}
+
+/// The URL of the online docs of `path`, an item in a crate that isn't read locally.
+///
+/// Without the crate's source there's no way to know what sort of item `path` is, which rustdoc's
+/// URLs depend on, so the URL searches the crate's docs for it. Generic arguments and the trait of
+/// an impl block aren't searched for, since rustdoc's search only knows the items' names.
+pub(crate) fn docs_url(path: &RustPath) -> String {
+    let (crate_name, item_path) = path.head_tail();
+    let base = match crate_name {
+        "std" | "core" | "alloc" | "proc_macro" | "test" => {
+            format!("https://doc.rust-lang.org/{}/", crate_name)
+        }
+        _ => format!("https://docs.rs/{0}/latest/{0}/", crate_name),
+    };
+    match item_path {
+        Some(item_path) => {
+            let query = item_path.segments().collect::<Vec<_>>().join("::");
+            format!("{}?search={}", base, percent_encode(&query))
+        }
+        None => base,
+    }
+}
+
+/// Percent-encode `text` for use in a URL's query, leaving alone the characters that don't need
+/// it, like the `:`s in a path.
+fn percent_encode(text: &str) -> String {
+    let mut encoded = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~:".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+/// Join `relative`, a path from a config file or source code, onto `base`.
+///
+/// Both `/` and `\` are treated as separators, so that paths written on one platform work on the
+/// others, and `..` removes the last part of `base` instead of being added to it. Windows doesn't
+/// resolve `..` or `/` itself in the `\\?\` paths that [`Path::canonicalize`] returns there.
+pub(crate) fn join_portable(base: &Path, relative: &str) -> PathBuf {
+    let relative = if cfg!(windows) {
+        relative.to_string()
+    } else {
+        relative.replace('\\', "/")
+    };
+    let mut rv = base.to_owned();
+    for component in Path::new(&relative).components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir
+                if matches!(rv.components().next_back(), Some(Component::Normal(_))) =>
+            {
+                rv.pop();
+            }
+            component => rv.push(component),
+        }
+    }
+    rv
+}
+
+/// Resolve `..` and symlinks in the crate directory `path`, and check that it looks like a crate.
+fn canonical_crate_dir(path: &Path) -> Result<PathBuf> {
+    let canonical = path.canonicalize().context(format!(
+        "Resolving crate directory {}",
+        path.to_string_lossy()
+    ))?;
+    ensure!(
+        canonical.join("src").is_dir() || canonical.join("Cargo.toml").is_file(),
+        "The directory {} doesn't look like a crate, it has no src directory or Cargo.toml",
+        path.to_string_lossy()
+    );
+    Ok(canonical)
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::RustPath;
-    use std::str::FromStr;
+    use super::{docs_url, join_portable};
+    use crate::{test_support::TempDir, CrateRoot, CrateRoots, RustPath};
+    use std::{convert::TryFrom, path::Path, str::FromStr};
+
+    fn crate_roots(values: &[&str]) -> anyhow::Result<CrateRoots> {
+        let manifest_dir = env!("CARGO_MANIFEST_DIR");
+        let values: Vec<String> = values
+            .iter()
+            .map(|value| value.replace("$DIR", manifest_dir))
+            .collect();
+        CrateRoots::try_from(values)
+    }
+
+    fn segments(names: &[&str]) -> Vec<String> {
+        names.iter().map(ToString::to_string).collect()
+    }
 
     #[test]
     fn test_single() {
         assert_eq!(
             RustPath::from_str("one").unwrap(),
-            RustPath {
-                head: "one".to_string(),
-                tail: None
-            }
+            RustPath::from_segments(segments(&["one"]))
         );
     }
 
@@ -132,10 +827,7 @@ mod tests {
     fn test_double() {
         assert_eq!(
             RustPath::from_str("one::two").unwrap(),
-            RustPath {
-                head: "one".to_string(),
-                tail: Some(vec!["two".to_string()])
-            }
+            RustPath::from_segments(segments(&["one", "two"]))
         );
     }
 
@@ -143,10 +835,7 @@ mod tests {
     fn test_triple() {
         assert_eq!(
             RustPath::from_str("one::two::three").unwrap(),
-            RustPath {
-                head: "one".to_string(),
-                tail: Some(vec!["two".to_string(), "three".to_string()])
-            }
+            RustPath::from_segments(segments(&["one", "two", "three"]))
         );
     }
 
@@ -154,10 +843,7 @@ mod tests {
     fn test_tuple_indexes() {
         assert_eq!(
             RustPath::from_str("a_tuple::0").unwrap(),
-            RustPath {
-                head: "a_tuple".to_string(),
-                tail: Some(vec!["0".to_string()])
-            }
+            RustPath::from_segments(segments(&["a_tuple", "0"]))
         );
     }
 
@@ -165,14 +851,443 @@ mod tests {
     fn test_failure_1() {
         assert_eq!(
             RustPath::from_str("test_crate::crustaceans::CookedCrab::0").unwrap(),
-            RustPath {
-                head: "test_crate".to_string(),
-                tail: Some(vec![
-                    "crustaceans".to_string(),
-                    "CookedCrab".to_string(),
-                    "0".to_string(),
-                ])
-            }
+            RustPath::from_segments(segments(&["test_crate", "crustaceans", "CookedCrab", "0"]))
+        );
+    }
+
+    #[test]
+    fn test_builder_single() {
+        assert_eq!(RustPath::new("one"), RustPath::from_str("one").unwrap());
+    }
+
+    #[test]
+    fn test_builder_double() {
+        assert_eq!(
+            RustPath::new("one").push("two"),
+            RustPath::from_str("one::two").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_builder_triple() {
+        assert_eq!(
+            RustPath::new("one").push("two").push("three"),
+            RustPath::from_str("one::two::three").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_builder_tuple_indexes() {
+        assert_eq!(
+            RustPath::new("a_tuple").push("0"),
+            RustPath::from_str("a_tuple::0").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_builder_failure_1() {
+        assert_eq!(
+            RustPath::new("test_crate")
+                .push("crustaceans")
+                .push("CookedCrab")
+                .push("0"),
+            RustPath::from_str("test_crate::crustaceans::CookedCrab::0").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_builder_keeps_impl_trait() {
+        let pushed = RustPath::from_str("my_crate::Burrow#Speak")
+            .unwrap()
+            .push("greet");
+        assert_eq!(pushed.impl_trait(), Some("Speak"));
+        assert_eq!(
+            pushed,
+            RustPath::from_str("my_crate::Burrow::greet#Speak").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_join() {
+        let module = RustPath::from_str("test_crate::crustaceans").unwrap();
+        let item = RustPath::from_str("Vec::<u8>::new").unwrap();
+        let joined = module.join(&item);
+        assert_eq!(
+            joined,
+            RustPath::from_str("test_crate::crustaceans::Vec::<u8>::new").unwrap()
+        );
+        assert_eq!(joined.generics().collect::<Vec<_>>(), vec![("Vec", "<u8>")]);
+        // Joining doesn't change either path.
+        assert_eq!(module.to_string(), "test_crate::crustaceans");
+        assert_eq!(item.to_string(), "Vec::new");
+    }
+
+    #[test]
+    fn test_segments() {
+        let path = RustPath::from_str("test_crate::Crab::new").unwrap();
+        assert_eq!(
+            path.segments().collect::<Vec<_>>(),
+            vec!["test_crate", "Crab", "new"]
+        );
+        let (_, tail) = path.head_tail();
+        assert_eq!(
+            tail.unwrap().segments().collect::<Vec<_>>(),
+            vec!["Crab", "new"]
+        );
+    }
+
+    #[test]
+    fn test_crate_roots_are_canonical() {
+        let roots = crate_roots(&["test_crate=$DIR/test-book/../test-crate"]).unwrap();
+        let expected = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("test-crate")
+            .canonicalize()
+            .unwrap();
+        assert_eq!(
+            roots.get("test_crate").map(CrateRoot::dir),
+            Some(expected.as_path())
+        );
+    }
+
+    #[test]
+    fn test_crate_roots_with_backslashes() {
+        let roots = crate_roots(&["test_crate=$DIR\\test-book\\..\\test-crate"]).unwrap();
+        let expected = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("test-crate")
+            .canonicalize()
+            .unwrap();
+        assert_eq!(
+            roots.get("test_crate").map(CrateRoot::dir),
+            Some(expected.as_path())
+        );
+    }
+
+    #[test]
+    fn test_docs_url() {
+        let url = |path: &str| docs_url(&RustPath::from_str(path).unwrap());
+        assert_eq!(url("serde"), "https://docs.rs/serde/latest/serde/");
+        assert_eq!(
+            url("std::collections::HashMap<K, V>::get"),
+            "https://doc.rust-lang.org/std/?search=collections::HashMap::get"
+        );
+        assert_eq!(
+            url("serde::de::Deserializer#From<u8>"),
+            "https://docs.rs/serde/latest/serde/?search=de::Deserializer"
+        );
+        assert_eq!(
+            url("krabbe::Größe"),
+            "https://docs.rs/krabbe/latest/krabbe/?search=Gr%C3%B6%C3%9Fe"
+        );
+    }
+
+    #[test]
+    fn test_join_portable() {
+        let base = Path::new("/books/crate");
+        assert_eq!(
+            join_portable(base, "src\\net\\mod.rs"),
+            Path::new("/books/crate/src/net/mod.rs")
+        );
+        assert_eq!(
+            join_portable(base, "src/net/mod.rs"),
+            Path::new("/books/crate/src/net/mod.rs")
+        );
+        assert_eq!(
+            join_portable(base, "..\\README.md"),
+            Path::new("/books/README.md")
+        );
+        assert_eq!(
+            join_portable(base, "./src\\.\\lib.rs"),
+            base.join("src/lib.rs")
+        );
+        assert_eq!(
+            join_portable(Path::new(""), "..\\..\\crate"),
+            Path::new("../../crate")
+        );
+    }
+
+    #[test]
+    fn test_crate_roots_aliases() {
+        let roots = crate_roots(&["crabs=$DIR/test-crate"]).unwrap();
+        assert!(roots.get("test_crate").is_none());
+        assert!(roots.get("crabs").unwrap().dir().ends_with("test-crate"));
+
+        let err = crate_roots(&["test-crate=$DIR/test-crate"])
+            .unwrap_err()
+            .to_string();
+        assert!(err.starts_with("The crate name `test-crate`"));
+        assert!(err.ends_with("use a name like `test_crate` instead"));
+    }
+
+    #[test]
+    fn test_crate_roots_match_dashes_and_case() {
+        // The package is called `test-crate`, and paths call it `test_crate`.
+        let roots = crate_roots(&["$DIR/test-crate"]).unwrap();
+        let dir = roots.get("test_crate").unwrap().dir();
+        assert_eq!(roots.get("test-crate").unwrap().dir(), dir);
+        assert_eq!(roots.get("Test_Crate").unwrap().dir(), dir);
+        assert!(roots.contains("test-crate"));
+        assert!(roots.get("test__crate").is_none());
+
+        // Exact names win, and names that only match loosely can't pick between crates.
+        let roots = crate_roots(&[
+            "my_lib=$DIR/test-crate",
+            "My_Lib=$DIR/test-crate-custom-lib",
+        ])
+        .unwrap();
+        assert!(roots.get("my_lib").unwrap().dir().ends_with("test-crate"));
+        assert!(roots
+            .get("My_Lib")
+            .unwrap()
+            .dir()
+            .ends_with("test-crate-custom-lib"));
+        assert!(roots.get("my-lib").is_none());
+    }
+
+    #[test]
+    fn test_crate_roots_duplicates() {
+        let roots = crate_roots(&["$DIR/test-crate", "test_crate=$DIR/test-crate/"]).unwrap();
+        assert_eq!(roots.iter().count(), 1);
+
+        let err = crate_roots(&["test_crate=$DIR/test-crate", "test_crate=$DIR/test-book"])
+            .unwrap_err()
+            .to_string();
+        assert!(err.starts_with("The crate name test_crate is used for both"));
+    }
+
+    #[test]
+    fn test_crate_roots_must_look_like_crates() {
+        let err = crate_roots(&["test_crate=$DIR/test-crate/src"])
+            .unwrap_err()
+            .to_string();
+        assert!(err.ends_with("doesn't look like a crate, it has no src directory or Cargo.toml"));
+    }
+
+    #[test]
+    fn test_crate_roots_read_lib_target() {
+        let roots = crate_roots(&["$DIR/test-crate", "$DIR/test-crate-custom-lib"]).unwrap();
+        let names: Vec<_> = roots.names().collect();
+        assert_eq!(names, vec!["custom_lib", "test_crate"]);
+        assert!(roots.contains("custom_lib"));
+        // With a `[lib] name`, the package name isn't a name for the crate.
+        assert!(!roots.contains("test-crate-custom-lib"));
+
+        let custom_lib = roots.get("custom_lib").unwrap();
+        assert_eq!(
+            custom_lib.lib_file(),
+            custom_lib.dir().join("src/custom.rs")
+        );
+        let test_crate = roots.get("test_crate").unwrap();
+        assert_eq!(test_crate.lib_file(), test_crate.dir().join("src/lib.rs"));
+        assert_eq!(test_crate.edition(), Some("2018"));
+    }
+
+    #[test]
+    fn test_crate_roots_entry_files() {
+        // A named entry file is used in place of the `[lib] path` of its `Cargo.toml`.
+        let roots = crate_roots(&["claws=$DIR/test-crate-custom-lib/src/custom.rs"]).unwrap();
+        let claws = roots.get("claws").unwrap();
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("test-crate-custom-lib")
+            .canonicalize()
+            .unwrap();
+        assert_eq!(claws.dir(), dir);
+        assert_eq!(claws.lib_file(), dir.join("src/custom.rs"));
+
+        // Without a name, the name still comes from the `Cargo.toml`.
+        let roots = crate_roots(&["$DIR/test-crate/src/lib.rs"]).unwrap();
+        let test_crate = roots.get("test_crate").unwrap();
+        assert_eq!(test_crate.lib_file(), test_crate.dir().join("src/lib.rs"));
+        assert_eq!(test_crate.edition(), Some("2018"));
+
+        // Crates without a `Cargo.toml` live in the directory holding `src`.
+        let crate_dir = TempDir::new("entry");
+        crate_dir.write("src/bin/tool.rs", "pub struct Tool;");
+        let entry = crate_dir
+            .path()
+            .join("src/bin/tool.rs")
+            .to_string_lossy()
+            .to_string();
+        let roots = CrateRoots::try_from(vec![format!("tool={}", entry)]).unwrap();
+        let tool = roots.get("tool").unwrap();
+        assert_eq!(tool.dir(), crate_dir.path().canonicalize().unwrap());
+        assert_eq!(tool.edition(), None);
+        let err = CrateRoots::try_from(vec![entry]).unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("There is no Cargo.toml to read the name of the crate at "));
+    }
+
+    #[test]
+    fn test_insert_crate_roots() {
+        let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let mut roots = CrateRoots::new();
+        assert_eq!(roots.names().count(), 0);
+        roots
+            .insert("crabs", manifest_dir.join("test-crate"))
+            .unwrap();
+        roots
+            .insert(
+                "claws",
+                manifest_dir.join("test-crate-custom-lib/src/custom.rs"),
+            )
+            .unwrap();
+        assert_eq!(roots.names().collect::<Vec<_>>(), ["claws", "crabs"]);
+        let crabs = roots.get("crabs").unwrap();
+        assert_eq!(crabs.lib_file(), crabs.dir().join("src/lib.rs"));
+        assert_eq!(crabs.edition(), Some("2018"));
+
+        // The same checks apply as for config entries.
+        roots
+            .insert("crabs", manifest_dir.join("test-crate"))
+            .unwrap();
+        let err = roots
+            .insert("crabs", manifest_dir.join("test-crate-custom-lib"))
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("The crate name crabs is used for both "));
+        let err = roots
+            .insert("my-crabs", manifest_dir.join("test-crate"))
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("The crate name `my-crabs` for "));
+    }
+
+    #[test]
+    fn test_generic_arguments() {
+        let turbofish = RustPath::from_str("std::vec::Vec::<u8>::new").unwrap();
+        assert_eq!(turbofish.to_string(), "std::vec::Vec::new");
+        assert_eq!(
+            turbofish.generics().collect::<Vec<_>>(),
+            vec![("Vec", "<u8>")]
+        );
+
+        let generic = RustPath::from_str("std::collections::HashMap<K, V>::get").unwrap();
+        assert_eq!(generic.to_string(), "std::collections::HashMap::get");
+        assert_eq!(
+            generic.generics().collect::<Vec<_>>(),
+            vec![("HashMap", "<K, V>")]
         );
+        assert_eq!(
+            generic.parent().unwrap().generics().collect::<Vec<_>>(),
+            vec![("HashMap", "<K, V>")]
+        );
+
+        let nested = RustPath::from_str("my_crate::Wrapper::<Vec<u8>>::unwrap").unwrap();
+        assert_eq!(nested.to_string(), "my_crate::Wrapper::unwrap");
+        assert!(RustPath::from_str("my_crate::Wrapper::<u8::new").is_err());
+    }
+
+    #[test]
+    fn test_other_separators() {
+        let canonical = RustPath::from_str("my_crate::net::TcpStream").unwrap();
+        assert_eq!(
+            RustPath::from_str("my_crate/net/TcpStream").unwrap(),
+            canonical
+        );
+        assert_eq!(
+            RustPath::from_str("my_crate.net.TcpStream").unwrap(),
+            canonical
+        );
+        assert_eq!(
+            RustPath::from_str("my_crate/net.TcpStream").unwrap(),
+            canonical
+        );
+        assert_eq!(
+            RustPath::from_str("my_crate.net.TcpStream")
+                .unwrap()
+                .to_string(),
+            "my_crate::net::TcpStream"
+        );
+
+        // A tuple field's index can follow a `.`, like it does in Rust.
+        assert_eq!(
+            RustPath::from_str("test_crate.CookedCrab.0").unwrap(),
+            RustPath::from_segments(segments(&["test_crate", "CookedCrab", "0"]))
+        );
+        assert_eq!(
+            RustPath::from_str("test_crate/CookedCrab/0").unwrap(),
+            RustPath::from_segments(segments(&["test_crate", "CookedCrab", "0"]))
+        );
+        // Anything else with a `.` is left whole.
+        assert_eq!(RustPath::from_str("my_crate::1.5").unwrap().last(), "1.5");
+        // Separators inside of generic arguments are left alone.
+        let generic = RustPath::from_str("my_crate/Wrapper<a.b>/new").unwrap();
+        assert_eq!(generic.to_string(), "my_crate::Wrapper::new");
+        assert_eq!(
+            generic.generics().collect::<Vec<_>>(),
+            vec![("Wrapper", "<a.b>")]
+        );
+    }
+
+    #[test]
+    fn test_qualified_paths() {
+        let path = RustPath::from_str("<my_crate::Crab as my_crate::Speak>::greet").unwrap();
+        assert_eq!(path.to_string(), "my_crate::Crab::greet");
+        assert_eq!(path.qualified_trait(), Some("my_crate::Speak"));
+
+        let path = RustPath::from_str("<my_crate::Crab<u8>>::new").unwrap();
+        assert_eq!(path.to_string(), "my_crate::Crab::new");
+        assert_eq!(path.qualified_trait(), None);
+    }
+
+    #[test]
+    fn test_impl_trait_paths() {
+        let path = RustPath::from_str("my_crate::Crab#std::fmt::Display").unwrap();
+        assert_eq!(path.to_string(), "my_crate::Crab#std::fmt::Display");
+        assert_eq!(path.last(), "Crab");
+        assert_eq!(path.impl_trait(), Some("std::fmt::Display"));
+        assert_ne!(path, RustPath::from_str("my_crate::Crab").unwrap());
+        assert_eq!(
+            path.without_impl_trait(),
+            RustPath::from_str("my_crate::Crab").unwrap()
+        );
+        assert_eq!(
+            path.with_head("other_crate").to_string(),
+            "other_crate::Crab#std::fmt::Display"
+        );
+
+        assert!(RustPath::from_str("my_crate::Crab#").is_err());
+        assert!(RustPath::from_str("my_crate::Crab#Speak#Eat").is_err());
+    }
+
+    #[test]
+    fn test_with_head() {
+        let path = RustPath::from_str("my_lib::Foo::bar").unwrap();
+        assert_eq!(
+            path.with_head("my_lib_v2"),
+            RustPath::from_str("my_lib_v2::Foo::bar").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_head_tail() {
+        let path = RustPath::from_str("one::two::three").unwrap();
+        let (head, tail) = path.head_tail();
+        assert_eq!(head, "one");
+        let tail = tail.unwrap();
+        assert_eq!(tail, RustPath::from_str("two::three").unwrap());
+        assert_eq!(tail.to_string(), "two::three");
+        assert_eq!(tail.last(), "three");
+
+        let (head, tail) = tail.head_tail();
+        assert_eq!(head, "two");
+        let last = tail.unwrap();
+        assert_eq!(last.head_tail(), ("three", None));
+
+        let generic = RustPath::from_str("my_crate::Vec::<u8>::new").unwrap();
+        let tail = generic.head_tail().1.unwrap();
+        assert_eq!(tail.generics().count(), 0);
+        assert_eq!(tail.parent(), Some(RustPath::from_str("Vec").unwrap()));
+    }
+
+    #[test]
+    fn test_parent() {
+        let path = RustPath::from_str("one::two::three").unwrap();
+        let parent = path.parent().unwrap();
+        assert_eq!(parent, RustPath::from_str("one::two").unwrap());
+        assert_eq!(parent.parent(), Some(RustPath::from_str("one").unwrap()));
+        assert_eq!(RustPath::from_str("one").unwrap().parent(), None);
     }
 }
@@ -0,0 +1,220 @@
+use std::{fmt, io, path::PathBuf};
+
+/// The newest edition whose syntax can all be parsed.
+const LATEST_READABLE_EDITION: &str = "2021";
+
+/// Why looking up an item failed, for callers that need to tell the reasons apart, like a link
+/// checker that reports misconfigured crates differently from missing items.
+///
+/// [`find_doc_for_item`](crate::find_doc_for_item) and [`find_item_info`](crate::find_item_info)
+/// return these directly. The methods of [`Resolver`](crate::Resolver) return [`anyhow::Error`]s
+/// with more context about where the lookup was when it failed, which hold one of these as their
+/// cause when there is one, so it can be found with [`anyhow::Error::downcast_ref`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ResolveError {
+    /// The path starts with the name of a crate that isn't configured. `suggestions` holds the
+    /// paths of items in the configured crates with that name, in case the crate name was left
+    /// off of the path.
+    CrateNotFound {
+        name: String,
+        suggestions: Vec<String>,
+    },
+    /// A source file that the lookup needed couldn't be read, usually because it doesn't exist.
+    FileNotFound { path: PathBuf, source: io::Error },
+    /// A source file that the lookup needed isn't valid Rust, or uses syntax that can't be read
+    /// yet. `edition` is the edition of the crate the file is in, if it is known.
+    ParseError {
+        path: PathBuf,
+        source: syn::Error,
+        edition: Option<String>,
+    },
+    /// The crate exists, but it has no item at `path`.
+    ItemNotFound { path: String },
+    /// The item at `path` is inside the module `module`, which is left out under the configured
+    /// `cfg` options since it needs `cfg(condition)`.
+    UnavailableUnderCfg {
+        path: String,
+        module: String,
+        condition: String,
+    },
+    /// The path goes through a kind of item that can't be read yet, like a `macro` 2.0 macro.
+    UnsupportedItemKind { kind: String },
+    /// The lookup would have parsed more than
+    /// [`ResolveOptions::max_scan_files`](crate::ResolveOptions::max_scan_files) source files,
+    /// and stopped before parsing the one at `path`.
+    ScanLimitReached { limit: usize, path: PathBuf },
+    /// Anything else, like an invalid directive argument or a broken cache.
+    Other(anyhow::Error),
+}
+
+impl fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::CrateNotFound { name, suggestions } if suggestions.is_empty() => {
+                write!(f, "Crate {} not found", name)
+            }
+            Self::CrateNotFound { name, suggestions } => {
+                let suggestions: Vec<_> = suggestions.iter().map(|s| format!("`{}`", s)).collect();
+                write!(
+                    f,
+                    "Crate {} not found. Paths must start with a crate name, did you mean {}?",
+                    name,
+                    suggestions.join(" or ")
+                )
+            }
+            Self::FileNotFound { path, source } => {
+                write!(f, "Couldn't read {}: {}", path.to_string_lossy(), source)
+            }
+            Self::ParseError {
+                path,
+                source,
+                edition,
+            } => {
+                let start = source.span().start();
+                write!(
+                    f,
+                    "Couldn't parse {} at line {}, column {}: {}",
+                    path.to_string_lossy(),
+                    start.line,
+                    start.column,
+                    source
+                )?;
+                // Newer editions add syntax, like `gen` blocks, that the parser doesn't know.
+                match edition {
+                    Some(edition) if edition.as_str() > LATEST_READABLE_EDITION => write!(
+                        f,
+                        ". The crate uses edition {}, which may have syntax that can't be read yet",
+                        edition
+                    ),
+                    _ => Ok(()),
+                }
+            }
+            Self::ItemNotFound { path } => write!(f, "Item {} not found", path),
+            Self::UnavailableUnderCfg {
+                path,
+                module,
+                condition,
+            } => write!(
+                f,
+                "{} is not available under the current cfg, since module {} needs `cfg({})`",
+                path, module, condition
+            ),
+            Self::UnsupportedItemKind { kind } => {
+                write!(f, "Items of the kind {} aren't supported yet", kind)
+            }
+            Self::ScanLimitReached { limit, path } => write!(
+                f,
+                "Reached the scan limit of {} source files for one lookup before reading {}; \
+                 narrow the path or raise `max_scan_files`",
+                limit,
+                path.to_string_lossy()
+            ),
+            // The alternate form includes the causes, like the preprocessor's own error messages.
+            Self::Other(err) => write!(f, "{:#}", err),
+        }
+    }
+}
+
+impl std::error::Error for ResolveError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::FileNotFound { source, .. } => Some(source),
+            Self::ParseError { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<anyhow::Error> for ResolveError {
+    /// Take the `ResolveError` that caused `err` out of it, or wrap `err` as
+    /// [`ResolveError::Other`] if it wasn't caused by one.
+    fn from(err: anyhow::Error) -> Self {
+        match err.downcast::<ResolveError>() {
+            Ok(err) => err,
+            Err(err) => Self::Other(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ResolveError;
+    use crate::{find_doc_for_item, test_support::TempDir, CrateRoots, Resolver, RustPath};
+    use std::{convert::TryFrom, str::FromStr};
+
+    fn find_error(crates: &CrateRoots, path: &str) -> ResolveError {
+        find_doc_for_item(&RustPath::from_str(path).unwrap(), crates).unwrap_err()
+    }
+
+    #[test]
+    fn test_error_kinds() {
+        let test_crate = format!("test_crate={}/test-crate", env!("CARGO_MANIFEST_DIR"));
+        let crates = CrateRoots::try_from(vec![test_crate]).unwrap();
+        match find_error(&crates, "CookedCrab") {
+            ResolveError::CrateNotFound { name, suggestions } => {
+                assert_eq!(name, "CookedCrab");
+                assert_eq!(suggestions, ["test_crate::crustaceans::CookedCrab"]);
+            }
+            other => panic!("Unexpected error {:?}", other),
+        }
+        assert!(matches!(
+            find_error(&crates, "test_crate::Lobster"),
+            ResolveError::ItemNotFound { path } if path == "test_crate::Lobster"
+        ));
+
+        // The resolver's own errors hold the same reasons.
+        let resolver = Resolver::new(crates);
+        let err = resolver
+            .resolve(&RustPath::from_str("Shrimp").unwrap())
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<ResolveError>(),
+            Some(ResolveError::CrateNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn test_file_errors() {
+        let crate_dir = TempDir::new("errors");
+        crate_dir.write("src/lib.rs", "mod bad;\nmod missing;");
+        crate_dir.write("src/bad.rs", "pub struct Bad {");
+        let src = crate_dir.path().join("src");
+        let root = format!("broken={}", crate_dir.path().to_string_lossy());
+        let crates = CrateRoots::try_from(vec![root]).unwrap();
+
+        match find_error(&crates, "broken::bad::Bad") {
+            ResolveError::ParseError { path, .. } => assert_eq!(path, src.join("bad.rs")),
+            other => panic!("Unexpected error {:?}", other),
+        }
+        let err = find_error(&crates, "broken::missing::Gone");
+        assert!(
+            matches!(&err, ResolveError::FileNotFound { path, .. } if *path == src.join("missing.rs"))
+        );
+        assert!(err.to_string().starts_with(&format!(
+            "Couldn't read {}: ",
+            src.join("missing.rs").to_string_lossy()
+        )));
+    }
+
+    #[test]
+    fn test_parse_errors_name_new_editions() {
+        let crate_dir = TempDir::new("edition");
+        crate_dir.write(
+            "Cargo.toml",
+            "[package]\nname = \"future\"\nversion = \"0.1.0\"\nedition = \"2024\"",
+        );
+        crate_dir.write("src/lib.rs", "pub struct Bad {");
+        let crates =
+            CrateRoots::try_from(vec![crate_dir.path().to_string_lossy().to_string()]).unwrap();
+
+        let err = find_error(&crates, "future::Bad");
+        assert!(matches!(
+            &err,
+            ResolveError::ParseError { edition: Some(edition), .. } if edition == "2024"
+        ));
+        assert!(err.to_string().ends_with(
+            ". The crate uses edition 2024, which may have syntax that can't be read yet"
+        ));
+    }
+}
@@ -0,0 +1,1776 @@
+use anyhow::{anyhow, bail, ensure, Result};
+use lazy_static::lazy_static;
+use log::warn;
+use pulldown_cmark::{CodeBlockKind, CowStr, Event, LinkType, Options, Parser, Tag};
+use regex::{Captures, Regex};
+use std::{
+    collections::HashMap,
+    ops::{Range, RangeInclusive},
+    str::FromStr,
+};
+
+use crate::{
+    cache::content_hash,
+    domain::{docs_url, join_portable, RustPath},
+    resolver::{MethodOrder, Resolver},
+};
+
+lazy_static! {
+    static ref DIRECTIVE_REGEX: Regex = Regex::new(
+        r#"\{\{\s*#(rustdoc|rustdoc-section|rustdoc-const|rustdoc-source|rustdoc-methods|rustdoc-impls|rustdoc-summary|rustdoc-readme)\s+((?:<[^{}]*?>|[\w:<>,&'#/.])+(?:::\*)?)((?:\s+[^\s}]+)*)\s*\}\}"#
+    )
+    .unwrap();
+    /// The start of a directive that hasn't been closed yet, at the end of some text.
+    static ref OPEN_DIRECTIVE_REGEX: Regex = Regex::new(r#"\{\{\s*#rustdoc[^}]*$"#).unwrap();
+    /// Anything that looks like it was meant to be a directive, like `{{ #rust_doc`, followed by
+    /// the first word after it.
+    static ref LOOSE_DIRECTIVE_REGEX: Regex =
+        Regex::new(r#"(?i)\{\{\s*#(rust[\w-]*)\s*([^\s}]*)"#).unwrap();
+}
+
+/// Options that change how directives are rendered into a chapter.
+#[derive(Debug, Clone)]
+pub struct ExpandOptions {
+    /// The heading level used by `{{ #rustdoc-section ... }}` directives that don't specify one.
+    pub section_level: u32,
+    /// The names of doc sections, like `Safety` for a `# Safety` heading, to leave out of
+    /// embedded docs.
+    pub drop_sections: Vec<String>,
+    /// If not empty, only these doc sections are kept in embedded docs, along with the text before
+    /// the first heading.
+    pub only_sections: Vec<String>,
+    /// A URL template for a link to the source of each embedded item, with `{file}` and `{line}`
+    /// placeholders. `{file}` is relative to the crate's directory. No links are added if this is
+    /// `None`.
+    pub source_link_base: Option<String>,
+    /// Raw Markdown or HTML, like `<div class="rustdoc">`, put before the docs of each directive
+    /// that is a paragraph on its own.
+    pub wrap_prefix: Option<String>,
+    /// Raw Markdown or HTML, like `</div>`, put after the docs of each directive that is a
+    /// paragraph on its own.
+    pub wrap_suffix: Option<String>,
+    /// Crates that aren't read locally, like `std`. Directives for their items are rendered as
+    /// links to the crate's online docs instead of failing.
+    pub external_crates: Vec<String>,
+    /// The order that `{{ #rustdoc-methods ... }}` directives that don't specify one list
+    /// methods in.
+    pub method_order: MethodOrder,
+    /// Whether to mark the runnable Rust examples in embedded docs as `rust` code blocks, so that
+    /// mdbook's playground integration gives them a run button.
+    pub playground_examples: bool,
+    /// A URL or path that relative links and images in embedded docs, like
+    /// `![](../assets/diagram.png)`, are rewritten to start from. Their targets are taken to be
+    /// relative to the file the docs are in, and are made relative to the crate's directory
+    /// before being added to this. If this is `None`, they are left as they are, with a warning.
+    pub link_base: Option<String>,
+    /// Whether to warn about text that looks like a misspelled directive, found with
+    /// [`find_directive_typos`].
+    pub typo_warnings: bool,
+}
+
+impl ExpandOptions {
+    /// Describes every option, to tell chapters expanded with different options apart in the disk
+    /// cache. Unlike the `Debug` output, this only changes when the options do.
+    fn fingerprint(&self) -> String {
+        let method_order = match self.method_order {
+            MethodOrder::Source => "source",
+            MethodOrder::Alphabetical => "alphabetical",
+        };
+        format!(
+            "section_level {} drop_sections {:?} only_sections {:?} source_link_base {:?} \
+             wrap_prefix {:?} wrap_suffix {:?} external_crates {:?} method_order {} \
+             playground_examples {} link_base {:?} typo_warnings {}",
+            self.section_level,
+            self.drop_sections,
+            self.only_sections,
+            self.source_link_base,
+            self.wrap_prefix,
+            self.wrap_suffix,
+            self.external_crates,
+            method_order,
+            self.playground_examples,
+            self.link_base,
+            self.typo_warnings
+        )
+    }
+}
+
+impl Default for ExpandOptions {
+    fn default() -> Self {
+        Self {
+            section_level: 3,
+            drop_sections: vec![],
+            only_sections: vec![],
+            source_link_base: None,
+            wrap_prefix: None,
+            wrap_suffix: None,
+            external_crates: vec![],
+            method_order: MethodOrder::default(),
+            playground_examples: false,
+            link_base: None,
+            typo_warnings: true,
+        }
+    }
+}
+
+/// Replace the `{{ #rustdoc ... }}` directives in the Markdown text `content` with the docs that
+/// they refer to.
+///
+/// Docs are parsed as Markdown and spliced into the surrounding document. A directive that is a
+/// paragraph on its own is replaced by all of the blocks of the docs, while a directive in the
+/// middle of some text is replaced inline, so long as the docs are a single paragraph.
+///
+/// Only the directives written in `content` are expanded. Directives inside of the embedded docs
+/// are left as they are, so docs that refer to each other can't expand forever.
+///
+/// With [`Resolver::enable_disk_cache`], the expanded chapter is saved and reused until its text,
+/// `options`, or one of the source files its directives read changes.
+///
+/// # Errors
+/// If any directive can't be resolved, a descriptive [`anyhow`] will be returned.
+pub fn expand_chapter(
+    resolver: &Resolver,
+    options: &ExpandOptions,
+    content: &str,
+) -> Result<String> {
+    // A chapter renders the same as long as its text, the options, and the files its directives
+    // read stay the same, so it can be reused from the resolver's disk cache.
+    let key = format!(
+        "chapter {}",
+        content_hash(format!("{}\n{}", options.fingerprint(), content).as_bytes())
+    );
+    resolver.cached_chapter(key, || expand_uncached(resolver, options, content))
+}
+
+fn expand_uncached(resolver: &Resolver, options: &ExpandOptions, content: &str) -> Result<String> {
+    let events = merge_text_events(Parser::new_ext(content, Options::all()));
+    let mut expanded: Vec<Event> = Vec::with_capacity(events.len());
+
+    let mut idx = 0;
+    while idx < events.len() {
+        if let [Event::Start(Tag::Paragraph), Event::Text(text), Event::End(Tag::Paragraph), ..] =
+            &events[idx..]
+        {
+            if let Some(captures) = whole_text_directive(text) {
+                let directive = Directive::from_captures(&captures)?;
+                let doc_events = render_directive(resolver, options, &directive)?;
+                expanded.extend(wrap_block(doc_events, options));
+                idx += 3;
+                continue;
+            }
+        }
+
+        match &events[idx] {
+            Event::Text(text) => expand_inline(resolver, options, text, &mut expanded)?,
+            ev => expanded.push(ev.clone()),
+        }
+        idx += 1;
+    }
+
+    let mut new_content = String::with_capacity(content.len());
+    pulldown_cmark_to_cmark::cmark(expanded.iter(), &mut new_content, None)?;
+    Ok(new_content)
+}
+
+/// Whether the Markdown text `content` has any directives in it.
+#[must_use]
+pub fn has_directives(content: &str) -> bool {
+    DIRECTIVE_REGEX.is_match(content)
+}
+
+/// A directive in a chapter that couldn't be resolved, found by [`check_chapter`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct DirectiveProblem {
+    /// The path written in the directive.
+    pub path: String,
+    /// The 1-based line of the chapter that the directive starts on.
+    pub line: usize,
+    /// The 1-based column, in characters, that the directive starts at.
+    pub column: usize,
+    /// What went wrong when resolving the directive.
+    pub message: String,
+}
+
+/// Try to resolve every directive in the Markdown text `content`, without rendering the chapter,
+/// and return a problem for each one that fails or that refers to an item that doesn't exist.
+///
+/// Directives are found the same way as when the chapter is expanded, so ones in inline code,
+/// which are left as they are, aren't checked.
+#[must_use]
+pub fn check_chapter(
+    resolver: &Resolver,
+    options: &ExpandOptions,
+    content: &str,
+) -> Vec<DirectiveProblem> {
+    let mut problems = vec![];
+    let events = merge_text_spans(Parser::new_ext(content, Options::all()).into_offset_iter());
+    for (event, range) in &events {
+        let text = match event {
+            Event::Text(text) => text,
+            _ => continue,
+        };
+        for captures in DIRECTIVE_REGEX.captures_iter(text) {
+            let whole = captures.get(0).expect("Bug: no match for directive regex");
+            let message = match Directive::from_captures(&captures)
+                .and_then(|directive| try_render_directive(resolver, options, &directive))
+            {
+                Ok(Some(_)) => continue,
+                Ok(None) => "No item found".to_string(),
+                Err(err) => format!("{:#}", err),
+            };
+            // Text can differ from its source, like when it has escapes, so the directive is
+            // looked for in the source to say where it is.
+            let offset = content[range.clone()]
+                .find(whole.as_str())
+                .map_or(range.start, |idx| range.start + idx);
+            let (line, column) = line_column(content, offset);
+            problems.push(DirectiveProblem {
+                path: captures[2].to_string(),
+                line,
+                column,
+                message,
+            });
+        }
+    }
+    problems
+}
+
+/// Find the text in `content` that looks like it was meant to be a directive but isn't one, like
+/// `{{ #rustdocs my_crate::Crab }}`, which is left in the chapter as it is. mdbook's own
+/// `{{ #rustdoc_include ... }}` isn't a typo.
+#[must_use]
+pub fn find_directive_typos(content: &str) -> Vec<DirectiveProblem> {
+    let mut typos = vec![];
+    for captures in LOOSE_DIRECTIVE_REGEX.captures_iter(content) {
+        let whole = captures.get(0).expect("Bug: no match for directive regex");
+        let is_directive = DIRECTIVE_REGEX
+            .find_at(content, whole.start())
+            .is_some_and(|directive| directive.start() == whole.start());
+        let name = &captures[1];
+        if is_directive || name == "rustdoc_include" {
+            continue;
+        }
+        let message = if DirectiveKind::ALL
+            .iter()
+            .any(|kind| &kind.name()[1..] == name)
+        {
+            format!(
+                "This `#{}` directive can't be read, so it was left as it is. Directives look \
+                 like `{{{{ #{} my_crate::Item }}}}`",
+                name, name
+            )
+        } else {
+            format!(
+                "`#{}` isn't a rustdoc directive, so it was left as it is. Did you mean `{}`?",
+                name,
+                suggest_directive_name(name).name()
+            )
+        };
+        let (line, column) = line_column(content, whole.start());
+        typos.push(DirectiveProblem {
+            path: captures[2].to_string(),
+            line,
+            column,
+            message,
+        });
+    }
+    typos
+}
+
+/// The kind of directive that the misspelled name `name` most likely meant, falling back to
+/// `#rustdoc`.
+fn suggest_directive_name(name: &str) -> DirectiveKind {
+    let normalized = name
+        .to_lowercase()
+        .replace('_', "-")
+        .replace("rust-doc", "rustdoc");
+    // Plurals are the most common slip, like `#rustdocs` or `#rustdoc-method`.
+    let candidates = [
+        normalized.clone(),
+        normalized.trim_end_matches('s').to_string(),
+        format!("{}s", normalized),
+    ];
+    DirectiveKind::ALL
+        .iter()
+        .copied()
+        .find(|kind| candidates.iter().any(|c| *c == kind.name()[1..]))
+        .unwrap_or(DirectiveKind::Doc)
+}
+
+/// The 1-based line and column, in characters, of the byte `offset` in `content`.
+fn line_column(content: &str, offset: usize) -> (usize, usize) {
+    let before = &content[..offset];
+    let line_start = before.rfind('\n').map_or(0, |idx| idx + 1);
+    (
+        before.matches('\n').count() + 1,
+        before[line_start..].chars().count() + 1,
+    )
+}
+
+/// The kinds of directive that can be used in a chapter.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DirectiveKind {
+    /// `{{ #rustdoc path }}` embeds the docs of an item, or of a module's items for `path::*`.
+    Doc,
+    /// `{{ #rustdoc-section path level=N }}` embeds the docs of an item under a heading with its
+    /// name.
+    Section,
+    /// `{{ #rustdoc-const path }}` embeds the docs of a `const` or `static` followed by its value.
+    Const,
+    /// `{{ #rustdoc-source path lines=A:B }}` embeds the source code of an item, or some of its
+    /// lines.
+    Source,
+    /// `{{ #rustdoc-methods path order=source }}` embeds the docs of every public method of a
+    /// type, each under a heading with its name.
+    Methods,
+    /// `{{ #rustdoc-impls path }}` lists the inherent impls of a type and the traits it
+    /// implements.
+    Impls,
+    /// `{{ #rustdoc-summary path }}` embeds the first paragraph of an item's docs, which fits in
+    /// the middle of a sentence.
+    Summary,
+    /// `{{ #rustdoc-readme my_crate }}` embeds the README of a crate.
+    Readme,
+}
+
+impl DirectiveKind {
+    const ALL: [Self; 8] = [
+        Self::Doc,
+        Self::Section,
+        Self::Const,
+        Self::Source,
+        Self::Methods,
+        Self::Impls,
+        Self::Summary,
+        Self::Readme,
+    ];
+
+    /// The name the directive is written with, like `#rustdoc-section`.
+    fn name(self) -> &'static str {
+        match self {
+            Self::Doc => "#rustdoc",
+            Self::Section => "#rustdoc-section",
+            Self::Const => "#rustdoc-const",
+            Self::Source => "#rustdoc-source",
+            Self::Methods => "#rustdoc-methods",
+            Self::Impls => "#rustdoc-impls",
+            Self::Summary => "#rustdoc-summary",
+            Self::Readme => "#rustdoc-readme",
+        }
+    }
+
+    /// The `key=value` arguments that this kind of directive takes.
+    fn arg_keys(self) -> &'static [&'static str] {
+        match self {
+            Self::Doc => &["crate", "heading", "attr"],
+            Self::Const => &["crate", "heading"],
+            Self::Section => &["crate", "heading", "level"],
+            Self::Source => &["crate", "lines"],
+            Self::Methods => &["crate", "order"],
+            Self::Impls | Self::Summary | Self::Readme => &["crate"],
+        }
+    }
+}
+
+/// A directive found in a chapter.
+#[derive(Debug)]
+struct Directive<'a> {
+    kind: DirectiveKind,
+    path: &'a str,
+    /// The `key=value` arguments written after the path, which are checked to be ones that the
+    /// directive's kind takes.
+    args: HashMap<&'a str, &'a str>,
+}
+
+impl<'a> Directive<'a> {
+    fn from_captures(captures: &Captures<'a>) -> Result<Self> {
+        let kind = match captures.get(1).map(|m| m.as_str()) {
+            Some("rustdoc") => DirectiveKind::Doc,
+            Some("rustdoc-section") => DirectiveKind::Section,
+            Some("rustdoc-const") => DirectiveKind::Const,
+            Some("rustdoc-source") => DirectiveKind::Source,
+            Some("rustdoc-methods") => DirectiveKind::Methods,
+            Some("rustdoc-impls") => DirectiveKind::Impls,
+            Some("rustdoc-summary") => DirectiveKind::Summary,
+            Some("rustdoc-readme") => DirectiveKind::Readme,
+            other => bail!("Bug: unexpected directive {:?}", other),
+        };
+        let path = captures
+            .get(2)
+            .expect("Bug: capture group not in directive regex")
+            .as_str();
+        let args = parse_args(kind, path, captures.get(3).map_or("", |m| m.as_str()))?;
+        Ok(Self { kind, path, args })
+    }
+
+    fn arg(&self, key: &str) -> Option<&'a str> {
+        self.args.get(key).copied()
+    }
+}
+
+/// Parse the space separated `key=value` arguments of a directive of `kind` for `path`.
+///
+/// Each key can only be given once, and must be one that `kind` takes, so that typos don't go
+/// unnoticed.
+fn parse_args<'a>(
+    kind: DirectiveKind,
+    path: &str,
+    text: &'a str,
+) -> Result<HashMap<&'a str, &'a str>> {
+    let mut args = HashMap::new();
+    for arg in text.split_whitespace() {
+        let (key, value) = match arg.split_once('=') {
+            Some((key, value)) if !key.is_empty() && !value.is_empty() => (key, value),
+            _ => bail!(
+                "Expected an argument like `key=value` in the {} directive for {}, found `{}`",
+                kind.name(),
+                path,
+                arg
+            ),
+        };
+        let keys = kind.arg_keys();
+        ensure!(
+            keys.contains(&key),
+            "Unknown argument `{}` in the {} directive for {}, expected one of {}",
+            key,
+            kind.name(),
+            path,
+            keys.iter()
+                .map(|key| format!("`{}`", key))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        ensure!(
+            args.insert(key, value).is_none(),
+            "The argument `{}` is given more than once in the {} directive for {}",
+            key,
+            kind.name(),
+            path
+        );
+    }
+    Ok(args)
+}
+
+/// If all of `text` is a single directive, return its captures.
+fn whole_text_directive(text: &str) -> Option<Captures<'_>> {
+    let text = text.trim();
+    DIRECTIVE_REGEX
+        .captures(text)
+        .filter(|captures| captures.get(0).map(|m| m.as_str().len()) == Some(text.len()))
+}
+
+/// Expand the directives in the middle of `text`, pushing the resulting inline events.
+fn expand_inline<'a>(
+    resolver: &Resolver,
+    options: &ExpandOptions,
+    text: &'a str,
+    out: &mut Vec<Event<'a>>,
+) -> Result<()> {
+    let mut last_end = 0;
+    for captures in DIRECTIVE_REGEX.captures_iter(text) {
+        let whole = captures.get(0).expect("Bug: no match for directive regex");
+        if whole.start() > last_end {
+            out.push(Event::Text(text[last_end..whole.start()].into()));
+        }
+        last_end = whole.end();
+
+        let directive = Directive::from_captures(&captures)?;
+        let doc_events = render_directive(resolver, options, &directive)?;
+        match doc_events.as_slice() {
+            [Event::Start(Tag::Paragraph), inner @ .., Event::End(Tag::Paragraph)]
+                if !inner.contains(&Event::End(Tag::Paragraph)) =>
+            {
+                out.extend(inner.iter().cloned());
+            }
+            // Block content can't be placed in the middle of a paragraph, so leave it as text.
+            _ => {
+                let mut doc = String::new();
+                pulldown_cmark_to_cmark::cmark(doc_events.iter(), &mut doc, None)?;
+                out.push(Event::Text(doc.into()));
+            }
+        }
+    }
+    if last_end < text.len() {
+        out.push(Event::Text(text[last_end..].into()));
+    }
+    Ok(())
+}
+
+/// Put the configured prefix and suffix around the events of a block directive.
+///
+/// They are raw blocks of their own, separated from the docs by blank lines, so that Markdown in
+/// the docs is still rendered inside of HTML tags like `<div>`.
+fn wrap_block(events: Vec<Event<'static>>, options: &ExpandOptions) -> Vec<Event<'static>> {
+    let raw_block = |text: &str| Event::Html(format!("{}\n", text).into());
+    let mut wrapped = Vec::with_capacity(events.len() + 2);
+    wrapped.extend(options.wrap_prefix.as_deref().map(raw_block));
+    wrapped.extend(events);
+    wrapped.extend(options.wrap_suffix.as_deref().map(raw_block));
+    wrapped
+}
+
+/// Look up the docs that a directive refers to, and render them as Markdown events. If there is
+/// no item at the directive's path, a placeholder is rendered instead. Either way, the directive
+/// is counted in the resolver's [`directive_counts`](Resolver::directive_counts).
+fn render_directive(
+    resolver: &Resolver,
+    options: &ExpandOptions,
+    directive: &Directive,
+) -> Result<Vec<Event<'static>>> {
+    let events = try_render_directive(resolver, options, directive)?;
+    resolver.record_directive(events.is_some());
+    let events = events.unwrap_or_else(|| {
+        let path = directive.path.trim_end_matches("::*");
+        parse_markdown(&format!("<< No documentation found for {} >>", path))
+    });
+    Ok(events)
+}
+
+/// Look up the docs that a directive refers to, and render them as Markdown events, or return
+/// `None` if there is no item at the directive's path.
+fn try_render_directive(
+    resolver: &Resolver,
+    options: &ExpandOptions,
+    directive: &Directive,
+) -> Result<Option<Vec<Event<'static>>>> {
+    let (path_str, is_glob) = match directive.path.strip_suffix("::*") {
+        Some(module_path) => (module_path, true),
+        None => (directive.path, false),
+    };
+    let mut item_path = RustPath::from_str(path_str)?;
+    // The same paths can be read from another version of a crate, registered under its own name.
+    if let Some(crate_name) = directive.arg("crate") {
+        item_path = item_path.with_head(crate_name);
+    }
+    let crate_name = item_path.head_tail().0;
+    if !resolver.knows_crate(crate_name) && options.external_crates.iter().any(|c| c == crate_name)
+    {
+        if directive.kind == DirectiveKind::Source {
+            bail!(
+                "{} is in the external crate {}, so its source can't be shown",
+                item_path,
+                crate_name
+            );
+        }
+        return Ok(Some(external_link(&item_path)));
+    }
+    if is_glob && directive.kind != DirectiveKind::Doc {
+        bail!(
+            "Only `#rustdoc` directives can list a module, found {}",
+            directive.path
+        );
+    }
+    if directive.kind == DirectiveKind::Readme {
+        ensure!(
+            item_path.head_tail().1.is_none(),
+            "`#rustdoc-readme` takes the name of a crate, not an item like {}",
+            item_path
+        );
+        let readme = resolver.resolve_readme(crate_name)?;
+        return Ok(readme.map(|readme| parse_markdown(&readme)));
+    }
+    if directive.kind == DirectiveKind::Source {
+        let lines = directive
+            .arg("lines")
+            .map(|lines| parse_line_range(lines, &item_path))
+            .transpose()?;
+        let source = resolver.resolve_source(&item_path, lines)?;
+        return Ok(source.map(|source| {
+            let fence = Tag::CodeBlock(CodeBlockKind::Fenced("rust".into()));
+            vec![
+                Event::Start(fence.clone()),
+                Event::Text(format!("{}\n", source).into()),
+                Event::End(fence),
+            ]
+        }));
+    }
+    let is_listing = is_glob
+        || matches!(
+            directive.kind,
+            DirectiveKind::Methods | DirectiveKind::Impls
+        );
+    let doc = if let Some(attr) = directive.arg("attr") {
+        ensure!(
+            is_glob,
+            "`attr={}` only applies to module listings like `{}::*`",
+            attr,
+            item_path
+        );
+        resolver.resolve_marked_module_items(&item_path, attr)?
+    } else if is_glob {
+        resolver.resolve_module_items(&item_path)?
+    } else if directive.kind == DirectiveKind::Methods {
+        let order = match directive.arg("order") {
+            Some(order) => order.parse()?,
+            None => options.method_order,
+        };
+        resolver.resolve_methods(&item_path, order)?
+    } else if directive.kind == DirectiveKind::Impls {
+        resolver.resolve_impls(&item_path)?
+    } else {
+        resolver.resolve(&item_path)?
+    };
+    let doc = match doc {
+        Some(doc) => doc,
+        None => return Ok(None),
+    };
+    let doc_events = parse_markdown(&doc);
+    let doc_events = match directive.arg("heading") {
+        None | Some("true") => doc_events,
+        Some("false") if is_listing => bail!(
+            "`heading=false` only applies to the docs of a single item, not the listing of {}",
+            item_path
+        ),
+        Some("false") => strip_leading_heading(doc_events),
+        Some(other) => bail!(
+            "Invalid value `{}` for `heading` on {}, expected `true` or `false`",
+            other,
+            item_path
+        ),
+    };
+    // Listings are made of the headings of each item, so sections aren't filtered there.
+    let doc_events = if is_listing {
+        doc_events
+    } else {
+        filter_sections(doc_events, options)
+    };
+    let doc_events = if options.playground_examples {
+        playground_examples(doc_events)
+    } else {
+        doc_events
+    };
+    let doc_events = relative_links(resolver, options, &item_path, doc_events)?;
+
+    let events = match directive.kind {
+        DirectiveKind::Doc | DirectiveKind::Methods | DirectiveKind::Impls => doc_events,
+        DirectiveKind::Const => {
+            let mut events = doc_events;
+            if let Some(value) = resolver.resolve_const_value(&item_path)? {
+                events.extend(vec![
+                    Event::Start(Tag::Paragraph),
+                    Event::Code(value.into()),
+                    Event::End(Tag::Paragraph),
+                ]);
+            }
+            events
+        }
+        DirectiveKind::Source | DirectiveKind::Readme => {
+            unreachable!("Bug: source and README directives are rendered above")
+        }
+        DirectiveKind::Summary => match first_paragraph(doc_events) {
+            Some(events) => events,
+            None => bail!(
+                "The docs of {} have no paragraph to summarize them",
+                item_path
+            ),
+        },
+        DirectiveKind::Section => {
+            let level = match directive.arg("level") {
+                Some(level) => match level.parse() {
+                    Ok(level @ 1..=6) => level,
+                    _ => bail!(
+                        "Invalid heading level `{}` for {}, expected 1 to 6",
+                        level,
+                        item_path
+                    ),
+                },
+                None => options.section_level,
+            };
+            let mut events = vec![
+                Event::Start(Tag::Heading(level)),
+                Event::Text(item_path.last().to_string().into()),
+                Event::End(Tag::Heading(level)),
+            ];
+            events.extend(offset_headings(doc_events, level));
+            events
+        }
+    };
+    let mut events = events;
+    // A link after a summary would be a paragraph of its own, so it couldn't be inlined.
+    let is_summary = directive.kind == DirectiveKind::Summary;
+    if let (Some(link_base), false) = (&options.source_link_base, is_listing || is_summary) {
+        if let Some(location) = resolver.resolve_location(&item_path)? {
+            let file = location.file.to_string_lossy().replace('\\', "/");
+            let url = link_base
+                .replace("{file}", &file)
+                .replace("{line}", &location.line.to_string());
+            let link = Tag::Link(LinkType::Inline, url.into(), "".into());
+            events.extend(vec![
+                Event::Start(Tag::Paragraph),
+                Event::Start(link.clone()),
+                Event::Text("→ source".into()),
+                Event::End(link),
+                Event::End(Tag::Paragraph),
+            ]);
+        }
+    }
+    Ok(Some(events))
+}
+
+/// A paragraph with a link to the online docs of `path`, which is in an external crate.
+fn external_link(path: &RustPath) -> Vec<Event<'static>> {
+    let link = Tag::Link(LinkType::Inline, docs_url(path).into(), "".into());
+    vec![
+        Event::Start(Tag::Paragraph),
+        Event::Start(link.clone()),
+        Event::Code(path.to_string().into()),
+        Event::End(link),
+        Event::End(Tag::Paragraph),
+    ]
+}
+
+/// Parse the `lines` argument of a source directive for `path`, like `10:25`. Either end can be
+/// left out to start at the beginning of the item or run to its end.
+fn parse_line_range(lines: &str, path: &RustPath) -> Result<RangeInclusive<usize>> {
+    let invalid = || {
+        anyhow!(
+            "Invalid line range `{}` for {}, expected something like `10:25`",
+            lines,
+            path
+        )
+    };
+    let (first, last) = lines.split_once(':').ok_or_else(invalid)?;
+    let parse = |line: &str, default| match line {
+        "" => Ok(default),
+        line => line
+            .parse::<usize>()
+            .ok()
+            .filter(|&line| line > 0)
+            .ok_or_else(invalid),
+    };
+    let range = parse(first, 1)?..=parse(last, usize::MAX)?;
+    ensure!(!range.is_empty(), invalid());
+    Ok(range)
+}
+
+/// A section of docs that [`filter_sections`] is inside of.
+struct OpenSection {
+    level: u32,
+    keep: bool,
+    /// Whether this section is named in [`ExpandOptions::only_sections`].
+    is_only: bool,
+}
+
+/// Remove the sections of `events` that `options` says to drop, or that aren't in its list of
+/// sections to keep. A section runs from its heading to the next heading of the same or a higher
+/// level, so subsections are dropped or kept along with the section they are in.
+fn filter_sections(events: Vec<Event<'static>>, options: &ExpandOptions) -> Vec<Event<'static>> {
+    if options.drop_sections.is_empty() && options.only_sections.is_empty() {
+        return events;
+    }
+    let is_named = |names: &[String], title: &str| {
+        names
+            .iter()
+            .any(|name| name.trim().eq_ignore_ascii_case(title.trim()))
+    };
+
+    let mut open_sections: Vec<OpenSection> = vec![];
+    let mut filtered = Vec::with_capacity(events.len());
+    for (idx, ev) in events.iter().enumerate() {
+        if let Event::Start(Tag::Heading(level)) = ev {
+            let title = heading_text(&events[idx + 1..]);
+            while open_sections.last().is_some_and(|s| s.level >= *level) {
+                open_sections.pop();
+            }
+            let parent_keep = open_sections.last().map_or(true, |s| s.keep);
+            let is_only = is_named(&options.only_sections, &title);
+            let in_only = options.only_sections.is_empty()
+                || is_only
+                || open_sections.iter().any(|s| s.is_only);
+            open_sections.push(OpenSection {
+                level: *level,
+                keep: parent_keep && in_only && !is_named(&options.drop_sections, &title),
+                is_only,
+            });
+        }
+        if open_sections.last().map_or(true, |s| s.keep) {
+            filtered.push(ev.clone());
+        }
+    }
+    filtered
+}
+
+/// The attributes that rustdoc understands on the code blocks of doc examples, like `should_panic`
+/// in ```` ```rust,should_panic ````.
+const RUSTDOC_CODE_ATTRIBUTES: &[&str] = &[
+    "rust",
+    "should_panic",
+    "no_run",
+    "ignore",
+    "compile_fail",
+    "test_harness",
+    "edition2015",
+    "edition2018",
+    "edition2021",
+    "edition2024",
+];
+
+/// Rewrite the targets of the relative links and images in the docs of `path` to start from
+/// [`ExpandOptions::link_base`], since they won't be found from the book's chapters.
+///
+/// Absolute paths, URLs with a scheme like `https:`, links to anchors, and rustdoc's links to
+/// other items, like `crate::Crab`, are left alone.
+fn relative_links(
+    resolver: &Resolver,
+    options: &ExpandOptions,
+    path: &RustPath,
+    events: Vec<Event<'static>>,
+) -> Result<Vec<Event<'static>>> {
+    if !events.iter().any(|ev| match ev {
+        Event::Start(Tag::Link(_, url, _)) | Event::Start(Tag::Image(_, url, _)) => {
+            is_relative_link(url)
+        }
+        _ => false,
+    }) {
+        return Ok(events);
+    }
+    let location = match &options.link_base {
+        Some(_) => resolver.resolve_location(path)?,
+        None => None,
+    };
+    let rewrite = |url: CowStr<'static>| -> CowStr<'static> {
+        if !is_relative_link(&url) {
+            return url;
+        }
+        match (&options.link_base, &location) {
+            (Some(link_base), Some(location)) => {
+                let file_dir = location.file.parent().unwrap_or(&location.file);
+                let target = join_portable(file_dir, &url)
+                    .to_string_lossy()
+                    .replace('\\', "/");
+                format!("{}/{}", link_base.trim_end_matches('/'), target).into()
+            }
+            (link_base, _) => {
+                warn!(
+                    "The docs of {} link to the relative path {}, which won't be found from the \
+                     book{}",
+                    path,
+                    url,
+                    if link_base.is_none() {
+                        ". Set `link_base` to rewrite it"
+                    } else {
+                        ""
+                    }
+                );
+                url
+            }
+        }
+    };
+    // Each link is rewritten once, at its start, and its end is given the same target. Images
+    // can be inside links, so the targets of the open ones are kept in a stack.
+    let mut open = vec![];
+    Ok(events
+        .into_iter()
+        .map(|ev| match ev {
+            Event::Start(Tag::Link(link_type, url, title)) => {
+                let url = rewrite(url);
+                open.push(url.clone());
+                Event::Start(Tag::Link(link_type, url, title))
+            }
+            Event::Start(Tag::Image(link_type, url, title)) => {
+                let url = rewrite(url);
+                open.push(url.clone());
+                Event::Start(Tag::Image(link_type, url, title))
+            }
+            Event::End(Tag::Link(link_type, url, title)) => {
+                Event::End(Tag::Link(link_type, open.pop().unwrap_or(url), title))
+            }
+            Event::End(Tag::Image(link_type, url, title)) => {
+                Event::End(Tag::Image(link_type, open.pop().unwrap_or(url), title))
+            }
+            ev => ev,
+        })
+        .collect())
+}
+
+/// Whether the link target `url` is a path relative to the file its docs are in.
+fn is_relative_link(url: &str) -> bool {
+    let has_scheme = url.split_once(':').is_some_and(|(scheme, _)| {
+        scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+    });
+    // Without a `/` or `.`, a target like `Crab` is more likely one of rustdoc's item links.
+    let is_file = url.contains('/') || url.contains('.');
+    !url.is_empty() && !url.starts_with(['/', '#']) && !url.contains("::") && !has_scheme && is_file
+}
+
+/// Turn the runnable Rust examples in `events` into code blocks that mdbook runs in its
+/// playground.
+///
+/// Like rustdoc, code blocks without a language, or with only rustdoc's attributes, are Rust.
+/// Their info string is made to start with `rust`, which is what mdbook looks for. Examples marked
+/// `ignore`, `no_run`, or `compile_fail` aren't meant to be run, so they are left as they are, as
+/// are code blocks in other languages.
+fn playground_examples(events: Vec<Event<'static>>) -> Vec<Event<'static>> {
+    let playground_kind = |kind: &CodeBlockKind| -> Option<CodeBlockKind<'static>> {
+        let info = match kind {
+            CodeBlockKind::Indented => "",
+            CodeBlockKind::Fenced(info) => info,
+        };
+        let attrs: Vec<&str> = info
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|attr| !attr.is_empty())
+            .collect();
+        let is_runnable_rust = attrs
+            .iter()
+            .all(|attr| RUSTDOC_CODE_ATTRIBUTES.contains(attr))
+            && !attrs
+                .iter()
+                .any(|attr| ["ignore", "no_run", "compile_fail"].contains(attr));
+        if !is_runnable_rust {
+            return None;
+        }
+        let mut info = vec!["rust"];
+        info.extend(attrs.into_iter().filter(|attr| *attr != "rust"));
+        Some(CodeBlockKind::Fenced(info.join(",").into()))
+    };
+    events
+        .into_iter()
+        .map(|ev| match ev {
+            Event::Start(Tag::CodeBlock(kind)) => {
+                Event::Start(Tag::CodeBlock(playground_kind(&kind).unwrap_or(kind)))
+            }
+            Event::End(Tag::CodeBlock(kind)) => {
+                Event::End(Tag::CodeBlock(playground_kind(&kind).unwrap_or(kind)))
+            }
+            ev => ev,
+        })
+        .collect()
+}
+
+/// The events of the first top level paragraph in `events`, like the summary rustdoc shows for an
+/// item in module listings, or `None` if there isn't one.
+fn first_paragraph(events: Vec<Event<'static>>) -> Option<Vec<Event<'static>>> {
+    let mut depth = 0;
+    let mut start = None;
+    for (i, ev) in events.iter().enumerate() {
+        match ev {
+            Event::Start(Tag::Paragraph) if depth == 0 => start = Some(i),
+            Event::End(Tag::Paragraph) if depth == 1 && start.is_some() => {
+                return start.map(|start| events[start..=i].to_vec());
+            }
+            _ => {}
+        }
+        match ev {
+            Event::Start(_) => depth += 1,
+            Event::End(_) => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Remove the heading at the very start of `events`, if there is one, for docs that start with a
+/// title of their own.
+fn strip_leading_heading(events: Vec<Event<'static>>) -> Vec<Event<'static>> {
+    if !matches!(events.first(), Some(Event::Start(Tag::Heading(_)))) {
+        return events;
+    }
+    match events
+        .iter()
+        .position(|ev| matches!(ev, Event::End(Tag::Heading(_))))
+    {
+        Some(end) => events.into_iter().skip(end + 1).collect(),
+        None => events,
+    }
+}
+
+/// The plain text of the heading whose contents start at the beginning of `events`.
+fn heading_text(events: &[Event]) -> String {
+    let mut text = String::new();
+    for ev in events {
+        match ev {
+            Event::End(Tag::Heading(_)) => break,
+            Event::Text(t) | Event::Code(t) => text.push_str(t),
+            _ => {}
+        }
+    }
+    text
+}
+
+/// Move the headings in `events` down by `offset` levels, so that they nest under a heading of
+/// that level. Headings can't go deeper than level 6.
+fn offset_headings(events: Vec<Event>, offset: u32) -> impl Iterator<Item = Event> {
+    events.into_iter().map(move |ev| match ev {
+        Event::Start(Tag::Heading(level)) => Event::Start(Tag::Heading((level + offset).min(6))),
+        Event::End(Tag::Heading(level)) => Event::End(Tag::Heading((level + offset).min(6))),
+        ev => ev,
+    })
+}
+
+/// Parse `markdown` into events that don't borrow from it.
+fn parse_markdown(markdown: &str) -> Vec<Event<'static>> {
+    merge_text_events(Parser::new_ext(markdown, Options::all()))
+        .into_iter()
+        .map(event_into_static)
+        .collect()
+}
+
+/// Combine runs of adjacent text events into single events.
+///
+/// The Markdown parser splits text at characters that might be syntax, like the `*` in a glob
+/// directive, so directives have to be matched against the combined text. Line breaks in the
+/// middle of a directive, from a long directive wrapped onto several lines, are merged into its
+/// text too, while the ones anywhere else are kept.
+fn merge_text_events<'a>(events: impl Iterator<Item = Event<'a>>) -> Vec<Event<'a>> {
+    merge_text_spans(events.map(|ev| (ev, 0..0)))
+        .into_iter()
+        .map(|(ev, _)| ev)
+        .collect()
+}
+
+/// Like [`merge_text_events`], for events along with the range of the source that they came from.
+/// The range of a combined event covers all of the events it was made from.
+fn merge_text_spans<'a>(
+    events: impl Iterator<Item = (Event<'a>, Range<usize>)>,
+) -> Vec<(Event<'a>, Range<usize>)> {
+    let mut merged: Vec<(Event, Range<usize>)> = Vec::new();
+    for (ev, range) in events {
+        match (merged.last_mut(), ev) {
+            (Some((Event::Text(prev), prev_range)), Event::Text(text)) => {
+                *prev = format!("{}{}", prev, text).into();
+                prev_range.end = range.end;
+            }
+            (Some((Event::Text(prev), prev_range)), Event::SoftBreak)
+                if OPEN_DIRECTIVE_REGEX.is_match(prev) =>
+            {
+                *prev = format!("{}\n", prev).into();
+                prev_range.end = range.end;
+            }
+            (_, ev) => merged.push((ev, range)),
+        }
+    }
+    merged
+}
+
+fn cow_into_static(s: CowStr) -> CowStr<'static> {
+    s.into_string().into()
+}
+
+fn event_into_static(event: Event) -> Event<'static> {
+    match event {
+        Event::Start(tag) => Event::Start(tag_into_static(tag)),
+        Event::End(tag) => Event::End(tag_into_static(tag)),
+        Event::Text(s) => Event::Text(cow_into_static(s)),
+        Event::Code(s) => Event::Code(cow_into_static(s)),
+        Event::Html(s) => Event::Html(cow_into_static(s)),
+        Event::FootnoteReference(s) => Event::FootnoteReference(cow_into_static(s)),
+        Event::SoftBreak => Event::SoftBreak,
+        Event::HardBreak => Event::HardBreak,
+        Event::Rule => Event::Rule,
+        Event::TaskListMarker(checked) => Event::TaskListMarker(checked),
+    }
+}
+
+fn tag_into_static(tag: Tag) -> Tag<'static> {
+    match tag {
+        Tag::Paragraph => Tag::Paragraph,
+        Tag::Heading(level) => Tag::Heading(level),
+        Tag::BlockQuote => Tag::BlockQuote,
+        Tag::CodeBlock(CodeBlockKind::Indented) => Tag::CodeBlock(CodeBlockKind::Indented),
+        Tag::CodeBlock(CodeBlockKind::Fenced(info)) => {
+            Tag::CodeBlock(CodeBlockKind::Fenced(cow_into_static(info)))
+        }
+        Tag::List(start) => Tag::List(start),
+        Tag::Item => Tag::Item,
+        Tag::FootnoteDefinition(label) => Tag::FootnoteDefinition(cow_into_static(label)),
+        Tag::Table(alignments) => Tag::Table(alignments),
+        Tag::TableHead => Tag::TableHead,
+        Tag::TableRow => Tag::TableRow,
+        Tag::TableCell => Tag::TableCell,
+        Tag::Emphasis => Tag::Emphasis,
+        Tag::Strong => Tag::Strong,
+        Tag::Strikethrough => Tag::Strikethrough,
+        Tag::Link(link_type, url, title) => {
+            Tag::Link(link_type, cow_into_static(url), cow_into_static(title))
+        }
+        Tag::Image(link_type, url, title) => {
+            Tag::Image(link_type, cow_into_static(url), cow_into_static(title))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        check_chapter, expand_chapter, find_directive_typos, has_directives, DirectiveProblem,
+        ExpandOptions,
+    };
+    use crate::{test_support::TempDir, CrateRoots, DirectiveCounts, Resolver};
+    use std::{cell::RefCell, convert::TryFrom, sync::Once};
+
+    fn expand(content: &str) -> String {
+        expand_chapter(&test_resolver(), &ExpandOptions::default(), content).unwrap()
+    }
+
+    fn test_resolver() -> Resolver {
+        let test_crate = format!("test_crate={}/test-crate", env!("CARGO_MANIFEST_DIR"));
+        Resolver::new(CrateRoots::try_from(vec![test_crate]).unwrap())
+    }
+
+    thread_local! {
+        static WARNINGS: RefCell<Option<Vec<String>>> = const { RefCell::new(None) };
+    }
+
+    /// Collects the warnings logged by the tests that are capturing them on their thread.
+    struct WarningLogger;
+
+    impl log::Log for WarningLogger {
+        fn enabled(&self, metadata: &log::Metadata) -> bool {
+            metadata.level() <= log::Level::Warn
+        }
+
+        fn log(&self, record: &log::Record) {
+            if self.enabled(record.metadata()) {
+                WARNINGS.with(|warnings| {
+                    if let Some(warnings) = warnings.borrow_mut().as_mut() {
+                        warnings.push(record.args().to_string());
+                    }
+                });
+            }
+        }
+
+        fn flush(&self) {}
+    }
+
+    /// Run `f`, returning what it returns along with the warnings it logged.
+    fn capture_warnings<T>(f: impl FnOnce() -> T) -> (T, Vec<String>) {
+        static INIT: Once = Once::new();
+        INIT.call_once(|| {
+            log::set_logger(&WarningLogger).unwrap();
+            log::set_max_level(log::LevelFilter::Warn);
+        });
+        WARNINGS.with(|warnings| *warnings.borrow_mut() = Some(vec![]));
+        let rv = f();
+        let warnings = WARNINGS.with(|warnings| warnings.borrow_mut().take().unwrap());
+        (rv, warnings)
+    }
+
+    #[test]
+    fn test_crate_override() {
+        let roots = vec![
+            format!("test_crate={}/test-crate", env!("CARGO_MANIFEST_DIR")),
+            format!(
+                "custom_lib={}/test-crate-custom-lib",
+                env!("CARGO_MANIFEST_DIR")
+            ),
+        ];
+        let resolver = Resolver::new(CrateRoots::try_from(roots).unwrap());
+        let options = ExpandOptions::default();
+        let overridden = expand_chapter(
+            &resolver,
+            &options,
+            "{{ #rustdoc test_crate::claws crate=custom_lib }}",
+        )
+        .unwrap();
+        assert_eq!(
+            overridden,
+            expand_chapter(&resolver, &options, "{{ #rustdoc custom_lib::claws }}").unwrap()
+        );
+
+        let err = expand_chapter(
+            &resolver,
+            &options,
+            "{{ #rustdoc test_crate::Crab crate=missing_crate }}",
+        )
+        .unwrap_err();
+        assert!(format!("{:#}", err).contains("missing_crate"));
+    }
+
+    #[test]
+    fn test_block_directive_with_code_example() {
+        let content = "# Lobsters\n\n{{ #rustdoc test_crate::crustaceans::LobsterColor::halloween }}\n\nThe end.";
+        assert_eq!(
+            expand(content),
+            "# Lobsters\n\nA common split colored lobster.\n\n\
+             ````\nlet lobster = LobsterColor::halloween();\n````\n\nThe end."
+        );
+    }
+
+    #[test]
+    fn test_paragraphs_and_hard_breaks() {
+        let content = "# Molting\n\n{{ #rustdoc test_crate::crustaceans::Crab::molt }}\n\nThe end.";
+        assert_eq!(
+            expand(content),
+            "# Molting\n\nShed the old shell.\n\n\
+             This takes a few hours,  \nand the crab hides until the new shell hardens.\n\n\
+             The end."
+        );
+    }
+
+    #[test]
+    fn test_wrap_prefix_and_suffix() {
+        let options = ExpandOptions {
+            wrap_prefix: Some("<div class=\"rustdoc\">".to_string()),
+            wrap_suffix: Some("</div>".to_string()),
+            ..ExpandOptions::default()
+        };
+        let content = "Intro.\n\n{{ #rustdoc test_crate::crustaceans::Crab }}\n\n\
+                       Crabs: {{ #rustdoc test_crate::crustaceans::Crab }}";
+        assert_eq!(
+            expand_chapter(&test_resolver(), &options, content).unwrap(),
+            "Intro.\n\n<div class=\"rustdoc\">\n\nA crab.\n\n</div>\n\nCrabs: A crab."
+        );
+    }
+
+    #[test]
+    fn test_directives_in_docs_are_not_expanded() {
+        let content = "{{ #rustdoc test_crate::crustaceans::Crab::wave }}";
+        assert_eq!(
+            expand(content),
+            "Wave a claw, like {{ #rustdoc test_crate::crustaceans::Crab::wave }} does."
+        );
+    }
+
+    #[test]
+    fn test_inline_directive() {
+        let content = "Crabs: {{ #rustdoc test_crate::crustaceans::Crab }} Really.";
+        assert_eq!(expand(content), "Crabs: A crab. Really.");
+    }
+
+    #[test]
+    fn test_directive_counts() {
+        let resolver = test_resolver();
+        let content = "{{ #rustdoc test_crate::crustaceans::Crab }}\n\n\
+                       Lobsters: {{ #rustdoc test_crate::crustaceans::Lobster }}\n\n\
+                       {{ #rustdoc-section test_crate::crustaceans::Crab }}";
+        expand_chapter(&resolver, &ExpandOptions::default(), content).unwrap();
+        assert_eq!(
+            resolver.directive_counts(),
+            DirectiveCounts {
+                expanded: 3,
+                resolved: 2,
+                placeholders: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn test_directive_typos() {
+        let content = "{{ #rustdocs test_crate::Crab }}\n\n\
+                       Inline {{#rust_doc-section test_crate::Crab}} and \
+                       {{ #RustDoc-Method test_crate::Crab }}.\n\n\
+                       {{ #rustdoc }} {{ #rustdoc test_crate::Crab }}\n\n\
+                       {{#rustdoc_include src/main.rs}}";
+        let typos = find_directive_typos(content);
+        let messages: Vec<_> = typos.iter().map(|typo| typo.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "`#rustdocs` isn't a rustdoc directive, so it was left as it is. Did you mean \
+                 `#rustdoc`?",
+                "`#rust_doc-section` isn't a rustdoc directive, so it was left as it is. Did you \
+                 mean `#rustdoc-section`?",
+                "`#RustDoc-Method` isn't a rustdoc directive, so it was left as it is. Did you \
+                 mean `#rustdoc-methods`?",
+                "This `#rustdoc` directive can't be read, so it was left as it is. Directives \
+                 look like `{{ #rustdoc my_crate::Item }}`",
+            ]
+        );
+        assert_eq!(
+            typos[1],
+            DirectiveProblem {
+                path: "test_crate::Crab".to_string(),
+                line: 3,
+                column: 8,
+                message: messages[1].to_string(),
+            }
+        );
+        // Typos are left as they are.
+        assert_eq!(
+            expand("{{ #rustdocs test_crate::Crab }}"),
+            "{{ #rustdocs test_crate::Crab }}"
+        );
+    }
+
+    #[test]
+    fn test_relative_links() {
+        let content = "{{ #rustdoc test_crate::crab_behavior::BurrowMap }}";
+        let options = ExpandOptions {
+            link_base: Some("https://example.com/crab/blob/main/".to_string()),
+            ..ExpandOptions::default()
+        };
+        let expanded = expand_chapter(&test_resolver(), &options, content).unwrap();
+        assert!(expanded
+            .contains("![The tunnels](https://example.com/crab/blob/main/assets/tunnels.png)"));
+        assert!(expanded
+            .contains("[digging guide](https://example.com/crab/blob/main/src/guide.md#tunnels)"));
+        assert!(expanded.contains("[the web](https://example.com/burrows)"));
+        assert!(expanded.contains("[below](#tunnels)"));
+        assert!(expanded.contains("[`Burrow`](crate::crab_behavior::Burrow)"));
+
+        // Without a base, links are left as they are.
+        assert!(expand(content).contains("![The tunnels](../assets/tunnels.png)"));
+    }
+
+    #[test]
+    fn test_relative_links_warn_once() {
+        let (expanded, warnings) =
+            capture_warnings(|| expand("{{ #rustdoc test_crate::crab_behavior::BurrowMap }}"));
+        assert!(expanded.contains("[digging guide](guide.md#tunnels)"));
+        let relative: Vec<_> = warnings
+            .iter()
+            .filter(|warning| warning.contains("link to the relative path"))
+            .collect();
+        assert_eq!(relative.len(), 2, "{:?}", relative);
+        assert!(relative[0].contains("../assets/tunnels.png"));
+        assert!(relative[1].contains("guide.md#tunnels"));
+    }
+
+    #[test]
+    fn test_readme_directive() {
+        let temp_dir = TempDir::new("readme");
+        for name in ["intro", "plain", "none"] {
+            temp_dir.write(format!("{}/src/lib.rs", name), "//! Crate docs.");
+            temp_dir.write(format!("{}/README.md", name), "# Plain\n\nThe README.");
+        }
+        temp_dir.write("intro/docs/INTRO.md", "An *introduction*.");
+        temp_dir.write(
+            "intro/Cargo.toml",
+            "[package]\nname = \"intro\"\nversion = \"0.1.0\"\nreadme = \"docs/INTRO.md\"",
+        );
+        temp_dir.write(
+            "none/Cargo.toml",
+            "[package]\nname = \"none\"\nversion = \"0.1.0\"\nreadme = false",
+        );
+        let dir = temp_dir.path();
+        let roots = vec![
+            dir.join("intro").to_string_lossy().to_string(),
+            format!("plain={}", dir.join("plain").to_string_lossy()),
+            dir.join("none").to_string_lossy().to_string(),
+        ];
+        let resolver = Resolver::new(CrateRoots::try_from(roots).unwrap());
+        let expand = |content: &str| expand_chapter(&resolver, &ExpandOptions::default(), content);
+
+        // The `readme` field is used, or else `README.md`, whatever the crate's docs say.
+        assert_eq!(
+            expand("{{ #rustdoc-readme intro }}").unwrap(),
+            "An *introduction*."
+        );
+        assert_eq!(
+            expand("{{ #rustdoc-readme plain }}").unwrap(),
+            "# Plain\n\nThe README."
+        );
+        assert_eq!(
+            expand("{{ #rustdoc-readme none }}").unwrap(),
+            "\\<< No documentation found for none >>"
+        );
+        let err = expand("{{ #rustdoc-readme intro::Thing }}").unwrap_err();
+        assert!(format!("{:#}", err).contains(
+            "`#rustdoc-readme` takes the name of a crate, not an item like intro::Thing"
+        ));
+    }
+
+    #[test]
+    fn test_summary_directive() {
+        let content =
+            "Blue lobsters: {{ #rustdoc-summary test_crate::crustaceans::LobsterColor::Blue }}";
+        assert_eq!(
+            expand(content),
+            "Blue lobsters: Caused by a genetic defect."
+        );
+        // Only the first paragraph is kept, so longer docs still fit in a sentence.
+        let content =
+            "To molt is to {{ #rustdoc-summary test_crate::crustaceans::Crab::molt }} Mostly.";
+        assert_eq!(expand(content), "To molt is to Shed the old shell. Mostly.");
+        assert_eq!(
+            expand("{{ #rustdoc-summary test_crate::crustaceans::Crab::molt }}"),
+            "Shed the old shell."
+        );
+    }
+
+    #[test]
+    fn test_other_separators() {
+        assert_eq!(
+            expand("{{ #rustdoc test_crate/crustaceans/Crab }}"),
+            "A crab."
+        );
+        assert_eq!(
+            expand("Cooked: {{ #rustdoc test_crate.crustaceans.CookedCrab.0 }}"),
+            "Cooked: The crab that was cooked."
+        );
+    }
+
+    #[test]
+    fn test_generic_paths_with_spaces() {
+        assert_eq!(
+            expand("{{ #rustdoc test_crate::crustaceans::Crab<K, V>::num_legs }}"),
+            expand("{{ #rustdoc test_crate::crustaceans::Crab::num_legs }}")
+        );
+        assert_eq!(
+            expand("{{ #rustdoc <test_crate::crustaceans::Crab as Speak>::greet }}"),
+            "Say hello."
+        );
+        assert_eq!(
+            expand(
+                "{{ #rustdoc-section <test_crate::crustaceans::Crab as Speak>::greet level=2 }}"
+            ),
+            "## greet\n\nSay hello."
+        );
+    }
+
+    #[test]
+    fn test_wrapped_directive() {
+        assert_eq!(
+            expand("{{ #rustdoc test_crate::crustaceans::Crab\n}}"),
+            "A crab."
+        );
+        assert_eq!(
+            expand("{{ #rustdoc-section\ntest_crate::crustaceans::Crab\nlevel=2 }}"),
+            "## Crab\n\nA crab."
+        );
+        assert_eq!(
+            expand("Crabs:\n{{ #rustdoc test_crate::crustaceans::Crab\n}} Really."),
+            "Crabs:\nA crab. Really."
+        );
+        // Line breaks outside of directives are left alone.
+        assert_eq!(
+            expand("Curly {{ braces\nand more."),
+            "Curly {{ braces\nand more."
+        );
+    }
+
+    #[test]
+    fn test_module_listing_headings() {
+        let content = "{{ #rustdoc test_crate::* }}";
+        assert_eq!(
+            expand(content),
+            "### crustaceans\n\nAll sorts of crustaceans.\n\n\
+             ### boil_crab\n\nBoil a crab the old way."
+        );
+    }
+
+    #[test]
+    fn test_attr_listing() {
+        assert_eq!(
+            expand("{{ #rustdoc test_crate::* attr=deprecated }}"),
+            "### boil_crab\n\nBoil a crab the old way."
+        );
+        let err = expand_chapter(
+            &test_resolver(),
+            &ExpandOptions::default(),
+            "{{ #rustdoc test_crate::boil_crab attr=deprecated }}",
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            "`attr=deprecated` only applies to module listings like `test_crate::boil_crab::*`"
+        );
+    }
+
+    #[test]
+    fn test_section_directive() {
+        let content = "{{ #rustdoc-section test_crate::crustaceans::LobsterColor::halloween }}";
+        assert_eq!(
+            expand(content),
+            "### halloween\n\nA common split colored lobster.\n\n\
+             ````\nlet lobster = LobsterColor::halloween();\n````"
+        );
+
+        let content = "{{ #rustdoc-section test_crate::crustaceans::Crab level=2 }}";
+        assert_eq!(expand(content), "## Crab\n\nA crab.");
+    }
+
+    #[test]
+    fn test_section_offsets_doc_headings() {
+        let events = super::offset_headings(super::parse_markdown("# Examples\n\n###### Deep"), 3)
+            .collect::<Vec<_>>();
+        let mut markdown = String::new();
+        pulldown_cmark_to_cmark::cmark(events.iter(), &mut markdown, None).unwrap();
+        assert_eq!(markdown, "#### Examples\n\n###### Deep");
+    }
+
+    #[test]
+    fn test_heading_argument() {
+        let path = "test_crate::crab_behavior::Burrow";
+        assert_eq!(
+            expand(&format!("{{{{ #rustdoc {} }}}}", path)),
+            "# Burrows\n\nWhere a crab hides from the tide."
+        );
+        assert_eq!(
+            expand(&format!("{{{{ #rustdoc {} heading=false }}}}", path)),
+            "Where a crab hides from the tide."
+        );
+        assert_eq!(
+            expand(&format!(
+                "{{{{ #rustdoc-section {} level=2 heading=false }}}}",
+                path
+            )),
+            "## Burrow\n\nWhere a crab hides from the tide."
+        );
+        // Docs that don't start with a heading are left as they are.
+        assert_eq!(
+            expand("{{ #rustdoc test_crate::crustaceans::Crab heading=false }}"),
+            "A crab."
+        );
+
+        let expand_error = |content: &str| {
+            expand_chapter(&test_resolver(), &ExpandOptions::default(), content)
+                .unwrap_err()
+                .root_cause()
+                .to_string()
+        };
+        assert_eq!(
+            expand_error(&format!("{{{{ #rustdoc {} heading=no }}}}", path)),
+            "Invalid value `no` for `heading` on test_crate::crab_behavior::Burrow, expected \
+             `true` or `false`"
+        );
+        assert_eq!(
+            expand_error("{{ #rustdoc test_crate::crustaceans::* heading=false }}"),
+            "`heading=false` only applies to the docs of a single item, not the listing of \
+             test_crate::crustaceans"
+        );
+    }
+
+    #[test]
+    fn test_directive_arguments() {
+        assert_eq!(
+            expand(
+                "{{ #rustdoc-section test_crate::crustaceans::Crab  crate=test_crate  level=2 }}"
+            ),
+            "## Crab\n\nA crab."
+        );
+
+        let expand_error = |content: &str| {
+            expand_chapter(&test_resolver(), &ExpandOptions::default(), content)
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(
+            expand_error("{{ #rustdoc test_crate::crustaceans::Crab level=2 }}"),
+            "Unknown argument `level` in the #rustdoc directive for \
+             test_crate::crustaceans::Crab, expected one of `crate`, `heading`, `attr`"
+        );
+        assert_eq!(
+            expand_error("{{ #rustdoc-source test_crate::crustaceans::Crab lines }}"),
+            "Expected an argument like `key=value` in the #rustdoc-source directive for \
+             test_crate::crustaceans::Crab, found `lines`"
+        );
+        assert_eq!(
+            expand_error("{{ #rustdoc-section test_crate::crustaceans::Crab level=2 level=3 }}"),
+            "The argument `level` is given more than once in the #rustdoc-section directive \
+             for test_crate::crustaceans::Crab"
+        );
+    }
+
+    #[test]
+    fn test_playground_examples() {
+        let markdown = "```\nlet a = 1;\n```\n\n\
+                        ```rust,should_panic\npanic!();\n```\n\n\
+                        ```edition2021\nlet b = 2;\n```\n\n\
+                        ```no_run\nloop {}\n```\n\n\
+                        ```rust,ignore\nnot rust\n```\n\n\
+                        ```toml\n[package]\n```\n\n\
+                        Text.\n\n    let c = 3;\n\nMore text.";
+        let events = super::playground_examples(super::parse_markdown(markdown));
+        let mut rendered = String::new();
+        pulldown_cmark_to_cmark::cmark(events.iter(), &mut rendered, None).unwrap();
+        assert_eq!(
+            rendered.trim_start(),
+            "````rust\nlet a = 1;\n````\n\n\
+             ````rust,should_panic\npanic!();\n````\n\n\
+             ````rust,edition2021\nlet b = 2;\n````\n\n\
+             ````no_run\nloop {}\n````\n\n\
+             ````rust,ignore\nnot rust\n````\n\n\
+             ````toml\n[package]\n````\n\n\
+             Text.\n\n\
+             ````rust\nlet c = 3;\n````\n\n\
+             More text."
+        );
+    }
+
+    #[test]
+    fn test_const_directive() {
+        let content = "{{ #rustdoc-const test_crate::crustaceans::Crab::MAX_LEGS }}";
+        assert_eq!(
+            expand(content),
+            "The most legs a crab can have.\n\n`MAX_LEGS = 10`"
+        );
+    }
+
+    #[test]
+    fn test_methods_directive() {
+        let content = "{{ #rustdoc-methods test_crate::crustaceans::Crab order=alphabetical }}";
+        let expanded = expand(content);
+        assert!(expanded.starts_with(
+            "### greet\n\n````rust\nfn greet(&self) -> String\n````\n\nSay hello.\n\n### molt"
+        ));
+
+        let content = "{{ #rustdoc-methods test_crate::crustaceans::Crab order=random }}";
+        let err = expand_chapter(&test_resolver(), &ExpandOptions::default(), content).unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            "Unknown method order `random`, expected `source` or `alphabetical`"
+        );
+    }
+
+    #[test]
+    fn test_impls_directive() {
+        let content = "{{ #rustdoc-impls test_crate::crustaceans::Crab }}";
+        assert_eq!(
+            expand(content),
+            "### Implementations\n\n* `impl Crab`\n\n\
+             ### Trait Implementations\n\n* `Speak`: Things that can talk."
+        );
+
+        let content = "{{ #rustdoc-impls test_crate::crustaceans::Crab heading=false }}";
+        let err = expand_chapter(&test_resolver(), &ExpandOptions::default(), content).unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            "Unknown argument `heading` in the #rustdoc-impls directive for \
+             test_crate::crustaceans::Crab, expected one of `crate`"
+        );
+    }
+
+    #[test]
+    fn test_trait_impl_directive() {
+        assert_eq!(
+            expand("{{ #rustdoc test_crate::crab_behavior::Burrow#Speak }}"),
+            "Burrows echo whatever is said into them."
+        );
+    }
+
+    #[test]
+    fn test_has_directives() {
+        assert!(has_directives("Part {{ #rustdoc test_crate::Crab }}"));
+        assert!(!has_directives("Part one"));
+        assert!(!has_directives("{{ #include file.rs }}"));
+    }
+
+    #[test]
+    fn test_check_chapter() {
+        let content = "# Crabs\n\n{{ #rustdoc test_crate::crustaceans::Crab }}\n\n\
+                       A shrimp: {{ #rustdoc test_crate::crustaceans::Shrimp }}\n\n\
+                       {{ #rustdoc-section test_crate::crustaceans::Crab level=9 }}";
+        let problems = check_chapter(&test_resolver(), &ExpandOptions::default(), content);
+        assert_eq!(
+            problems,
+            vec![
+                DirectiveProblem {
+                    path: "test_crate::crustaceans::Shrimp".to_string(),
+                    line: 5,
+                    column: 11,
+                    message: "No item found".to_string(),
+                },
+                DirectiveProblem {
+                    path: "test_crate::crustaceans::Crab".to_string(),
+                    line: 7,
+                    column: 1,
+                    message: "Invalid heading level `9` for test_crate::crustaceans::Crab, \
+                              expected 1 to 6"
+                        .to_string(),
+                },
+            ]
+        );
+
+        // Directives in inline code aren't expanded, so they aren't checked either.
+        let content = "Write `{{ #rustdoc my_crate::Missing }}` to embed docs.";
+        assert_eq!(expand(content), content);
+        assert!(check_chapter(&test_resolver(), &ExpandOptions::default(), content).is_empty());
+    }
+
+    #[test]
+    fn test_filter_sections() {
+        let doc = "Summary.\n\n# Examples\n\nAn example.\n\n## More examples\n\nAnother.\n\n\
+                   # Safety\n\nBe careful.\n\n# Panics\n\nSometimes.";
+        let filter = |drop_sections: &[&str], only_sections: &[&str]| {
+            let options = ExpandOptions {
+                drop_sections: drop_sections.iter().map(|s| s.to_string()).collect(),
+                only_sections: only_sections.iter().map(|s| s.to_string()).collect(),
+                ..ExpandOptions::default()
+            };
+            let events = super::filter_sections(super::parse_markdown(doc), &options);
+            let mut markdown = String::new();
+            pulldown_cmark_to_cmark::cmark(events.iter(), &mut markdown, None).unwrap();
+            markdown
+        };
+
+        assert_eq!(
+            filter(&["Safety", "panics"], &[]),
+            "Summary.\n\n# Examples\n\nAn example.\n\n## More examples\n\nAnother."
+        );
+        assert_eq!(
+            filter(&["More examples"], &["Examples"]),
+            "Summary.\n\n# Examples\n\nAn example."
+        );
+        assert_eq!(
+            filter(&[], &["Panics"]),
+            "Summary.\n\n# Panics\n\nSometimes."
+        );
+    }
+
+    #[test]
+    fn test_source_directive() {
+        let content = "Greeting:\n\n{{ #rustdoc-source test_crate::crab_behavior::Speak::greet lines=25:27 }}";
+        assert_eq!(
+            expand(content),
+            "Greeting:\n\n````rust\nfn greet(&self) -> String {\n    format!(\"Hello, I'm {}\", self.name())\n}\n````"
+        );
+
+        let problems = check_chapter(
+            &test_resolver(),
+            &ExpandOptions::default(),
+            "{{ #rustdoc-source test_crate::crab_behavior::Speak::greet lines=27:25 }}\n\n\
+             {{ #rustdoc-source test_crate::crab_behavior::Speak::greet lines=1:2 }}",
+        );
+        let messages: Vec<_> = problems.iter().map(|p| p.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "Invalid line range `27:25` for test_crate::crab_behavior::Speak::greet, \
+                 expected something like `10:25`",
+                "Lines 1:2 are outside of test_crate::crab_behavior::Speak::greet, \
+                 which is on lines 24:27",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_external_crates() {
+        let options = ExpandOptions {
+            external_crates: vec!["std".to_string(), "serde".to_string()],
+            ..ExpandOptions::default()
+        };
+        let content = "Like {{ #rustdoc std::vec::Vec }}, see {{ #rustdoc serde }}.";
+        assert_eq!(
+            expand_chapter(&test_resolver(), &options, content).unwrap(),
+            "Like [`std::vec::Vec`](https://doc.rust-lang.org/std/?search=vec::Vec), \
+             see [`serde`](https://docs.rs/serde/latest/serde/)."
+        );
+
+        // Local crates are always read, even if they are listed as external.
+        let options = ExpandOptions {
+            external_crates: vec!["test_crate".to_string()],
+            ..ExpandOptions::default()
+        };
+        let content = "{{ #rustdoc test_crate::crustaceans::Crab }}";
+        assert_eq!(
+            expand_chapter(&test_resolver(), &options, content).unwrap(),
+            "A crab."
+        );
+        assert!(check_chapter(
+            &test_resolver(),
+            &ExpandOptions::default(),
+            "{{ #rustdoc std::vec::Vec }}"
+        )[0]
+        .message
+        .starts_with("Crate std not found"));
+    }
+
+    #[test]
+    fn test_source_links() {
+        let options = ExpandOptions {
+            source_link_base: Some("https://example.com/{file}#L{line}".to_string()),
+            ..ExpandOptions::default()
+        };
+        let content = "{{ #rustdoc test_crate::crustaceans::Crab::num_legs }}";
+        assert_eq!(
+            expand_chapter(&test_resolver(), &options, content).unwrap(),
+            "The number of legs this crab has. Probably 8, but there are some weird\n\
+             crabs out there!\n\n\
+             [→ source](https://example.com/src/crustaceans.rs#L8)"
+        );
+    }
+}
@@ -1,20 +1,36 @@
-use std::{convert::TryFrom, io, process, str::FromStr};
+use std::{
+    collections::BTreeMap,
+    convert::TryFrom,
+    env,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process,
+    str::FromStr,
+};
 
-use anyhow::{Context, Result};
+use anyhow::{anyhow, bail, ensure, Context, Result};
 use clap::Clap;
-use lazy_static::lazy_static;
+use log::{error, info, warn, LevelFilter};
 use mdbook::{
     book::Book,
     preprocess::{CmdPreprocessor, Preprocessor, PreprocessorContext},
-    BookItem,
+    BookItem, Config, MDBook,
+};
+use mdbook_rust_doc::{
+    check_chapter, expand_chapter, find_directive_typos, has_directives, CfgSet, CrateRoots,
+    ExpandOptions, ItemInfo, MethodOrder, ResolveOptions, Resolver, RustPath, VisibilityFilter,
 };
-use mdbook_rust_doc::{find_doc_for_item, CrateRoots, RustPath};
-use pulldown_cmark::Event;
-use regex::{Captures, Regex};
 use semver::{Version, VersionReq};
 use serde::Deserialize;
 
 #[derive(Clap, Debug)]
+#[clap(
+    version,
+    about,
+    after_help = "Without a subcommand, the preprocessor reads a book from mdbook on stdin and \
+                  writes it back with its rustdoc directives expanded. Add it to a book with a \
+                  `[preprocessor.rustdoc]` table in book.toml."
+)]
 struct Opts {
     #[clap(subcommand)]
     cmd: Option<SubCommand>,
@@ -22,7 +38,49 @@ struct Opts {
 
 #[derive(Clap, Debug)]
 enum SubCommand {
-    Supports { renderer: String },
+    /// Exit with 0 if the renderer is supported, or 1 if it isn't. Used by mdbook.
+    Supports {
+        /// The name of the renderer, like `html`.
+        renderer: String,
+    },
+    /// Check that every rustdoc directive in a book resolves, without rendering it.
+    Check {
+        /// The root directory of the book, which contains `book.toml`.
+        #[clap(default_value = ".")]
+        dir: PathBuf,
+    },
+    /// Look up a single item with the crates configured for a book, and print what was found.
+    Query {
+        /// The path of the item, like `my_crate::Crab::num_legs`.
+        path: String,
+        /// The root directory of the book, which contains `book.toml`.
+        #[clap(long, default_value = ".")]
+        book: PathBuf,
+        /// How to print the item.
+        #[clap(long, default_value = "text", possible_values = &["text", "json"])]
+        format: QueryFormat,
+    },
+}
+
+/// The output formats of the `query` command.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum QueryFormat {
+    /// The item's kind and location on the first line, followed by its docs.
+    Text,
+    /// A JSON object with the item's kind, file, start and end positions, and docs.
+    Json,
+}
+
+impl FromStr for QueryFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            _ => bail!("Unknown format {}, expected `text` or `json`", s),
+        }
+    }
 }
 
 fn main() -> Result<()> {
@@ -36,6 +94,7 @@ fn main() -> Result<()> {
     // );
     // Ok(())
 
+    init_logger();
     let opts = Opts::parse();
     // let crate_roots =
     //     CrateRoots::try_from(opts.crates.clone()).context("Converting crate roots")?;
@@ -44,12 +103,37 @@ fn main() -> Result<()> {
 
     match opts.cmd {
         Some(SubCommand::Supports { renderer }) => handle_supports(&preprocessor, &renderer),
+        Some(SubCommand::Check { dir }) => handle_check(&preprocessor, &dir)?,
+        Some(SubCommand::Query { path, book, format }) => {
+            handle_query(&preprocessor, &path, &book, format)?;
+        }
         None => handle_preprocessing(&preprocessor)?,
     }
 
     Ok(())
 }
 
+/// Log like mdbook does, so that warnings show up alongside its own and can be filtered with
+/// `RUST_LOG`. Without `RUST_LOG`, everything at the info level and above is shown.
+fn init_logger() {
+    let mut builder = env_logger::Builder::new();
+    builder.format(|formatter, record| {
+        writeln!(
+            formatter,
+            "{} [{}] ({}): {}",
+            formatter.timestamp_seconds(),
+            record.level(),
+            record.target(),
+            record.args()
+        )
+    });
+    match env::var("RUST_LOG") {
+        Ok(filters) => builder.parse_filters(&filters),
+        Err(_) => builder.filter(None, LevelFilter::Info),
+    };
+    builder.init();
+}
+
 fn handle_supports(pre: &dyn Preprocessor, renderer: &str) -> ! {
     let supported = pre.supports_renderer(renderer);
     // let crate_roots =
@@ -65,8 +149,8 @@ fn handle_preprocessing(pre: &dyn Preprocessor) -> Result<()> {
     let version_req = VersionReq::parse(mdbook::MDBOOK_VERSION)?;
 
     if !version_req.matches(&book_version) {
-        eprintln!(
-            "Warning: The {} plugin was built against version {} of mdbook, \
+        warn!(
+            "The {} plugin was built against version {} of mdbook, \
              but we're being called from version {}",
             pre.name(),
             mdbook::MDBOOK_VERSION,
@@ -80,88 +164,668 @@ fn handle_preprocessing(pre: &dyn Preprocessor) -> Result<()> {
     Ok(())
 }
 
+/// Resolve every directive in the book at `dir`, reporting the ones that fail and exiting with 1
+/// if there are any.
+///
+/// Failures and the summary of them are logged as errors, next to the warnings about typos and
+/// undocumented items, so all of the command's output goes through the same logger.
+fn handle_check(pre: &RustDocPreprocessor, dir: &Path) -> Result<()> {
+    let md = MDBook::load(dir).context(format!("Loading book at {}", dir.to_string_lossy()))?;
+    // Like when mdbook runs the preprocessor, crate paths are relative to the working directory.
+    let (resolver, expand_options) = pre.setup(&md.root, &md.config)?;
+
+    let mut checked = 0;
+    let mut failed = 0;
+    for item in md.book.iter() {
+        warn_if_part_title_has_directives(item);
+        if let BookItem::Chapter(chapter) = item {
+            let chapter_path = match &chapter.path {
+                Some(path) => md.root.join(&md.config.book.src).join(path),
+                // Draft chapters have no content to check.
+                None => continue,
+            };
+            checked += 1;
+            if expand_options.typo_warnings {
+                warn_directive_typos(&chapter_path.to_string_lossy(), &chapter.content);
+            }
+            for problem in check_chapter(&resolver, &expand_options, &chapter.content) {
+                failed += 1;
+                error!(
+                    "{}:{}:{}: {}: {}",
+                    chapter_path.to_string_lossy(),
+                    problem.line,
+                    problem.column,
+                    problem.path,
+                    problem.message
+                );
+            }
+        }
+    }
+
+    resolver.save_disk_cache()?;
+    report_undocumented(&resolver);
+    if failed > 0 {
+        error!(
+            "{} rustdoc directive(s) failed to resolve in {} chapter(s)",
+            failed, checked
+        );
+        process::exit(1);
+    }
+    info!("All rustdoc directives resolved in {} chapter(s)", checked);
+    Ok(())
+}
+
+/// Warn about each bit of `content`, the text of the chapter at `location`, that looks like a
+/// misspelled directive, since it is silently left as it is.
+fn warn_directive_typos(location: &str, content: &str) {
+    for typo in find_directive_typos(content) {
+        warn!(
+            "{}:{}:{}: {}",
+            location, typo.line, typo.column, typo.message
+        );
+    }
+}
+
+/// Part titles are plain text in the book's table of contents, so directives in them can't be
+/// expanded. Warn about them instead of silently leaving them as they are.
+fn warn_if_part_title_has_directives(item: &BookItem) {
+    if let BookItem::PartTitle(title) = item {
+        if has_directives(title) {
+            warn!(
+                "rustdoc directives in part titles aren't expanded: {}",
+                title
+            );
+        }
+    }
+}
+
+/// Look up the item at `path` with the resolver of the book at `dir`, and print it in `format`.
+fn handle_query(
+    pre: &RustDocPreprocessor,
+    path: &str,
+    dir: &Path,
+    format: QueryFormat,
+) -> Result<()> {
+    let md = MDBook::load(dir).context(format!("Loading book at {}", dir.to_string_lossy()))?;
+    let (resolver, _) = pre.setup(&md.root, &md.config)?;
+    let rust_path = RustPath::from_str(path)?;
+    let info = resolver
+        .resolve_info(&rust_path)?
+        .ok_or_else(|| anyhow!("Item {} not found", rust_path))?;
+    println!("{}", format_item_info(&info, format)?);
+    Ok(())
+}
+
+/// Format `info` for the `query` command.
+fn format_item_info(info: &ItemInfo, format: QueryFormat) -> Result<String> {
+    Ok(match format {
+        QueryFormat::Text => format!(
+            "{} at {}:{}\n\n{}",
+            info.kind,
+            info.file.to_string_lossy(),
+            info.start.line,
+            info.docs
+        ),
+        QueryFormat::Json => serde_json::to_string_pretty(info)?,
+    })
+}
+
+/// Parse `preludes` config entries like `my_crate=prelude` into each crate's prelude module.
+fn parse_preludes(entries: &[String]) -> Result<BTreeMap<String, RustPath>> {
+    let mut preludes = BTreeMap::new();
+    for entry in entries {
+        let (crate_name, module) = entry.split_once('=').ok_or_else(|| {
+            anyhow!(
+                "Expected an entry like `my_crate=prelude`, found `{}`",
+                entry
+            )
+        })?;
+        let module = RustPath::from_str(module)
+            .context(format!("Parsing the prelude module of {}", crate_name))?;
+        if preludes
+            .insert(crate_name.trim().to_string(), module)
+            .is_some()
+        {
+            bail!("The crate {} has more than one prelude", crate_name.trim());
+        }
+    }
+    Ok(preludes)
+}
+
+/// Print which of the items that the book refers to have no docs, to help track coverage.
+fn report_undocumented(resolver: &Resolver) {
+    let undocumented = resolver.undocumented();
+    if !undocumented.is_empty() {
+        warn!(
+            "{} item(s) referenced by rustdoc directives have no documentation: {}",
+            undocumented.len(),
+            undocumented.join(", ")
+        );
+    }
+}
+
+/// Print a summary of the directives expanded in a build, followed by the undocumented items
+/// they referred to.
+fn report_build(resolver: &Resolver) {
+    let counts = resolver.directive_counts();
+    let summary = format!(
+        "Expanded {} rustdoc directive(s): {} resolved, {} with no item found",
+        counts.expanded, counts.resolved, counts.placeholders
+    );
+    if counts.placeholders > 0 {
+        warn!("{}", summary);
+    } else {
+        info!("{}", summary);
+    }
+    report_undocumented(resolver);
+}
+
 struct RustDocPreprocessor;
 
 impl RustDocPreprocessor {
-    fn process_item(crate_roots: &CrateRoots, item: &mut BookItem) -> Result<()> {
-        if let BookItem::Chapter(chapter) = item {
-            let mut new_content = String::with_capacity(chapter.content.len());
+    /// Build the resolver and expansion options from the rustdoc table in the config of the book
+    /// at `root`.
+    fn setup(&self, root: &Path, config: &Config) -> Result<(Resolver, ExpandOptions)> {
+        let rustdoc_meta = BookMetaPreprocessorRustDoc::from_preprocessor_table(
+            config.get_preprocessor(self.name()),
+        )?;
+        let crates = rustdoc_meta
+            .crates
+            .into_iter()
+            .map(CrateEntry::into_config_string)
+            .collect::<Vec<_>>();
+        let crate_roots = CrateRoots::try_from(crates).context("Reading rustdoc crates config")?;
+        let options = ResolveOptions {
+            cfg: CfgSet::try_from(rustdoc_meta.cfg).context("Reading rustdoc cfg config")?,
+            cfg_notes: rustdoc_meta.cfg_notes,
+            visibility: rustdoc_meta.visibility,
+            deprecation_notices: rustdoc_meta.deprecation_notices,
+            must_use_notices: rustdoc_meta.must_use_notices,
+            signature_fallback: rustdoc_meta.signature_fallback,
+            expand_field_types: rustdoc_meta.expand_field_types,
+            target_dirs: rustdoc_meta.target_dirs,
+            keep_blank_lines: rustdoc_meta.keep_blank_lines,
+            preludes: parse_preludes(&rustdoc_meta.preludes)
+                .context("Reading rustdoc preludes config")?,
+            reexport_depth: rustdoc_meta
+                .reexport_depth
+                .unwrap_or_else(|| ResolveOptions::default().reexport_depth),
+            fn_body_items: rustdoc_meta.fn_body_items,
+            max_scan_files: rustdoc_meta
+                .max_scan_files
+                .unwrap_or_else(|| ResolveOptions::default().max_scan_files),
+            #[cfg(feature = "docs-rs")]
+            docs_rs: rustdoc_meta
+                .docs_rs
+                .iter()
+                .map(|krate| krate.parse())
+                .collect::<Result<_>>()
+                .context("Reading rustdoc docs_rs config")?,
+        };
+        #[cfg(not(feature = "docs-rs"))]
+        if !rustdoc_meta.docs_rs.is_empty() {
+            warn!(
+                "`docs_rs` in the rustdoc config is ignored, since mdbook-rust-doc was \
+                 built without the `docs-rs` feature"
+            );
+        }
 
-            let parser =
-                pulldown_cmark::Parser::new_ext(&chapter.content, pulldown_cmark::Options::all());
+        let mut expand_options = ExpandOptions {
+            drop_sections: rustdoc_meta.drop_sections,
+            only_sections: rustdoc_meta.only_sections,
+            source_link_base: rustdoc_meta.source_link_base,
+            wrap_prefix: rustdoc_meta.wrap_prefix,
+            wrap_suffix: rustdoc_meta.wrap_suffix,
+            playground_examples: rustdoc_meta.playground_examples,
+            external_crates: rustdoc_meta.external_crates,
+            method_order: rustdoc_meta.method_order,
+            link_base: rustdoc_meta.link_base,
+            typo_warnings: !rustdoc_meta.ignore_directive_typos,
+            ..ExpandOptions::default()
+        };
+        if let Some(level) = rustdoc_meta.section_level {
+            ensure!(
+                (1..=6).contains(&level),
+                "`section_level` under `[preprocessor.rustdoc]` in book.toml must be 1 to 6"
+            );
+            expand_options.section_level = level;
+        }
 
-            let mut any_error = None;
+        let mut resolver = Resolver::with_options(crate_roots, options);
+        if let Some(cache_dir) = rustdoc_meta.cache_dir {
+            resolver.enable_disk_cache(root.join(cache_dir).join("rustdoc-cache.json"));
+        }
 
-            let modified_events = parser
-                .map(|ev| match ev {
-                    Event::Text(text) => {
-                        lazy_static! {
-                            static ref DIRECTIVE_REGEX: Regex =
-                                Regex::new(r#"\{\{\s*#rustdoc\s+([\w:]+)\s*\}\}"#).unwrap();
-                        }
+        Ok((resolver, expand_options))
+    }
 
-                        let text = text.to_string();
-                        let text = DIRECTIVE_REGEX.replace(&text, |captures: &Captures| {
-                            let path_match = captures
-                                .get(1)
-                                .expect("Bug: capture group not in directive regex");
-                            let item_path =
-                                RustPath::from_str(path_match.as_str()).expect("invalid item path");
-                            find_doc_for_item(&item_path, crate_roots)
-                                .unwrap_or_else(|error| {
-                                    any_error.replace(error);
-                                    None
-                                })
-                                .unwrap_or_else(|| {
-                                    format!("<< No documentation found for {} >>", item_path)
-                                })
-                        });
-                        Ok(Event::Text(text.to_string().into()))
-                    }
-                    ev => Ok(ev),
-                })
-                .collect::<Result<Vec<Event>>>()?
-                .into_iter();
-
-            if let Some(error) = any_error {
-                return Err(error);
+    fn process_item(
+        resolver: &Resolver,
+        options: &ExpandOptions,
+        item: &mut BookItem,
+    ) -> Result<()> {
+        // Draft chapters are chapters too, and their content is expanded like any other. Separators
+        // have no content at all.
+        match item {
+            BookItem::Chapter(chapter) => {
+                if options.typo_warnings {
+                    let location = match &chapter.path {
+                        Some(path) => path.to_string_lossy().to_string(),
+                        None => chapter.name.clone(),
+                    };
+                    warn_directive_typos(&location, &chapter.content);
+                }
+                chapter.content = expand_chapter(resolver, options, &chapter.content)?;
             }
-
-            pulldown_cmark_to_cmark::cmark(modified_events, &mut new_content, None)?;
-            chapter.content = new_content;
+            BookItem::PartTitle(_) => warn_if_part_title_has_directives(item),
+            BookItem::Separator => {}
         }
         Ok(())
     }
 }
 
-#[derive(Debug, Clone, Deserialize)]
-struct BookMeta {
-    preprocessor: BookMetaPreprocessor,
+/// An entry of the `crates` list, either a string like `name=path` or a table.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+enum CrateEntry {
+    Path(String),
+    Table {
+        name: Option<String>,
+        /// The crate's directory.
+        root: String,
+        /// The library's root file, relative to `root`.
+        entry: Option<String>,
+    },
 }
 
-#[derive(Debug, Clone, Deserialize)]
-struct BookMetaPreprocessor {
-    rustdoc: BookMetaPreprocessorRustDoc,
+impl CrateEntry {
+    /// The entry written as a string, which names the root file instead of the crate's directory
+    /// if the table has an `entry`.
+    fn into_config_string(self) -> String {
+        match self {
+            Self::Path(value) => value,
+            Self::Table { name, root, entry } => {
+                let path = match entry {
+                    Some(entry) => format!("{}/{}", root.trim_end_matches(['/', '\\']), entry),
+                    None => root,
+                };
+                match name {
+                    Some(name) => format!("{}={}", name, path),
+                    None => path,
+                }
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize)]
 struct BookMetaPreprocessorRustDoc {
-    crates: Vec<String>,
+    crates: Vec<CrateEntry>,
+    #[serde(default)]
+    cfg: Vec<String>,
+    #[serde(default)]
+    cfg_notes: bool,
+    #[serde(default)]
+    section_level: Option<u32>,
+    cache_dir: Option<PathBuf>,
+    #[serde(default)]
+    drop_sections: Vec<String>,
+    #[serde(default)]
+    only_sections: Vec<String>,
+    source_link_base: Option<String>,
+    link_base: Option<String>,
+    wrap_prefix: Option<String>,
+    wrap_suffix: Option<String>,
+    #[serde(default)]
+    playground_examples: bool,
+    #[serde(default)]
+    visibility: VisibilityFilter,
+    #[serde(default)]
+    deprecation_notices: bool,
+    #[serde(default)]
+    must_use_notices: bool,
+    #[serde(default)]
+    signature_fallback: bool,
+    #[serde(default)]
+    expand_field_types: bool,
+    #[serde(default)]
+    target_dirs: Vec<String>,
+    #[serde(default)]
+    keep_blank_lines: bool,
+    #[serde(default)]
+    preludes: Vec<String>,
+    #[serde(default)]
+    reexport_depth: Option<usize>,
+    #[serde(default)]
+    fn_body_items: bool,
+    #[serde(default)]
+    max_scan_files: Option<usize>,
+    #[serde(default)]
+    docs_rs: Vec<String>,
+    #[serde(default)]
+    external_crates: Vec<String>,
+    #[serde(default)]
+    method_order: MethodOrder,
+    #[serde(default)]
+    ignore_directive_typos: bool,
+}
+
+/// The environment variable that picks a profile, ahead of the `profile` option.
+const PROFILE_VAR: &str = "MDBOOK_RUSTDOC_PROFILE";
+
+impl BookMetaPreprocessorRustDoc {
+    /// Read the `[preprocessor.rustdoc]` table from the book's config, with the profile picked by
+    /// the environment merged over it.
+    fn from_preprocessor_table(table: Option<&toml::value::Table>) -> Result<Self> {
+        let profile = env::var(PROFILE_VAR)
+            .ok()
+            .filter(|profile| !profile.is_empty());
+        Self::from_table_with_profile(table, profile.as_deref())
+    }
+
+    /// Read the `[preprocessor.rustdoc]` table, with the options of the named profile under
+    /// `profiles` replacing the ones they share with it. Without a name, the table's own
+    /// `profile` option picks one, if it is set. The most common configuration mistakes are
+    /// checked first so that they get a helpful error.
+    fn from_table_with_profile(
+        table: Option<&toml::value::Table>,
+        profile: Option<&str>,
+    ) -> Result<Self> {
+        let mut table = table
+            .ok_or_else(|| anyhow!("missing `[preprocessor.rustdoc]` table in book.toml"))?
+            .clone();
+        let profiles = match table.remove("profiles") {
+            None => toml::value::Table::new(),
+            Some(toml::Value::Table(profiles)) => profiles,
+            Some(_) => bail!(
+                "`profiles` under `[preprocessor.rustdoc]` in book.toml must be a table of profiles"
+            ),
+        };
+        let named = match table.remove("profile") {
+            None => None,
+            Some(toml::Value::String(name)) => Some(name),
+            Some(_) => {
+                bail!("`profile` under `[preprocessor.rustdoc]` in book.toml must be a string")
+            }
+        };
+        if let Some(name) = profile.or(named.as_deref()) {
+            match profiles.get(name) {
+                Some(toml::Value::Table(overrides)) => {
+                    info!("Using the rustdoc profile {}", name);
+                    table.extend(overrides.clone());
+                }
+                Some(_) => bail!(
+                    "`profiles.{}` under `[preprocessor.rustdoc]` in book.toml must be a table",
+                    name
+                ),
+                None => {
+                    let mut known: Vec<_> = profiles.keys().map(|k| format!("`{}`", k)).collect();
+                    known.sort();
+                    bail!(
+                        "Unknown rustdoc profile `{}`, book.toml defines {}",
+                        name,
+                        if known.is_empty() {
+                            "none".to_string()
+                        } else {
+                            known.join(", ")
+                        }
+                    );
+                }
+            }
+        }
+
+        match table.get("crates") {
+            None => bail!("missing `crates` under `[preprocessor.rustdoc]` in book.toml"),
+            Some(toml::Value::Array(crates)) if crates.is_empty() => bail!(
+                "`crates` under `[preprocessor.rustdoc]` in book.toml is empty, \
+                 add at least one crate like `crates = [\"my_crate=../my-crate\"]`"
+            ),
+            Some(toml::Value::Array(_)) => {}
+            Some(_) => bail!(
+                "`crates` under `[preprocessor.rustdoc]` in book.toml must be a list of strings \
+                 or tables"
+            ),
+        }
+
+        toml::Value::Table(table)
+            .try_into()
+            .context("Invalid `[preprocessor.rustdoc]` table in book.toml")
+    }
 }
 
 impl mdbook::preprocess::Preprocessor for RustDocPreprocessor {
     fn name(&self) -> &str {
-        "rust-doc"
+        "rustdoc"
     }
 
     fn run(&self, ctx: &PreprocessorContext, mut book: Book) -> Result<Book> {
-        let book_meta_toml =
-            std::fs::read_to_string(ctx.root.join("book.toml")).context("Opening book.toml")?;
-        let book_meta: BookMeta = toml::from_str(&book_meta_toml).context("parsing book.toml")?;
-        let crate_roots = CrateRoots::try_from(book_meta.preprocessor.rustdoc.crates)
-            .context("Reading rustdoc crates config")?;
+        // One resolver expands every chapter, so each source file is only parsed once per build,
+        // however many directives refer to it.
+        let (resolver, expand_options) = self.setup(&ctx.root, &ctx.config)?;
 
-        book.for_each_mut(|item| Self::process_item(&crate_roots, item).unwrap());
+        book.for_each_mut(|item| Self::process_item(&resolver, &expand_options, item).unwrap());
+        resolver.save_disk_cache()?;
+        report_build(&resolver);
         Ok(book)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        format_item_info, parse_preludes, BookMetaPreprocessorRustDoc, CrateEntry, Opts,
+        QueryFormat, SubCommand,
+    };
+    use clap::{Clap, ErrorKind};
+    use mdbook_rust_doc::{ItemInfo, VisibilityFilter};
+    use proc_macro2::LineColumn;
+    use std::path::{Path, PathBuf};
+
+    fn read_config(book_toml: &str) -> anyhow::Result<BookMetaPreprocessorRustDoc> {
+        let config: mdbook::Config = book_toml.parse().unwrap();
+        BookMetaPreprocessorRustDoc::from_preprocessor_table(config.get_preprocessor("rustdoc"))
+    }
+
+    fn config_error(book_toml: &str) -> String {
+        read_config(book_toml).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn test_missing_preprocessor_table() {
+        assert_eq!(
+            config_error("[book]\ntitle = \"Test\""),
+            "missing `[preprocessor.rustdoc]` table in book.toml"
+        );
+    }
+
+    #[test]
+    fn test_missing_crates() {
+        assert_eq!(
+            config_error("[preprocessor.rustdoc]\ncrate = [\"test-crate\"]"),
+            "missing `crates` under `[preprocessor.rustdoc]` in book.toml"
+        );
+    }
+
+    #[test]
+    fn test_empty_crates() {
+        assert!(config_error("[preprocessor.rustdoc]\ncrates = []").contains("is empty"));
+    }
+
+    #[test]
+    fn test_visibility() {
+        let config = read_config(
+            "[preprocessor.rustdoc]\ncrates = [\"test-crate\"]\nvisibility = \"public\"",
+        )
+        .unwrap();
+        assert_eq!(config.visibility, VisibilityFilter::Public);
+        assert!(config_error(
+            "[preprocessor.rustdoc]\ncrates = [\"test-crate\"]\nvisibility = \"crate\""
+        )
+        .contains("Invalid `[preprocessor.rustdoc]` table"));
+    }
+
+    #[test]
+    fn test_query_formats() {
+        let info = ItemInfo {
+            kind: "struct",
+            docs: "A crab.".to_string(),
+            file: PathBuf::from("/src/crustaceans.rs"),
+            start: LineColumn { line: 3, column: 0 },
+            end: LineColumn { line: 9, column: 1 },
+            edition: Some("2021".to_string()),
+        };
+        assert_eq!(
+            format_item_info(&info, QueryFormat::Text).unwrap(),
+            "struct at /src/crustaceans.rs:3\n\nA crab."
+        );
+        let json: serde_json::Value =
+            serde_json::from_str(&format_item_info(&info, QueryFormat::Json).unwrap()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "kind": "struct",
+                "docs": "A crab.",
+                "file": "/src/crustaceans.rs",
+                "start": { "line": 3, "column": 0 },
+                "end": { "line": 9, "column": 1 },
+                "edition": "2021",
+            })
+        );
+        assert!("yaml".parse::<QueryFormat>().is_err());
+    }
+
+    #[test]
+    fn test_command_line() {
+        let opts = Opts::try_parse_from(["mdbook-rust-doc", "query", "crabs::Crab"]).unwrap();
+        assert!(matches!(
+            opts.cmd,
+            Some(SubCommand::Query { path, format: QueryFormat::Text, .. }) if path == "crabs::Crab"
+        ));
+        let args = [
+            "mdbook-rust-doc",
+            "query",
+            "crabs::Crab",
+            "--format",
+            "json",
+        ];
+        let opts = Opts::try_parse_from(args).unwrap();
+        assert!(matches!(
+            opts.cmd,
+            Some(SubCommand::Query {
+                format: QueryFormat::Json,
+                ..
+            })
+        ));
+        let opts = Opts::try_parse_from(["mdbook-rust-doc", "check"]).unwrap();
+        assert!(matches!(opts.cmd, Some(SubCommand::Check { dir }) if dir == Path::new(".")));
+        assert!(Opts::try_parse_from(["mdbook-rust-doc"])
+            .unwrap()
+            .cmd
+            .is_none());
+
+        let args = [
+            "mdbook-rust-doc",
+            "query",
+            "crabs::Crab",
+            "--format",
+            "yaml",
+        ];
+        let err = Opts::try_parse_from(args).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::InvalidValue);
+        let err = Opts::try_parse_from(["mdbook-rust-doc", "supports"]).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::MissingRequiredArgument);
+        let err = Opts::try_parse_from(["mdbook-rust-doc", "--version"]).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::DisplayVersion);
+        assert!(err.to_string().contains(env!("CARGO_PKG_VERSION")));
+    }
+
+    #[test]
+    fn test_preludes() {
+        let entries = vec![
+            "crabs=prelude".to_string(),
+            "lobsters = common::prelude".to_string(),
+        ];
+        let preludes = parse_preludes(&entries).unwrap();
+        assert_eq!(preludes["crabs"].to_string(), "prelude");
+        assert_eq!(preludes["lobsters"].to_string(), "common::prelude");
+
+        let err = parse_preludes(&["crabs".to_string()]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Expected an entry like `my_crate=prelude`, found `crabs`"
+        );
+        let entries = vec!["crabs=prelude".to_string(), "crabs=other".to_string()];
+        let err = parse_preludes(&entries).unwrap_err();
+        assert_eq!(err.to_string(), "The crate crabs has more than one prelude");
+    }
+
+    #[test]
+    fn test_profiles() {
+        let book_toml = "[preprocessor.rustdoc]\n\
+                         crates = [\"test-crate\"]\n\
+                         visibility = \"all\"\n\
+                         cfg = [\"internal\"]\n\
+                         [preprocessor.rustdoc.profiles.public]\n\
+                         visibility = \"public\"\n\
+                         deprecation_notices = true";
+        let config: mdbook::Config = book_toml.parse().unwrap();
+        let table = config.get_preprocessor("rustdoc");
+        let read = |profile| BookMetaPreprocessorRustDoc::from_table_with_profile(table, profile);
+
+        let internal = read(None).unwrap();
+        assert_eq!(internal.visibility, VisibilityFilter::All);
+        assert!(!internal.deprecation_notices);
+        let public = read(Some("public")).unwrap();
+        assert_eq!(public.visibility, VisibilityFilter::Public);
+        assert!(public.deprecation_notices);
+        // Options the profile doesn't set keep their values from the table.
+        assert_eq!(public.cfg, vec!["internal".to_string()]);
+
+        assert_eq!(
+            read(Some("secret")).unwrap_err().to_string(),
+            "Unknown rustdoc profile `secret`, book.toml defines `public`"
+        );
+
+        // The `profile` option picks one when the environment doesn't.
+        let with_default = book_toml.replace("cfg = ", "profile = \"public\"\ncfg = ");
+        let config: mdbook::Config = with_default.parse().unwrap();
+        let table = config.get_preprocessor("rustdoc");
+        let config = BookMetaPreprocessorRustDoc::from_table_with_profile(table, None).unwrap();
+        assert_eq!(config.visibility, VisibilityFilter::Public);
+    }
+
+    #[test]
+    fn test_crate_tables() {
+        let config = read_config(
+            "[preprocessor.rustdoc]\n\
+             crates = [\n\
+                 { name = \"claws\", root = \"../claws/\", entry = \"lib/claws.rs\" },\n\
+                 { root = \"../shell\" },\n\
+                 \"../legs\",\n\
+             ]",
+        )
+        .unwrap();
+        let crates: Vec<_> = config
+            .crates
+            .into_iter()
+            .map(CrateEntry::into_config_string)
+            .collect();
+        assert_eq!(
+            crates,
+            ["claws=../claws/lib/claws.rs", "../shell", "../legs"]
+        );
+        assert!(
+            config_error("[preprocessor.rustdoc]\ncrates = [{ name = \"claws\" }]")
+                .contains("Invalid `[preprocessor.rustdoc]` table")
+        );
+    }
+
+    #[test]
+    fn test_valid_config() {
+        let config = read_config("[preprocessor.rustdoc]\ncrates = [\"test-crate\"]").unwrap();
+        assert_eq!(
+            config.crates,
+            vec![CrateEntry::Path("test-crate".to_string())]
+        );
+    }
+}
@@ -0,0 +1,5892 @@
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use lazy_static::lazy_static;
+use log::warn;
+use proc_macro2::{LineColumn, Span, TokenStream};
+use quote::{quote, ToTokens};
+use regex::Regex;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    cell::{Cell, RefCell},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+    rc::Rc,
+    str::FromStr,
+    string::ToString,
+};
+use syn::{
+    parse::{ParseStream, Parser},
+    punctuated::Punctuated,
+    spanned::Spanned,
+    Attribute, Block, Expr, Field, Fields, FieldsNamed, FieldsUnnamed, Ident, Item, ItemEnum,
+    ItemImpl, ItemMod, ItemStruct, ItemTrait, ItemUnion, Lit, LitStr, Meta, NestedMeta, Stmt,
+    TraitItem, Type, TypeParamBound, UseTree, Variant, Visibility,
+};
+
+#[cfg(feature = "docs-rs")]
+use crate::docs_rs::{DocsRsCrate, DocsRsIndex};
+use crate::{
+    cache::DiskCache,
+    cfg::{self, CfgSet},
+    domain::{docs_url, join_portable, CrateRoot, CrateRoots, RustPath},
+    error::ResolveError,
+};
+
+/// How many levels of supertraits are searched for the items of a trait.
+const MAX_SUPERTRAIT_DEPTH: usize = 8;
+
+/// How many `use` declarations are followed to find what a name in a module refers to, so that
+/// globs that import each other don't loop.
+const MAX_IMPORT_DEPTH: usize = 16;
+
+/// Options that change how a [`Resolver`] turns paths into documentation.
+#[derive(Debug, Clone)]
+pub struct ResolveOptions {
+    /// The `cfg` options used to decide which `#[cfg_attr(...)]` docs to include. If any are set,
+    /// modules behind a `#[cfg(...)]` that doesn't hold for them are treated as missing.
+    pub cfg: CfgSet,
+    /// Whether to start the docs of items with `#[doc(cfg(...))]` with a note saying which `cfg`
+    /// options they are available under, like docs.rs does.
+    pub cfg_notes: bool,
+    /// Which items can be found, by their visibility.
+    pub visibility: VisibilityFilter,
+    /// Whether to start the docs of `#[deprecated]` items with a quoted deprecation notice.
+    pub deprecation_notices: bool,
+    /// Whether to start the docs of `#[must_use]` items with a quoted notice, including the
+    /// attribute's message.
+    pub must_use_notices: bool,
+    /// Whether to show the signature of items that have no docs, instead of nothing.
+    pub signature_fallback: bool,
+    /// Whether to follow the docs of a field with the first paragraph of the docs of its type,
+    /// if the type is an item in one of the crates.
+    pub expand_field_types: bool,
+    /// Directories of extra targets in each crate, like `examples`, whose files can be referred to
+    /// as modules, like `my_crate::examples::basic` for `examples/basic.rs`.
+    pub target_dirs: Vec<String>,
+    /// Whether to keep the blank lines at the start and end of docs, like the ones left by a doc
+    /// comment that starts with an empty `///` line. They are removed by default.
+    pub keep_blank_lines: bool,
+    /// The module of each crate, by crate name, that paths fall back to when they aren't found as
+    /// written, like `prelude` for a `my_crate::prelude` module.
+    pub preludes: BTreeMap<String, RustPath>,
+    /// How many `pub use` re-exports in a row to follow for paths that aren't found as written,
+    /// so that an item can be referred to by the public path shown in its crate's published docs.
+    /// `0` turns re-exports off.
+    pub reexport_depth: usize,
+    /// Whether paths can go inside of function and method bodies, like `my_crate::build::Config`
+    /// for a `Config` struct declared in `fn build`. Off by default, since it means looking
+    /// through function bodies for paths that aren't found.
+    pub fn_body_items: bool,
+    /// The most source files that a single lookup can parse, so that a path that sets off a scan
+    /// of a huge crate fails instead of taking forever. Files that earlier lookups already parsed
+    /// don't count.
+    pub max_scan_files: usize,
+    /// Crates that aren't in the resolver's crates, whose docs are downloaded from docs.rs
+    /// instead. Only the docs of items with paths of their own are found, and failed downloads are
+    /// warned about and treated as missing items.
+    #[cfg(feature = "docs-rs")]
+    pub docs_rs: Vec<DocsRsCrate>,
+}
+
+impl Default for ResolveOptions {
+    fn default() -> Self {
+        Self {
+            cfg: CfgSet::default(),
+            cfg_notes: false,
+            visibility: VisibilityFilter::default(),
+            deprecation_notices: false,
+            must_use_notices: false,
+            signature_fallback: false,
+            expand_field_types: false,
+            target_dirs: vec![],
+            keep_blank_lines: false,
+            preludes: BTreeMap::new(),
+            reexport_depth: 4,
+            fn_body_items: false,
+            max_scan_files: 10_000,
+            #[cfg(feature = "docs-rs")]
+            docs_rs: vec![],
+        }
+    }
+}
+
+/// The order that [`Resolver::resolve_methods`] lists the methods of a type in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MethodOrder {
+    /// The order they are written in, one impl block after another.
+    #[default]
+    Source,
+    /// Sorted by name.
+    Alphabetical,
+}
+
+impl FromStr for MethodOrder {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "source" => Ok(Self::Source),
+            "alphabetical" => Ok(Self::Alphabetical),
+            _ => bail!(
+                "Unknown method order `{}`, expected `source` or `alphabetical`",
+                s
+            ),
+        }
+    }
+}
+
+/// Which items a [`Resolver`] can find, based on their visibility.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VisibilityFilter {
+    /// Find every item, whatever its visibility. Useful for documenting a crate's internals.
+    #[default]
+    All,
+    /// Only find items that are `pub`, treating everything else as if it doesn't exist. Items
+    /// that are only visible inside the crate, like `pub(crate)`, are not found.
+    Public,
+}
+
+impl VisibilityFilter {
+    /// Whether an item with the visibility `vis` can be found.
+    #[must_use]
+    pub fn allows(self, vis: &Visibility) -> bool {
+        match self {
+            Self::All => true,
+            Self::Public => matches!(vis, Visibility::Public(_)),
+        }
+    }
+}
+
+/// How many directives were expanded with a [`Resolver`], for a summary at the end of a build.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct DirectiveCounts {
+    /// Every directive that was expanded.
+    pub expanded: usize,
+    /// The directives whose items were found.
+    pub resolved: usize,
+    /// The directives that were rendered as a "No documentation found" placeholder, since there
+    /// was no item at their path.
+    pub placeholders: usize,
+}
+
+/// An expanded chapter saved in the disk cache, along with what expanding it added to the
+/// resolver's report, to add again when it is reused.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedChapter {
+    content: String,
+    counts: DirectiveCounts,
+    undocumented: BTreeSet<String>,
+    /// The keys of the cache entries that expanding the chapter used, which are kept for as long
+    /// as the chapter is reused.
+    entries: Vec<String>,
+}
+
+/// Where an item is defined in the source code of its crate.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SourceLocation {
+    /// The file the item is in, relative to the crate's directory.
+    pub file: PathBuf,
+    /// The 1-based line that the item's name is on.
+    pub line: usize,
+}
+
+/// Everything known about an item found by [`Resolver::resolve_info`], for tools that work with
+/// its source code, like editors that jump from a directive to the item.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ItemInfo {
+    /// What sort of item it is, like `struct`, `fn`, `method`, or `field`.
+    pub kind: &'static str,
+    /// The docs of the item, as [`Resolver::resolve`] would return them.
+    pub docs: String,
+    /// The absolute path of the file the item is in.
+    pub file: PathBuf,
+    /// Where the item starts, including its attributes. Lines are 1-based and columns are 0-based.
+    #[serde(serialize_with = "serialize_line_column")]
+    pub start: LineColumn,
+    /// Where the item ends.
+    #[serde(serialize_with = "serialize_line_column")]
+    pub end: LineColumn,
+    /// The Rust edition of the item's crate, as given by [`CrateRoot::edition`].
+    pub edition: Option<String>,
+}
+
+/// Write a [`LineColumn`], which doesn't implement `Serialize` itself, as `{"line": 1, "column": 0}`.
+fn serialize_line_column<S: serde::Serializer>(
+    position: &LineColumn,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    use serde::ser::SerializeStruct;
+    let mut state = serializer.serialize_struct("LineColumn", 2)?;
+    state.serialize_field("line", &position.line)?;
+    state.serialize_field("column", &position.column)?;
+    state.end()
+}
+
+/// An item found while resolving a path, with the parts of it that docs are rendered from.
+#[derive(Debug, Clone)]
+struct FoundItem {
+    attrs: Vec<Attribute>,
+    /// The docs from `attrs`, with the files named by their `include_str!`s read in. This is
+    /// filled in by [`Resolver::find_item`].
+    docs: Vec<DocFragment>,
+    /// The name, type, and value of the item, if it is a free or associated `const` or a `static`.
+    const_value: Option<(Ident, Type, Expr)>,
+    /// The type that the item names, if it is a type alias.
+    alias_of: Option<Type>,
+    /// The type of the item, if it is a field.
+    field_type: Option<Type>,
+    /// The file the item is in. This is filled in by [`Resolver::find_item_in_file`] once the
+    /// item has been found.
+    file: Option<PathBuf>,
+    line: usize,
+    /// The item's signature, like `pub fn cook(crab: Crab) -> CookedCrab`, without its body.
+    signature: Option<String>,
+    /// Where the whole item starts and ends in its file, including its attributes.
+    extent: Option<(LineColumn, LineColumn)>,
+    /// What sort of item it is, for [`ItemInfo::kind`].
+    kind: Option<&'static str>,
+    /// The default value or type of a trait's associated `const` or `type`, like `4` or `u32`.
+    default: Option<String>,
+    /// The path the item was found at, after following re-exports and preludes. This is filled
+    /// in by [`Resolver::locate_item_as_written`].
+    path: Option<RustPath>,
+}
+
+impl FoundItem {
+    /// An item with `attrs` whose name (or other defining part) is at `span`.
+    fn new(attrs: Vec<Attribute>, span: Span) -> Self {
+        Self {
+            attrs,
+            docs: vec![],
+            const_value: None,
+            alias_of: None,
+            field_type: None,
+            file: None,
+            line: span.start().line,
+            signature: None,
+            extent: None,
+            kind: None,
+            default: None,
+            path: None,
+        }
+    }
+
+    /// Record what sort of item this is, unless a more specific kind was already found.
+    fn with_kind(mut self, kind: &'static str) -> Self {
+        self.kind.get_or_insert(kind);
+        self
+    }
+
+    /// Record that the whole item covers `span`, unless a more specific extent was already found.
+    fn with_extent(mut self, span: Span) -> Self {
+        self.extent.get_or_insert((span.start(), span.end()));
+        self
+    }
+
+    fn with_signature(self, signature: TokenStream) -> Self {
+        Self {
+            signature: Some(signature_to_string(&signature)),
+            ..self
+        }
+    }
+
+    fn with_default(self, default: String) -> Self {
+        Self {
+            default: Some(default),
+            ..self
+        }
+    }
+
+    fn with_field_type(self, field_type: Type) -> Self {
+        Self {
+            field_type: Some(field_type),
+            ..self
+        }
+    }
+}
+
+/// Resolves [`RustPath`]s to documentation, caching parsed source files between lookups.
+///
+/// Reuse a single `Resolver` when looking up many paths, so that each source file is only read and
+/// parsed once.
+#[derive(Debug)]
+pub struct Resolver {
+    crates: CrateRoots,
+    options: ResolveOptions,
+    parse_cache: RefCell<HashMap<PathBuf, Rc<syn::File>>>,
+    /// The impl blocks and traits of each crate, keyed by the crate's root file.
+    impl_cache: RefCell<HashMap<PathBuf, Rc<ImplIndex>>>,
+    /// The `pub use` re-exports of each crate, keyed by the crate's root file.
+    reexport_cache: RefCell<HashMap<PathBuf, Rc<ReexportIndex>>>,
+    disk_cache: Option<DiskCache>,
+    /// The source files read since the current cached lookup started.
+    read_files: RefCell<HashSet<PathBuf>>,
+    /// How many source files the current lookup has parsed, for
+    /// [`ResolveOptions::max_scan_files`], or `None` outside of a lookup.
+    files_parsed: Cell<Option<usize>>,
+    /// The errors for modules that were skipped while scanning a whole crate, so that each one is
+    /// only warned about once.
+    skipped_modules: RefCell<HashSet<String>>,
+    /// The last module that the search for an item skipped because of its `#[cfg(...)]`, and the
+    /// condition it needs, to explain why the item wasn't found.
+    cfg_skipped: RefCell<Option<(String, String)>>,
+    /// The paths that were found to have no docs, for [`Resolver::undocumented`].
+    undocumented: RefCell<BTreeSet<String>>,
+    /// The directives expanded so far, for [`Resolver::directive_counts`].
+    directive_counts: Cell<DirectiveCounts>,
+    /// Whether a docs.rs download failed since the current cached chapter started, so that the
+    /// chapter isn't saved and the download is tried again in the next run.
+    docs_rs_failed: Cell<bool>,
+    /// The docs downloaded from docs.rs for each crate, or `None` if the download failed.
+    #[cfg(feature = "docs-rs")]
+    docs_rs_cache: RefCell<HashMap<String, Option<Rc<DocsRsIndex>>>>,
+}
+
+/// An impl block, with the file and module it is in.
+#[derive(Debug, Clone)]
+struct CrateImpl {
+    file: PathBuf,
+    /// The path of the module inside the crate, which the paths written in the impl are read from.
+    module: Vec<String>,
+    item: ItemImpl,
+}
+
+type FileImpls = Vec<CrateImpl>;
+
+/// The impl blocks and traits of a crate, by the paths inside the crate of the items they are
+/// for, along with what was needed to read those paths from where they are written.
+///
+/// Paths are followed through the names each module declares and the `use` declarations it has,
+/// so types and traits with the same name in different modules are kept apart. Ones that lead
+/// out of the crate, like `std::fmt::Display`, or to generic parameters aren't indexed.
+#[derive(Debug, Default)]
+struct ImplIndex {
+    /// The impl blocks of each type.
+    impls: HashMap<Vec<String>, FileImpls>,
+    /// Each trait, with the file it is in.
+    traits: HashMap<Vec<String>, (PathBuf, ItemTrait)>,
+    /// The names of the items declared in each module.
+    declared: HashMap<Vec<String>, HashSet<String>>,
+    /// The `use` declarations of every module, public or not.
+    imports: ReexportIndex,
+}
+
+impl ImplIndex {
+    /// The path inside the crate of the item that `path`, written in the module at `module`,
+    /// refers to, or `None` if it isn't an item of the crate.
+    fn resolve(&self, module: &[String], path: &[String]) -> Option<Vec<String>> {
+        let (first, rest) = path.split_first()?;
+        match first.as_str() {
+            "crate" | "self" | "super" => self.follow(vec![], &use_target(module, path)?, 0),
+            _ => self.follow(self.lookup(module, first, 0)?, rest, 0),
+        }
+    }
+
+    /// The impl blocks of the type at `path`, and of the type it names if it is an alias of
+    /// `alias_of`.
+    fn type_impls(&self, path: &RustPath, alias_of: Option<&Type>) -> Vec<&CrateImpl> {
+        let segments: Vec<String> = path.segments().skip(1).map(String::from).collect();
+        let module = &segments[..segments.len().saturating_sub(1)];
+        let alias_path = alias_of
+            .and_then(type_path)
+            .and_then(|alias_path| self.resolve(module, &alias_path));
+        std::iter::once(segments.clone())
+            .chain(alias_path)
+            .filter_map(|type_path| self.impls.get(&type_path))
+            .flatten()
+            .collect()
+    }
+
+    /// The path of the trait that `the_impl` implements, if it is a trait of the crate.
+    fn impl_trait(&self, the_impl: &CrateImpl) -> Option<Vec<String>> {
+        let (_, trait_path, _) = the_impl.item.trait_.as_ref()?;
+        self.resolve(&the_impl.module, &path_segments(trait_path))
+    }
+
+    /// Follow `rest` from the item at `start`, one module at a time. Once the path reaches an
+    /// item that isn't a module, like an enum, the rest is inside of it.
+    fn follow(&self, start: Vec<String>, rest: &[String], depth: usize) -> Option<Vec<String>> {
+        let mut current = start;
+        for (idx, segment) in rest.iter().enumerate() {
+            if !self.declared.contains_key(&current) {
+                current.extend_from_slice(&rest[idx..]);
+                break;
+            }
+            current = self.lookup(&current, segment, depth)?;
+        }
+        Some(current)
+    }
+
+    /// The path of the item that `name` refers to in the module at `module`: one declared there,
+    /// one it imports by name, or one that its globs bring in.
+    fn lookup(&self, module: &[String], name: &str, depth: usize) -> Option<Vec<String>> {
+        if depth > MAX_IMPORT_DEPTH {
+            return None;
+        }
+        let mut path = module.to_vec();
+        path.push(name.to_string());
+        if self.declared.get(module)?.contains(name) {
+            return Some(path);
+        }
+        if let Some(target) = self.imports.items.get(&path) {
+            return self.follow(vec![], target, depth + 1);
+        }
+        self.imports
+            .globs
+            .get(module)
+            .into_iter()
+            .flatten()
+            .find_map(|glob| {
+                let glob = self.follow(vec![], glob, depth + 1)?;
+                self.lookup(&glob, name, depth + 1)
+            })
+    }
+}
+
+/// The `pub use` re-exports of a crate, as paths inside the crate without its name.
+#[derive(Debug, Default)]
+struct ReexportIndex {
+    /// The path of the item each named re-export refers to, by the public path it gives it.
+    items: HashMap<Vec<String>, Vec<String>>,
+    /// The modules whose items are re-exported by `pub use module::*`, by the module they are
+    /// re-exported into. Globs of modules whose items were listed by
+    /// [`expand_globs`](Self::expand_globs) are in `items` instead.
+    globs: HashMap<Vec<String>, Vec<Vec<String>>>,
+}
+
+impl ReexportIndex {
+    /// Add the re-exports of the `use` tree `tree` in the module at `module_path`, where `prefix`
+    /// holds the segments of the tree that lead up to it.
+    fn add(&mut self, module_path: &[String], tree: &UseTree, prefix: &mut Vec<String>) {
+        match tree {
+            UseTree::Path(use_path) => {
+                prefix.push(use_path.ident.to_string());
+                self.add(module_path, &use_path.tree, prefix);
+                prefix.pop();
+            }
+            UseTree::Name(name) => self.add_item(module_path, prefix, &name.ident, &name.ident),
+            UseTree::Rename(rename) => {
+                self.add_item(module_path, prefix, &rename.ident, &rename.rename);
+            }
+            UseTree::Glob(_) => {
+                if let Some(target) = use_target(module_path, prefix) {
+                    let globs = self.globs.entry(module_path.to_vec()).or_default();
+                    globs.push(target);
+                }
+            }
+            UseTree::Group(group) => {
+                for tree in &group.items {
+                    self.add(module_path, tree, prefix);
+                }
+            }
+        }
+    }
+
+    fn add_item(&mut self, module_path: &[String], prefix: &[String], ident: &Ident, name: &Ident) {
+        // `use module::{self}` refers to `module` itself.
+        let mut path = prefix.to_vec();
+        if ident != "self" {
+            path.push(ident.to_string());
+        }
+        let name = match path.last() {
+            // Traits imported as `_` only bring their methods into scope.
+            _ if name == "_" => return,
+            Some(last) if name == "self" => last.clone(),
+            _ => name.to_string(),
+        };
+        if let Some(target) = use_target(module_path, &path) {
+            let mut public_path = module_path.to_vec();
+            public_path.push(name);
+            self.items.entry(public_path).or_insert(target);
+        }
+    }
+
+    /// Add each public item of the modules that globs re-export to `items`, under the module it
+    /// is re-exported into, given the public items of every module in `modules`. Items re-exported
+    /// into those modules are included too, so chains of globs are followed up to `depth` modules
+    /// deep, and cycles of globs stop once they have nothing new to add. Items that a module
+    /// defines or re-exports by name take precedence over the ones its globs bring in, like they
+    /// do in Rust. Globs of things that aren't modules in the crate, like enums, are kept as they
+    /// are.
+    fn expand_globs(&mut self, modules: &HashMap<Vec<String>, Vec<String>>, depth: usize) {
+        for _ in 0..depth {
+            let mut added = vec![];
+            for (into, targets) in &self.globs {
+                let own_names = modules.get(into).map_or(&[][..], Vec::as_slice);
+                for target in targets
+                    .iter()
+                    .filter(|target| modules.contains_key(*target))
+                {
+                    let reexported = self
+                        .items
+                        .keys()
+                        .filter(|path| path.len() == target.len() + 1 && path.starts_with(target))
+                        .filter_map(|path| path.last());
+                    for name in modules[target].iter().chain(reexported) {
+                        let mut public_path = into.clone();
+                        public_path.push(name.clone());
+                        if own_names.contains(name) || self.items.contains_key(&public_path) {
+                            continue;
+                        }
+                        let mut target_path = target.clone();
+                        target_path.push(name.clone());
+                        added.push((public_path, target_path));
+                    }
+                }
+            }
+            if added.is_empty() {
+                break;
+            }
+            for (public_path, target) in added {
+                self.items.entry(public_path).or_insert(target);
+            }
+        }
+        for targets in self.globs.values_mut() {
+            targets.retain(|target| !modules.contains_key(target));
+        }
+    }
+
+    /// The paths that `path` could refer to after following one re-export, either of `path`
+    /// itself or of one of the modules it is in.
+    fn targets(&self, path: &[String]) -> Vec<Vec<String>> {
+        let mut targets = vec![];
+        for len in (1..=path.len()).rev() {
+            if let Some(target) = self.items.get(&path[..len]) {
+                targets.push([target.as_slice(), &path[len..]].concat());
+            }
+        }
+        for len in (0..path.len()).rev() {
+            for module in self.globs.get(&path[..len]).into_iter().flatten() {
+                targets.push([module.as_slice(), &path[len..]].concat());
+            }
+        }
+        targets
+    }
+}
+
+/// Called with the file, path, and items of each module visited by [`Resolver::walk_modules`].
+type ModuleVisitor<'a> = dyn FnMut(&Path, &[String], &[Item]) + 'a;
+
+impl Resolver {
+    /// Create a resolver for `crates` with the default options.
+    #[must_use]
+    pub fn new(crates: CrateRoots) -> Self {
+        Self::with_options(crates, ResolveOptions::default())
+    }
+
+    /// Create a resolver for `crates` that uses `options`.
+    #[must_use]
+    pub fn with_options(crates: CrateRoots, options: ResolveOptions) -> Self {
+        Self {
+            crates,
+            options,
+            parse_cache: RefCell::new(HashMap::new()),
+            impl_cache: RefCell::new(HashMap::new()),
+            reexport_cache: RefCell::new(HashMap::new()),
+            disk_cache: None,
+            read_files: RefCell::new(HashSet::new()),
+            files_parsed: Cell::new(None),
+            skipped_modules: RefCell::new(HashSet::new()),
+            cfg_skipped: RefCell::new(None),
+            undocumented: RefCell::new(BTreeSet::new()),
+            directive_counts: Cell::new(DirectiveCounts::default()),
+            docs_rs_failed: Cell::new(false),
+            #[cfg(feature = "docs-rs")]
+            docs_rs_cache: RefCell::new(HashMap::new()),
+        }
+    }
+
+    #[must_use]
+    pub fn crates(&self) -> &CrateRoots {
+        &self.crates
+    }
+
+    #[must_use]
+    pub fn options(&self) -> &ResolveOptions {
+        &self.options
+    }
+
+    /// Load the docstring for the item given by `path`.
+    ///
+    /// Items that are found but have no docs are warned about, and can be listed afterwards with
+    /// [`Resolver::undocumented`].
+    ///
+    /// # Errors
+    /// If the path cannot be found, a descriptive [`anyhow`] will be returned.
+    pub fn resolve(&self, path: &RustPath) -> Result<Option<String>> {
+        #[cfg(feature = "docs-rs")]
+        if let Some(docs) = self.resolve_from_docs_rs(path) {
+            return Ok(docs);
+        }
+        let resolved = self.cached(format!("doc {}", path), || {
+            let path = self.crate_relative(path)?;
+            let found = self.find_item(&path)?;
+            Ok(found.map(|found| {
+                let is_documented = !self.doc_text(&found.docs).is_empty();
+                let mut docs = self.found_item_docs(&found);
+                if self.options.expand_field_types {
+                    if let Some(summary) = self.field_type_summary(&path, &found) {
+                        if !docs.is_empty() {
+                            docs.push_str("\n\n");
+                        }
+                        docs.push_str(&summary);
+                    }
+                }
+                (docs, is_documented)
+            }))
+        })?;
+        Ok(resolved.map(|(docs, is_documented)| {
+            if !is_documented && self.undocumented.borrow_mut().insert(path.to_string()) {
+                warn!("{} has no documentation", path);
+            }
+            docs
+        }))
+    }
+
+    /// Whether paths starting with `name` can be resolved, because it is one of the resolver's
+    /// crates or, with the `docs-rs` feature, one of the crates read from docs.rs.
+    #[must_use]
+    pub fn knows_crate(&self, name: &str) -> bool {
+        #[cfg(feature = "docs-rs")]
+        if self.options.docs_rs.iter().any(|krate| krate.name == name) {
+            return true;
+        }
+        self.crates.contains(name)
+    }
+
+    /// Load the docs of `path` from docs.rs, or return `None` if its crate isn't one of the
+    /// configured docs.rs crates. Local crates with the same name take priority.
+    #[cfg(feature = "docs-rs")]
+    fn resolve_from_docs_rs(&self, path: &RustPath) -> Option<Option<String>> {
+        let name = path.head_tail().0;
+        if self.crates.contains(name) {
+            return None;
+        }
+        let krate = self
+            .options
+            .docs_rs
+            .iter()
+            .find(|krate| krate.name == name)?;
+        // Errors are never cached, and neither are the chapters that hit them, so failed
+        // downloads are tried again in the next run.
+        let resolved = self.cached(format!("docs.rs {}", path), || {
+            let index = self
+                .docs_rs_index(krate)
+                .ok_or_else(|| anyhow!("Couldn't download the docs of {}", name))?;
+            Ok(index.docs(path).map(ToString::to_string))
+        });
+        if resolved.is_err() {
+            self.docs_rs_failed.set(true);
+        }
+        Some(resolved.ok().flatten())
+    }
+
+    /// The docs of `krate` from docs.rs, downloading them the first time they are needed.
+    #[cfg(feature = "docs-rs")]
+    fn docs_rs_index(&self, krate: &DocsRsCrate) -> Option<Rc<DocsRsIndex>> {
+        let mut cache = self.docs_rs_cache.borrow_mut();
+        let index = cache.entry(krate.name.clone()).or_insert_with(|| {
+            match DocsRsIndex::fetch(krate) {
+                Ok(index) => Some(Rc::new(index)),
+                Err(err) => {
+                    warn!(
+                        "Couldn't read the docs of {} {} from docs.rs, treating its items as missing: {:#}",
+                        krate.name, krate.version, err
+                    );
+                    None
+                }
+            }
+        });
+        index.clone()
+    }
+
+    /// List every item in the modules of the crate called `crate_name`, each with whether it has
+    /// any docs, for tools that measure how much of a crate is documented.
+    ///
+    /// Items are listed module by module, starting at the crate root, in the order they are
+    /// written. Like module listings, items marked `#[doc(hidden)]` or behind a `#[cfg(...)]` that
+    /// doesn't hold are left out. With [`VisibilityFilter::Public`], only `pub` items in `pub`
+    /// modules are listed. Out-of-line modules count as documented if their file starts with
+    /// `//!` docs.
+    ///
+    /// # Errors
+    /// If the crate isn't configured or its root file can't be read, a descriptive [`anyhow`]
+    /// will be returned.
+    pub fn list_items(&self, crate_name: &str) -> Result<Vec<(RustPath, bool)>> {
+        let lib_file = self.crate_root(crate_name)?.lib_file();
+        let visibility = self.options.visibility;
+        // Modules are visited before the modules inside of them, so the ones that can be listed
+        // are known by the time their items are.
+        let mut listed_modules: HashSet<Vec<String>> = HashSet::new();
+        listed_modules.insert(vec![crate_name.to_string()]);
+        let mut found = vec![];
+        let mut visit = |file_path: &Path, module_path: &[String], items: &[Item]| {
+            if !listed_modules.contains(module_path) {
+                return;
+            }
+            for item in items {
+                let (name, attrs, is_public) = match listable_item(item) {
+                    Some(listable) => listable,
+                    None => continue,
+                };
+                if (visibility == VisibilityFilter::Public && !is_public)
+                    || is_doc_hidden(attrs)
+                    || self.inactive_cfg(attrs).is_some()
+                {
+                    continue;
+                }
+                let mut item_path = module_path.to_vec();
+                item_path.push(name);
+                let mod_file = match item {
+                    Item::Mod(the_mod @ ItemMod { content: None, .. }) => {
+                        self.mod_file_path(file_path, the_mod).ok()
+                    }
+                    _ => None,
+                };
+                if let Item::Mod(_) = item {
+                    listed_modules.insert(item_path.clone());
+                }
+                found.push((item_path, attrs.to_vec(), file_path.to_owned(), mod_file));
+            }
+        };
+        self.walk_file_modules(lib_file, &mut vec![crate_name.to_string()], &mut visit)
+            .context(format!("Listing the items of {}", crate_name))?;
+
+        let mut items = vec![];
+        for (item_path, attrs, file_path, mod_file) in found {
+            let mut docs = self.read_docs(&attrs, &file_path)?;
+            if let Some(mod_file) = mod_file {
+                let inner_attrs = &self.parse_file(&mod_file)?.attrs;
+                docs.extend(self.read_docs(inner_attrs, &mod_file)?);
+            }
+            let path = item_path[1..]
+                .iter()
+                .fold(RustPath::new(&item_path[0]), RustPath::push);
+            items.push((path, !self.doc_text(&docs).is_empty()));
+        }
+        Ok(items)
+    }
+
+    /// The paths that were resolved to items without any documentation so far, sorted.
+    #[must_use]
+    pub fn undocumented(&self) -> Vec<String> {
+        self.undocumented.borrow().iter().cloned().collect()
+    }
+
+    /// How many directives have been expanded with this resolver so far, and how many of them
+    /// found their items.
+    #[must_use]
+    pub fn directive_counts(&self) -> DirectiveCounts {
+        self.directive_counts.get()
+    }
+
+    /// Count an expanded directive for [`Resolver::directive_counts`].
+    pub(crate) fn record_directive(&self, resolved: bool) {
+        let mut counts = self.directive_counts.get();
+        counts.expanded += 1;
+        if resolved {
+            counts.resolved += 1;
+        } else {
+            counts.placeholders += 1;
+        }
+        self.directive_counts.set(counts);
+    }
+
+    /// The docs of an item with `attrs`, with its deprecation and `cfg` notices if the options ask
+    /// for them.
+    ///
+    /// Items without any docs are shown as a code block of their `signature` if the options ask
+    /// for that, so that they stand out in the book.
+    fn item_docs(
+        &self,
+        attrs: &[Attribute],
+        docs: &[DocFragment],
+        signature: Option<&str>,
+    ) -> String {
+        let mut docs = self.doc_text(docs);
+        if let Some(signature) = signature.filter(|_| docs.is_empty()) {
+            if self.options.signature_fallback {
+                docs = format!("```rust\n{}\n```", signature);
+            }
+        }
+        let mut parts = vec![];
+        if self.options.deprecation_notices {
+            parts.extend(deprecation_notice(attrs));
+        }
+        if self.options.must_use_notices {
+            parts.extend(must_use_notice(attrs));
+        }
+        if self.options.cfg_notes {
+            parts.extend(cfg_notice(attrs, &self.options.cfg));
+        }
+        if !docs.is_empty() {
+            parts.push(docs);
+        }
+        parts.join("\n\n")
+    }
+
+    /// The docs of `found`, like [`item_docs`](Self::item_docs), followed by the default of a
+    /// trait's associated `const` or `type` if the options ask for signatures.
+    fn found_item_docs(&self, found: &FoundItem) -> String {
+        let docs = self.item_docs(&found.attrs, &found.docs, found.signature.as_deref());
+        match &found.default {
+            Some(default) if self.options.signature_fallback => {
+                let default = format!("Defaults to `{}`.", default);
+                if docs.is_empty() {
+                    default
+                } else {
+                    format!("{}\n\n{}", docs, default)
+                }
+            }
+            _ => docs,
+        }
+    }
+
+    /// The first paragraph of the docs of the type of `found`, a field at `path`, after the type's
+    /// name, or `None` if it isn't a field or its type isn't a documented item in the crates.
+    ///
+    /// The type is looked for in the module the field is in and then the modules around it, or at
+    /// its full path if it is written with one. Only the type's own docs are used, so the types of
+    /// its fields aren't summarized in turn.
+    fn field_type_summary(&self, path: &RustPath, found: &FoundItem) -> Option<String> {
+        let segments = type_path(found.field_type.as_ref()?)?;
+        let name = segments.last()?.clone();
+        let join = |module: RustPath, segments: &[String]| {
+            segments
+                .iter()
+                .fold(module, |path, segment| path.push(segment.as_str()))
+        };
+        let mut candidates = vec![];
+        match segments.first().map(String::as_str) {
+            Some("crate") => {
+                candidates.push(join(RustPath::new(path.head_tail().0), &segments[1..]))
+            }
+            Some(head) if segments.len() > 1 && self.crates.contains(head) => {
+                candidates.push(join(RustPath::new(head), &segments[1..]));
+            }
+            _ => {
+                // `self::` and `super::` are covered by looking in the modules around the field.
+                let relative: Vec<_> = segments
+                    .iter()
+                    .skip_while(|segment| *segment == "self" || *segment == "super")
+                    .cloned()
+                    .collect();
+                // A field's owner is a struct or an enum variant, and its module is above that.
+                let mut module = path.parent().and_then(|owner| owner.parent());
+                while let Some(current) = module {
+                    module = current.parent();
+                    candidates.push(join(current, &relative));
+                }
+            }
+        }
+        let the_type = candidates.iter().find_map(|candidate| {
+            self.find_item(candidate).ok().flatten().filter(|found| {
+                matches!(
+                    found.kind,
+                    Some("struct" | "enum" | "union" | "type" | "trait")
+                )
+            })
+        })?;
+        let docs = self.doc_text(&the_type.docs);
+        let summary: Vec<_> = docs
+            .lines()
+            .take_while(|line| !line.trim().is_empty())
+            .collect();
+        if summary.is_empty() {
+            return None;
+        }
+        Some(format!("`{}`: {}", name, summary.join("\n")))
+    }
+
+    /// The condition of the first `#[cfg(...)]` in `attrs` that doesn't hold for the configured
+    /// `cfg` options, or `None` if they all do. With no options configured, every condition holds.
+    fn inactive_cfg(&self, attrs: &[Attribute]) -> Option<Meta> {
+        if self.options.cfg.is_empty() {
+            return None;
+        }
+        attrs
+            .iter()
+            .filter(|attr| attr.path.is_ident("cfg"))
+            .filter_map(|attr| match attr.parse_meta() {
+                Ok(Meta::List(list)) if list.nested.len() == 1 => match list.nested.first() {
+                    Some(NestedMeta::Meta(condition)) => Some(condition.clone()),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .find(|condition| !self.options.cfg.is_active(condition))
+    }
+
+    /// The docstring made of `docs`, without the blank lines around it unless the options ask to
+    /// keep them. Blank lines between paragraphs are always kept.
+    fn doc_text(&self, docs: &[DocFragment]) -> String {
+        let docs = docs_to_string(docs);
+        if self.options.keep_blank_lines {
+            return docs;
+        }
+        let lines: Vec<&str> = docs.split('\n').collect();
+        let is_text = |line: &&str| !line.trim().is_empty();
+        match (
+            lines.iter().position(is_text),
+            lines.iter().rposition(is_text),
+        ) {
+            (Some(first), Some(last)) => lines[first..=last].join("\n"),
+            _ => String::new(),
+        }
+    }
+
+    /// Save resolved docs in a cache file at `path`, and reuse them in later runs until one of the
+    /// source files they came from changes. Call [`Resolver::save_disk_cache`] when done.
+    ///
+    /// The cache is thrown away if the crates or options change.
+    pub fn enable_disk_cache(&mut self, path: PathBuf) {
+        let mut fingerprint = format!(
+            "{} {:?} {:?} {} {} {:?} {} {}",
+            env!("CARGO_PKG_VERSION"),
+            self.options.cfg,
+            self.options.visibility,
+            self.options.deprecation_notices,
+            self.options.signature_fallback,
+            self.options.target_dirs,
+            self.options.keep_blank_lines,
+            self.options.cfg_notes
+        );
+        fingerprint.push_str(&format!(
+            " reexports {} fn_body_items {} max_scan_files {} must_use_notices {} \
+             expand_field_types {}",
+            self.options.reexport_depth,
+            self.options.fn_body_items,
+            self.options.max_scan_files,
+            self.options.must_use_notices,
+            self.options.expand_field_types
+        ));
+        for (crate_name, prelude) in &self.options.preludes {
+            fingerprint.push_str(&format!(" prelude {}={}", crate_name, prelude));
+        }
+        #[cfg(feature = "docs-rs")]
+        fingerprint.push_str(&format!(" {:?}", self.options.docs_rs));
+        for (name, root) in self.crates.iter() {
+            fingerprint.push_str(&format!(" {}={}", name, root.lib_file().to_string_lossy()));
+        }
+        self.disk_cache = Some(DiskCache::load(path, fingerprint));
+    }
+
+    /// Write the docs resolved so far to the cache enabled by [`Resolver::enable_disk_cache`].
+    ///
+    /// # Errors
+    /// If the cache file can't be written, a descriptive [`anyhow`] will be returned.
+    pub fn save_disk_cache(&self) -> Result<()> {
+        match &self.disk_cache {
+            Some(cache) => cache.save(),
+            None => Ok(()),
+        }
+    }
+
+    /// Return the result for `key` from the disk cache, or call `resolve` and save its result along
+    /// with the files it read. Errors are never cached.
+    ///
+    /// Lookups made by `resolve` count towards the [`ResolveOptions::max_scan_files`] of this one.
+    fn cached<T: Clone + Serialize + DeserializeOwned>(
+        &self,
+        key: String,
+        resolve: impl FnOnce() -> Result<T>,
+    ) -> Result<T> {
+        if self.files_parsed.get().is_some() {
+            return self.cached_in_lookup(key, resolve);
+        }
+        self.files_parsed.set(Some(0));
+        let rv = self.cached_in_lookup(key, resolve);
+        self.files_parsed.set(None);
+        rv
+    }
+
+    fn cached_in_lookup<T: Clone + Serialize + DeserializeOwned>(
+        &self,
+        key: String,
+        resolve: impl FnOnce() -> Result<T>,
+    ) -> Result<T> {
+        let cache = match &self.disk_cache {
+            Some(cache) => cache,
+            None => return resolve(),
+        };
+        if let Some((value, files)) = cache.get(&key) {
+            // Lookups that contain this one depend on the same files.
+            self.read_files.borrow_mut().extend(files);
+            return Ok(value);
+        }
+
+        let outer_files = self.read_files.replace(HashSet::new());
+        let rv = resolve();
+        let files = self.read_files.replace(outer_files);
+        if let Ok(value) = &rv {
+            let mut files: Vec<_> = files.iter().cloned().collect();
+            files.sort();
+            cache.insert(key, value, files);
+        }
+        self.read_files.borrow_mut().extend(files);
+        rv
+    }
+
+    /// Return the chapter saved under `key` in the disk cache if none of the source files its
+    /// directives read have changed, or call `expand` and save the chapter it returns. Chapters
+    /// that needed docs from a failed docs.rs download aren't saved.
+    ///
+    /// The directives of a reused chapter are counted again in
+    /// [`Resolver::directive_counts`], and the undocumented items they refer to are added to
+    /// [`Resolver::undocumented`]. While a chapter is expanded, items without docs are warned about
+    /// once for it, rather than once for the whole book.
+    pub(crate) fn cached_chapter(
+        &self,
+        key: String,
+        expand: impl FnOnce() -> Result<String>,
+    ) -> Result<String> {
+        let cache = match &self.disk_cache {
+            Some(cache) => cache,
+            None => return expand(),
+        };
+        if let Some((chapter, _)) = cache.get::<CachedChapter>(&key) {
+            let mut counts = self.directive_counts.get();
+            counts.expanded += chapter.counts.expanded;
+            counts.resolved += chapter.counts.resolved;
+            counts.placeholders += chapter.counts.placeholders;
+            self.directive_counts.set(counts);
+            for path in chapter.undocumented {
+                if self.undocumented.borrow_mut().insert(path.clone()) {
+                    warn!("{} has no documentation", path);
+                }
+            }
+            cache.mark_used(chapter.entries);
+            return Ok(chapter.content);
+        }
+
+        let outer_counts = self.directive_counts.replace(DirectiveCounts::default());
+        let outer_undocumented = self.undocumented.replace(BTreeSet::new());
+        let outer_files = self.read_files.replace(HashSet::new());
+        let outer_used = cache.replace_used(HashSet::new());
+        let outer_failed = self.docs_rs_failed.replace(false);
+        let rv = expand();
+        let failed = self
+            .docs_rs_failed
+            .replace(outer_failed || self.docs_rs_failed.get());
+        let counts = self.directive_counts.replace(outer_counts);
+        let undocumented = self.undocumented.replace(outer_undocumented);
+        let files = self.read_files.replace(outer_files);
+        let used = cache.replace_used(outer_used);
+        cache.mark_used(used.iter().cloned());
+
+        let mut total = self.directive_counts.get();
+        total.expanded += counts.expanded;
+        total.resolved += counts.resolved;
+        total.placeholders += counts.placeholders;
+        self.directive_counts.set(total);
+        self.undocumented
+            .borrow_mut()
+            .extend(undocumented.iter().cloned());
+        if let (Ok(content), false) = (&rv, failed) {
+            let mut files: Vec<_> = files.into_iter().collect();
+            files.sort();
+            let mut entries: Vec<_> = used.into_iter().collect();
+            entries.sort();
+            let chapter = CachedChapter {
+                content: content.clone(),
+                counts,
+                undocumented,
+                entries,
+            };
+            cache.insert(key, &chapter, files);
+        }
+        rv
+    }
+
+    /// Load the docstrings for each of `paths`, continuing past any that fail.
+    ///
+    /// Each path is returned with its result from [`Resolver::resolve`], in the same order as
+    /// `paths`. Source files are only parsed once for the whole batch.
+    #[must_use]
+    pub fn resolve_all(&self, paths: &[RustPath]) -> Vec<(RustPath, Result<Option<String>>)> {
+        paths
+            .iter()
+            .map(|path| (path.clone(), self.resolve(path)))
+            .collect()
+    }
+
+    /// Load the value of the free or associated `const`, or the `static`, given by `path`,
+    /// formatted like `MAX_LEGS = 10`. If the options ask for signatures, the type is included
+    /// too, like `MAX_LEGS: u8 = 10`.
+    ///
+    /// # Errors
+    /// If the path cannot be found, or if it refers to something other than a `const` or
+    /// `static`, a descriptive [`anyhow`] will be returned.
+    pub fn resolve_const_value(&self, path: &RustPath) -> Result<Option<String>> {
+        self.cached(format!("const {}", path), || {
+            match self.find_item(&self.crate_relative(path)?)? {
+                Some(FoundItem {
+                    const_value: Some((ident, ty, expr)),
+                    ..
+                }) => Ok(Some(if self.options.signature_fallback {
+                    let ty = tokens_to_string(&ty.to_token_stream());
+                    format!("{}: {} = {}", ident, ty, expr_to_string(&expr))
+                } else {
+                    format!("{} = {}", ident, expr_to_string(&expr))
+                })),
+                Some(_) => bail!(
+                    "{} is not a const or static, so it has no value to show",
+                    path
+                ),
+                None => Ok(None),
+            }
+        })
+    }
+
+    /// Find where the item given by `path` is defined.
+    ///
+    /// # Errors
+    /// If the path cannot be found, a descriptive [`anyhow`] will be returned.
+    pub fn resolve_location(&self, path: &RustPath) -> Result<Option<SourceLocation>> {
+        #[cfg(feature = "docs-rs")]
+        if self.resolve_from_docs_rs(path).is_some() {
+            return Ok(None);
+        }
+        self.cached(format!("location {}", path), || {
+            let path = &self.crate_relative(path)?;
+            let crate_dir = self.crate_root(path.head_tail().0)?.dir();
+            Ok(self.find_item(path)?.and_then(|found| {
+                let file = found.file?;
+                Some(SourceLocation {
+                    file: file.strip_prefix(crate_dir).unwrap_or(&file).to_owned(),
+                    line: found.line,
+                })
+            }))
+        })
+    }
+
+    /// Find the item given by `path`, along with its docs and where it is in the source code.
+    ///
+    /// Unlike the other lookups, this is never saved in the disk cache.
+    ///
+    /// # Errors
+    /// If the path cannot be found, a descriptive [`anyhow`] will be returned.
+    pub fn resolve_info(&self, path: &RustPath) -> Result<Option<ItemInfo>> {
+        let path = self.crate_relative(path)?;
+        let edition = self
+            .crates
+            .get(path.head_tail().0)
+            .and_then(CrateRoot::edition)
+            .map(String::from);
+        Ok(self.find_item(&path)?.and_then(|found| {
+            let (start, end) = found.extent?;
+            Some(ItemInfo {
+                kind: found.kind.unwrap_or("item"),
+                docs: self.found_item_docs(&found),
+                file: found.file?,
+                start,
+                end,
+                edition,
+            })
+        }))
+    }
+
+    /// Load the source code of the item given by `path`, from the start of its attributes to its
+    /// end, with the indentation that its lines share removed.
+    ///
+    /// If `lines` is given, only those lines of the item's file are included. They are limited to
+    /// the item's own lines, so that a range can't reach into the code around it.
+    ///
+    /// # Errors
+    /// If the path cannot be found, or if `lines` doesn't overlap with the item, a descriptive
+    /// [`anyhow`] will be returned.
+    pub fn resolve_source(
+        &self,
+        path: &RustPath,
+        lines: Option<RangeInclusive<usize>>,
+    ) -> Result<Option<String>> {
+        let info = match self.resolve_info(path)? {
+            Some(info) => info,
+            None => return Ok(None),
+        };
+        let (first, last) = match &lines {
+            Some(lines) => (
+                (*lines.start()).max(info.start.line),
+                (*lines.end()).min(info.end.line),
+            ),
+            None => (info.start.line, info.end.line),
+        };
+        ensure!(
+            first <= last,
+            "Lines {}:{} are outside of {}, which is on lines {}:{}",
+            lines.as_ref().map_or(first, |lines| *lines.start()),
+            lines.as_ref().map_or(last, |lines| *lines.end()),
+            path,
+            info.start.line,
+            info.end.line
+        );
+
+        let text = read_text(&info.file).map_err(|source| ResolveError::FileNotFound {
+            path: info.file.clone(),
+            source,
+        })?;
+        self.read_files.borrow_mut().insert(info.file.clone());
+        let source: Vec<&str> = text
+            .lines()
+            .skip(first - 1)
+            .take(last + 1 - first)
+            .collect();
+        let indent = source
+            .iter()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| line.len() - line.trim_start().len())
+            .min()
+            .unwrap_or(0);
+        let unindented: Vec<&str> = source
+            .iter()
+            .map(|line| line.get(indent..).unwrap_or("").trim_end())
+            .collect();
+        Ok(Some(unindented.join("\n")))
+    }
+
+    /// The path of the item that `path` refers to, for paths written without a crate name.
+    ///
+    /// When only one crate is configured, a path that doesn't start with the name of a crate is
+    /// read from inside of that crate, like `my_crate::net::Stream` for `net::Stream`, as long as
+    /// there is an item there. Otherwise `path` is returned as it is, so that the error for it
+    /// names the crate that wasn't found. A path that starts with a crate name is never read
+    /// this way, even if the crate also has a module of the same name.
+    fn crate_relative(&self, path: &RustPath) -> Result<RustPath> {
+        let mut names = self.crates.names();
+        let sole_crate = match (names.next(), names.next()) {
+            (Some(name), None) => name,
+            _ => return Ok(path.clone()),
+        };
+        #[cfg(feature = "docs-rs")]
+        if !self.options.docs_rs.is_empty() {
+            return Ok(path.clone());
+        }
+        if self.crates.contains(path.head_tail().0) {
+            return Ok(path.clone());
+        }
+        let relative = path.in_crate(sole_crate);
+        // An item left out by the `cfg` options still counts, so its error says why.
+        self.cfg_skipped.replace(None);
+        let exists = self.locate_item(&relative)?.is_some() || self.cfg_skipped.take().is_some();
+        Ok(if exists { relative } else { path.clone() })
+    }
+
+    /// Find the item given by `path`, with the files named by its `include_str!` docs read in.
+    ///
+    /// If it isn't found, but a module it could be in was skipped because of its `#[cfg(...)]`,
+    /// the error says so.
+    fn find_item(&self, path: &RustPath) -> Result<Option<FoundItem>> {
+        self.cfg_skipped.replace(None);
+        let located = match path.impl_trait() {
+            Some(impl_trait) => self.find_trait_impl(path, impl_trait)?,
+            None => self.locate_item(path)?,
+        };
+        let mut found = match located {
+            Some(found) => found,
+            None => match self.cfg_skipped.take() {
+                Some((module, condition)) => bail!(ResolveError::UnavailableUnderCfg {
+                    path: path.to_string(),
+                    module,
+                    condition,
+                }),
+                None => return Ok(None),
+            },
+        };
+        found.docs = match &found.file {
+            Some(file) => self.read_docs(&found.attrs, file)?,
+            None => doc_fragments(&found.attrs, &self.options.cfg),
+        };
+        Ok(Some(found))
+    }
+
+    /// Find the item given by `path`, trying each of these in order until one is found:
+    ///
+    /// 1. The path as written, from the crate's root file.
+    /// 2. A target in one of the configured target directories, like `examples::basic`.
+    /// 3. An associated item in any impl block of the crate, for impls in other modules.
+    /// 4. All of the above for the paths that the crate's `pub use` re-exports make `path` refer
+    ///    to, so that `my_crate::Foo` can find `my_crate::internal::Foo` after `pub use
+    ///    internal::Foo;`.
+    /// 5. All of the above again, with the path inside the crate's configured prelude module, so
+    ///    that `my_crate::Foo` can find `my_crate::prelude::Foo`.
+    fn locate_item(&self, path: &RustPath) -> Result<Option<FoundItem>> {
+        let mut candidates = vec![path.clone()];
+        let (crate_name, item_path) = path.head_tail();
+        if let (Some(prelude), Some(item_path)) = (self.options.preludes.get(crate_name), item_path)
+        {
+            candidates.push(RustPath::new(crate_name).join(prelude).join(&item_path));
+        }
+        for candidate in &candidates {
+            if let Some(found) = self.locate_item_as_written(candidate)? {
+                return Ok(Some(found));
+            }
+            if let Some(found) = self.locate_reexported_item(candidate)? {
+                return Ok(Some(found));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Find the item that `path` refers to through the `pub use` re-exports of its crate,
+    /// following chains of up to [`ResolveOptions::reexport_depth`] re-exports.
+    fn locate_reexported_item(&self, path: &RustPath) -> Result<Option<FoundItem>> {
+        if self.options.reexport_depth == 0 {
+            return Ok(None);
+        }
+        let crate_name = path.head_tail().0;
+        let index = self.crate_reexports(self.crate_root(crate_name)?.lib_file())?;
+        let mut seen = HashSet::new();
+        let mut paths: Vec<Vec<String>> = vec![path.segments().skip(1).map(String::from).collect()];
+        for _ in 0..self.options.reexport_depth {
+            let mut next = vec![];
+            for item_path in &paths {
+                for target in index.targets(item_path) {
+                    if seen.insert(target.clone()) {
+                        next.push(target);
+                    }
+                }
+            }
+            for target in &next {
+                let target_path = target
+                    .iter()
+                    .fold(RustPath::new(crate_name), RustPath::push);
+                if let Some(found) = self.locate_item_as_written(&target_path)? {
+                    return Ok(Some(found));
+                }
+            }
+            paths = next;
+        }
+        Ok(None)
+    }
+
+    fn locate_item_as_written(&self, path: &RustPath) -> Result<Option<FoundItem>> {
+        let (crate_name, item_path) = path.head_tail();
+        let crate_root = self.crate_root(crate_name)?;
+        let lib_file = crate_root.lib_file();
+        let found = if let Some(found) = self.find_attrs_in_crate(lib_file, &item_path)? {
+            Some(found)
+        } else if let Some((target_file, target_path)) =
+            self.find_target_file(crate_root, &item_path)
+        {
+            self.find_item_in_file(&target_file, &target_path)?
+        } else if let Some(found) = self.find_in_crate_impls(lib_file, path)? {
+            Some(found)
+        } else {
+            self.find_in_supertraits(lib_file, path)?
+        };
+        Ok(found.map(|mut found| {
+            found.path.get_or_insert_with(|| path.clone());
+            found
+        }))
+    }
+
+    /// If `item_path` starts with one of the configured target directories and the name of a
+    /// target in it, like `examples::basic`, find the root file of that target and return it along
+    /// with the rest of the path.
+    ///
+    /// Targets can be a single file, like `examples/basic.rs`, or a directory with a `main.rs`,
+    /// like `examples/basic/main.rs`.
+    fn find_target_file(
+        &self,
+        crate_root: &CrateRoot,
+        item_path: &Option<RustPath>,
+    ) -> Option<(PathBuf, Option<RustPath>)> {
+        let (dir_name, target_path) = item_path.as_ref()?.head_tail();
+        if !self.options.target_dirs.iter().any(|dir| dir == dir_name) {
+            return None;
+        }
+        let target_path = target_path?;
+        let (target_name, rest) = target_path.head_tail();
+        let dir = crate_root.dir().join(dir_name);
+        let candidates = [
+            dir.join(format!("{}.rs", target_name)),
+            dir.join(target_name).join("main.rs"),
+        ];
+        let file = candidates.iter().find(|file| file.is_file())?;
+        Some((file.clone(), rest))
+    }
+
+    /// Look for `path` as an associated item in any impl block of its crate, for types whose impl
+    /// blocks are in a different module than the type itself.
+    ///
+    /// The methods of a type alias are those of the type it names, so long as that type is in the
+    /// same crate. Only one alias is followed, so an alias of an alias isn't.
+    fn find_in_crate_impls(&self, lib_file: &Path, path: &RustPath) -> Result<Option<FoundItem>> {
+        let owner = match path.parent() {
+            Some(owner) if owner.parent().is_some() => owner,
+            _ => return Ok(None),
+        };
+        // Only look for impls of types that exist.
+        let the_type = match self.find_attrs_in_crate(lib_file, &owner.head_tail().1)? {
+            Some(the_type) => the_type,
+            None => {
+                // A path that goes on past an associated item, like `Crab::walk::extra`, is a
+                // mistake rather than a missing item.
+                let tail = Some(RustPath::new(path.last()));
+                let found = self.find_in_crate_impls(lib_file, &owner)?;
+                ensure_associated_item_is_leaf(found.as_ref(), owner.last(), &tail)?;
+                return Ok(None);
+            }
+        };
+        let index = self.crate_impls(lib_file)?;
+        let type_impls = index.type_impls(&owner, the_type.alias_of.as_ref());
+        let member = Some(RustPath::new(path.last()));
+        for the_impl in &type_impls {
+            if let Some(mut found) =
+                find_attrs_in_impl(&the_impl.item, &member, self.options.visibility)?
+            {
+                found.file = Some(the_impl.file.clone());
+                return Ok(Some(found));
+            }
+        }
+
+        // Methods that an impl doesn't override are documented by the trait's default.
+        for the_impl in type_impls {
+            let trait_path = match index.impl_trait(the_impl) {
+                Some(trait_path) => trait_path,
+                None => continue,
+            };
+            if let Some(found) = self.find_trait_default(lib_file, &trait_path, path.last())? {
+                return Ok(Some(found));
+            }
+        }
+        Ok(None)
+    }
+
+    /// If `path` is an item of a trait that the trait doesn't have itself, like a method of
+    /// `Swim` for `trait Dive: Swim`, find it in the trait's supertraits, like rustdoc lists them.
+    ///
+    /// Supertraits are searched breadth first, up to [`MAX_SUPERTRAIT_DEPTH`] levels up, and each
+    /// one only once, so traits that are each other's supertraits don't loop. Each bound is read
+    /// from the module of the trait it is on, so supertraits from other crates are not found.
+    fn find_in_supertraits(&self, lib_file: &Path, path: &RustPath) -> Result<Option<FoundItem>> {
+        let trait_path: Vec<String> = match path.parent() {
+            Some(trait_path) if trait_path.parent().is_some() => {
+                trait_path.segments().skip(1).map(String::from).collect()
+            }
+            _ => return Ok(None),
+        };
+        let mut traits = match self.find_crate_trait(lib_file, &trait_path)? {
+            Some((_, the_trait)) => vec![(trait_path, the_trait)],
+            None => return Ok(None),
+        };
+        let index = self.crate_impls(lib_file)?;
+        let mut seen: HashSet<Vec<String>> = traits.iter().map(|(path, _)| path.clone()).collect();
+        let member = Some(RustPath::new(path.last()));
+        for _ in 0..MAX_SUPERTRAIT_DEPTH {
+            let mut supertraits = vec![];
+            for (trait_path, the_trait) in &traits {
+                let module = &trait_path[..trait_path.len() - 1];
+                for bound in &the_trait.supertraits {
+                    let bound_path = match bound {
+                        TypeParamBound::Trait(bound) => {
+                            match index.resolve(module, &path_segments(&bound.path)) {
+                                Some(bound_path) => bound_path,
+                                None => continue,
+                            }
+                        }
+                        TypeParamBound::Lifetime(_) => continue,
+                    };
+                    if !seen.insert(bound_path.clone()) {
+                        continue;
+                    }
+                    if let Some((file_path, supertrait)) =
+                        self.find_crate_trait(lib_file, &bound_path)?
+                    {
+                        if let Some(mut found) = find_attrs_in_trait(&supertrait, &member)? {
+                            found.file = Some(file_path);
+                            return Ok(Some(found));
+                        }
+                        supertraits.push((bound_path, supertrait));
+                    }
+                }
+            }
+            if supertraits.is_empty() {
+                break;
+            }
+            traits = supertraits;
+        }
+        Ok(None)
+    }
+
+    /// Find the default method `method` of the trait at `trait_path`, a path inside the crate
+    /// with the root file `lib_file`.
+    fn find_trait_default(
+        &self,
+        lib_file: &Path,
+        trait_path: &[String],
+        method: &str,
+    ) -> Result<Option<FoundItem>> {
+        let found =
+            self.find_crate_trait(lib_file, trait_path)?
+                .and_then(|(file_path, the_trait)| {
+                    let mut found = find_trait_default_method(&the_trait, method)?;
+                    found.file = Some(file_path);
+                    Some(found)
+                });
+        Ok(found)
+    }
+
+    /// Find the trait at `trait_path`, a path inside the crate with the root file `lib_file`,
+    /// along with the file it is in.
+    fn find_crate_trait(
+        &self,
+        lib_file: &Path,
+        trait_path: &[String],
+    ) -> Result<Option<(PathBuf, ItemTrait)>> {
+        let index = self.crate_impls(lib_file)?;
+        Ok(index
+            .traits
+            .get(trait_path)
+            .filter(|(_, the_trait)| self.options.visibility.allows(&the_trait.vis))
+            .cloned())
+    }
+
+    /// Load the docs of every public method of the type given by `path`, from all of its inherent
+    /// and trait impls in the same crate, each under a `###` heading with the method's name and
+    /// followed by its signature.
+    ///
+    /// Default methods of the type's traits are included unless an impl overrides them. Methods
+    /// marked `#[doc(hidden)]` are skipped.
+    ///
+    /// # Errors
+    /// If the type cannot be read, or `path` isn't a type, a descriptive [`anyhow`] will be
+    /// returned.
+    pub fn resolve_methods(&self, path: &RustPath, order: MethodOrder) -> Result<Option<String>> {
+        self.cached(format!("methods {:?} {}", order, path), || {
+            self.find_methods_docs(&self.crate_relative(path)?, order)
+        })
+    }
+
+    /// Find the type given by `path` and every impl block for it in its crate, along with the
+    /// file each one is in. `listing` says what was going to be listed, for the error when `path`
+    /// isn't a type.
+    fn find_type_impls(
+        &self,
+        path: &RustPath,
+        listing: &str,
+    ) -> Result<Option<(FoundItem, FileImpls)>> {
+        let the_type = match self.find_item(path)? {
+            Some(found) => found,
+            None => return Ok(None),
+        };
+        match the_type.kind {
+            Some("struct" | "enum" | "union" | "type") => {}
+            kind => bail!(
+                "{} is a {}, not a type, so it has no {} to list",
+                path,
+                kind.unwrap_or("item"),
+                listing
+            ),
+        }
+        let lib_file = self.crate_root(path.head_tail().0)?.lib_file();
+        let index = self.crate_impls(lib_file)?;
+        let type_path = the_type.path.clone().unwrap_or_else(|| path.clone());
+        let type_impls = index
+            .type_impls(&type_path, the_type.alias_of.as_ref())
+            .into_iter()
+            .cloned()
+            .collect();
+        Ok(Some((the_type, type_impls)))
+    }
+
+    /// Find the block that implements the trait `impl_trait` for the type at `path`, which is
+    /// written like `Crab#Speak`.
+    ///
+    /// Traits are matched on their name alone, like impls are matched on the name of their type,
+    /// unless `impl_trait` has generic arguments, like `From<u8>`, to pick between several impls
+    /// of the same trait.
+    fn find_trait_impl(&self, path: &RustPath, impl_trait: &str) -> Result<Option<FoundItem>> {
+        let (the_type, impls) = match self.find_type_impls(&path.without_impl_trait(), "impls")? {
+            Some(found) => found,
+            None => return Ok(None),
+        };
+        let wanted = RustPath::from_str(impl_trait)?;
+        let wanted_args = wanted
+            .generics()
+            .filter(|(segment, _)| *segment == wanted.last())
+            .map(|(_, args)| args.replace(' ', ""))
+            .last();
+        let is_wanted = |trait_path: &syn::Path| match trait_path.segments.last() {
+            Some(segment) if segment.ident == wanted.last() => {
+                wanted_args.as_ref().map_or(true, |args| {
+                    segment
+                        .arguments
+                        .to_token_stream()
+                        .to_string()
+                        .replace(' ', "")
+                        == *args
+                })
+            }
+            _ => false,
+        };
+
+        let mut matching = impls.iter().filter(|the_impl| {
+            self.inactive_cfg(&the_impl.item.attrs).is_none()
+                && matches!(&the_impl.item.trait_, Some((None, trait_path, _)) if is_wanted(trait_path))
+        });
+        let CrateImpl {
+            file: file_path,
+            item: the_impl,
+            ..
+        } = match (matching.next(), matching.next()) {
+            (Some(found), None) => found,
+            (Some(_), Some(_)) => bail!(
+                "{} has more than one impl of {}, so give the trait's generic arguments too, \
+                 like `{}#{}<...>`",
+                path.without_impl_trait(),
+                impl_trait,
+                path.without_impl_trait(),
+                wanted.last()
+            ),
+            (None, _) => {
+                if derived_traits(&the_type.attrs).iter().any(is_wanted) {
+                    bail!(
+                        "{} is derived for {}, so there is no impl block to read docs from",
+                        impl_trait,
+                        path.without_impl_trait()
+                    );
+                }
+                return Ok(None);
+            }
+        };
+        let (generics, self_ty) = (&the_impl.generics, &the_impl.self_ty);
+        let where_clause = &generics.where_clause;
+        let trait_path = the_impl
+            .trait_
+            .as_ref()
+            .map(|(_, trait_path, _)| trait_path);
+        let mut found = FoundItem::new(the_impl.attrs.clone(), the_impl.impl_token.span)
+            .with_signature(quote!(impl #generics #trait_path for #self_ty #where_clause))
+            .with_extent(the_impl.span())
+            .with_kind("impl");
+        found.file = Some(file_path.clone());
+        Ok(Some(found))
+    }
+
+    fn find_methods_docs(&self, path: &RustPath, order: MethodOrder) -> Result<Option<String>> {
+        let impls = match self.find_type_impls(path, "methods")? {
+            Some((_, impls)) => impls,
+            None => return Ok(None),
+        };
+        let lib_file = self.crate_root(path.head_tail().0)?.lib_file();
+        let index = self.crate_impls(lib_file)?;
+        // The name, signature, attributes, and file of each method.
+        let mut methods: Vec<(String, String, Vec<Attribute>, PathBuf)> = vec![];
+        for crate_impl in &impls {
+            let (file_path, the_impl) = (&crate_impl.file, &crate_impl.item);
+            for item in &the_impl.items {
+                if let syn::ImplItem::Method(m) = item {
+                    let is_public =
+                        the_impl.trait_.is_some() || matches!(m.vis, Visibility::Public(_));
+                    if is_public && !is_doc_hidden(&m.attrs) {
+                        let (vis, sig) = (&m.vis, &m.sig);
+                        let signature = signature_to_string(&quote!(#vis #sig));
+                        let name = m.sig.ident.to_string();
+                        methods.push((name, signature, m.attrs.clone(), file_path.clone()));
+                    }
+                }
+            }
+            let trait_path = match index.impl_trait(crate_impl) {
+                Some(trait_path) => trait_path,
+                None => continue,
+            };
+            let (trait_file, the_trait) = match self.find_crate_trait(lib_file, &trait_path)? {
+                Some(found) => found,
+                None => continue,
+            };
+            let overridden = |name: &Ident| {
+                the_impl.items.iter().any(|item| match item {
+                    syn::ImplItem::Method(m) => m.sig.ident == *name,
+                    _ => false,
+                })
+            };
+            for item in &the_trait.items {
+                if let TraitItem::Method(m) = item {
+                    if m.default.is_some() && !overridden(&m.sig.ident) && !is_doc_hidden(&m.attrs)
+                    {
+                        let sig = &m.sig;
+                        let signature = signature_to_string(&quote!(#sig));
+                        let name = m.sig.ident.to_string();
+                        methods.push((name, signature, m.attrs.clone(), trait_file.clone()));
+                    }
+                }
+            }
+        }
+        if order == MethodOrder::Alphabetical {
+            methods.sort_by(|a, b| a.0.cmp(&b.0));
+        }
+
+        let mut sections = vec![];
+        for (name, signature, attrs, file_path) in methods {
+            let docs = self.read_docs(&attrs, &file_path)?;
+            let docs = self.item_docs(&attrs, &docs, None);
+            let mut section = format!("### {}\n\n```rust\n{}\n```", name, signature);
+            if !docs.is_empty() {
+                section.push_str(&format!("\n\n{}", docs));
+            }
+            sections.push(section);
+        }
+        Ok(Some(sections.join("\n\n")))
+    }
+
+    /// Summarize the impls of the type given by `path`, like the "Implementations" and "Trait
+    /// Implementations" sections of its page on docs.rs: a list of its inherent impls, and a list
+    /// of the traits it implements, including derived ones, under `###` headings.
+    ///
+    /// Traits from the standard library link to its docs, and traits from the type's own crate are
+    /// followed by the first paragraph of their docs. Impls marked `#[doc(hidden)]` or behind a
+    /// `#[cfg(...)]` that doesn't hold are skipped, as are inherent impls without public items.
+    ///
+    /// # Errors
+    /// If the type cannot be read, or `path` isn't a type, a descriptive [`anyhow`] will be
+    /// returned.
+    pub fn resolve_impls(&self, path: &RustPath) -> Result<Option<String>> {
+        self.cached(format!("impls {}", path), || {
+            self.find_impls_summary(&self.crate_relative(path)?)
+        })
+    }
+
+    fn find_impls_summary(&self, path: &RustPath) -> Result<Option<String>> {
+        let (the_type, impls) = match self.find_type_impls(path, "impls")? {
+            Some(found) => found,
+            None => return Ok(None),
+        };
+        let lib_file = self.crate_root(path.head_tail().0)?.lib_file();
+        // Derives are read from the module the type is in.
+        let type_path = the_type.path.as_ref().unwrap_or(path);
+        let type_module: Vec<String> = type_path
+            .parent()
+            .map(|module| module.segments().skip(1).map(String::from).collect())
+            .unwrap_or_default();
+        let is_public = |item: &syn::ImplItem| {
+            let (vis, attrs) = match item {
+                syn::ImplItem::Const(c) => (&c.vis, &c.attrs),
+                syn::ImplItem::Method(m) => (&m.vis, &m.attrs),
+                syn::ImplItem::Type(t) => (&t.vis, &t.attrs),
+                _ => return false,
+            };
+            matches!(vis, Visibility::Public(_)) && !is_doc_hidden(attrs)
+        };
+
+        let mut inherent = vec![];
+        // The name of each trait, to sort them by, and its line in the list.
+        let mut traits = vec![];
+        for crate_impl in &impls {
+            let the_impl = &crate_impl.item;
+            if is_doc_hidden(&the_impl.attrs) || self.inactive_cfg(&the_impl.attrs).is_some() {
+                continue;
+            }
+            match &the_impl.trait_ {
+                Some((bang, trait_path, _)) => {
+                    let module = &crate_impl.module;
+                    traits.push(self.trait_summary(
+                        lib_file,
+                        module,
+                        bang.is_some(),
+                        trait_path,
+                    )?);
+                }
+                None if the_impl.items.iter().any(is_public) => {
+                    let (generics, self_ty) = (&the_impl.generics, &the_impl.self_ty);
+                    let where_clause = &generics.where_clause;
+                    let header =
+                        signature_to_string(&quote!(impl #generics #self_ty #where_clause));
+                    let line = format!("- `{}`", header);
+                    if !inherent.contains(&line) {
+                        inherent.push(line);
+                    }
+                }
+                None => {}
+            }
+        }
+        for trait_path in derived_traits(&the_type.attrs) {
+            traits.push(self.trait_summary(lib_file, &type_module, false, &trait_path)?);
+        }
+        traits.sort();
+        traits.dedup();
+
+        let mut sections = vec![];
+        if !inherent.is_empty() {
+            sections.push(format!("### Implementations\n\n{}", inherent.join("\n")));
+        }
+        if !traits.is_empty() {
+            let lines: Vec<_> = traits.into_iter().map(|(_, line)| line).collect();
+            sections.push(format!("### Trait Implementations\n\n{}", lines.join("\n")));
+        }
+        Ok(Some(sections.join("\n\n")))
+    }
+
+    /// The name of the trait at `trait_path`, written in the module at `module`, and a line for the
+    /// list of traits that a type implements, or doesn't implement if `negative`.
+    fn trait_summary(
+        &self,
+        lib_file: &Path,
+        module: &[String],
+        negative: bool,
+        trait_path: &syn::Path,
+    ) -> Result<(String, String)> {
+        let last = trait_path
+            .segments
+            .last()
+            .expect("Bug: trait path without segments");
+        let name = last.ident.to_string();
+        let label = format!(
+            "{}{}",
+            if negative { "!" } else { "" },
+            signature_to_string(&last.to_token_stream())
+        );
+        let crate_trait = match self
+            .crate_impls(lib_file)?
+            .resolve(module, &path_segments(trait_path))
+        {
+            Some(crate_path) => self.find_crate_trait(lib_file, &crate_path)?,
+            None => None,
+        };
+        let line = if let Some((file_path, the_trait)) = crate_trait {
+            let docs = self.doc_text(&self.read_docs(&the_trait.attrs, &file_path)?);
+            match docs.split("\n\n").next() {
+                Some(summary) if !summary.is_empty() => {
+                    format!(
+                        "- `{}`: {}",
+                        label,
+                        summary.lines().collect::<Vec<_>>().join(" ")
+                    )
+                }
+                _ => format!("- `{}`", label),
+            }
+        } else if let Some(url) = std_trait_url(trait_path) {
+            format!("- [`{}`]({})", label, url)
+        } else {
+            format!("- `{}`", label)
+        };
+        Ok((name, line))
+    }
+
+    /// Index every impl block and trait in the crate with the root file `lib_file`, or return the
+    /// index made earlier.
+    fn crate_impls(&self, lib_file: &Path) -> Result<Rc<ImplIndex>> {
+        if let Some(index) = self.impl_cache.borrow().get(lib_file) {
+            return Ok(Rc::clone(index));
+        }
+
+        let mut index = ImplIndex::default();
+        let mut impls = vec![];
+        let mut visit = |file_path: &Path, module_path: &[String], items: &[Item]| {
+            let declared = index.declared.entry(module_path.to_vec()).or_default();
+            declared.extend(
+                items
+                    .iter()
+                    .filter_map(listable_item)
+                    .map(|(name, _, _)| name),
+            );
+            for item in items {
+                match item {
+                    Item::Impl(the_impl) => impls.push(CrateImpl {
+                        file: file_path.to_owned(),
+                        module: module_path.to_vec(),
+                        item: the_impl.clone(),
+                    }),
+                    Item::Trait(the_trait) => {
+                        let mut trait_path = module_path.to_vec();
+                        trait_path.push(the_trait.ident.to_string());
+                        index
+                            .traits
+                            .entry(trait_path)
+                            .or_insert_with(|| (file_path.to_owned(), the_trait.clone()));
+                    }
+                    Item::Use(the_use)
+                        if the_use.leading_colon.is_none()
+                            && self.inactive_cfg(&the_use.attrs).is_none() =>
+                    {
+                        index.imports.add(module_path, &the_use.tree, &mut vec![]);
+                    }
+                    _ => {}
+                }
+            }
+        };
+        self.walk_file_modules(lib_file, &mut vec![], &mut visit)
+            .context("Looking for impl blocks")?;
+        for the_impl in impls {
+            let type_path = type_path(&the_impl.item.self_ty)
+                .and_then(|type_path| index.resolve(&the_impl.module, &type_path));
+            if let Some(type_path) = type_path {
+                index.impls.entry(type_path).or_default().push(the_impl);
+            }
+        }
+        let index = Rc::new(index);
+        self.impl_cache
+            .borrow_mut()
+            .insert(lib_file.to_owned(), Rc::clone(&index));
+        Ok(index)
+    }
+
+    /// Index the `pub use` re-exports in every module of the crate with the root file
+    /// `lib_file`, or return the index made earlier.
+    ///
+    /// Re-exports with less visibility than [`ResolveOptions::visibility`] allows, or behind a
+    /// `#[cfg(...)]` that doesn't hold, are left out, as are re-exports of other crates' items.
+    fn crate_reexports(&self, lib_file: &Path) -> Result<Rc<ReexportIndex>> {
+        if let Some(index) = self.reexport_cache.borrow().get(lib_file) {
+            return Ok(Rc::clone(index));
+        }
+
+        let mut index = ReexportIndex::default();
+        let mut modules = HashMap::new();
+        let mut visit = |_: &Path, module_path: &[String], items: &[Item]| {
+            let public_items = items
+                .iter()
+                .filter_map(listable_item)
+                .filter(|(_, attrs, is_public)| *is_public && self.inactive_cfg(attrs).is_none())
+                .map(|(name, _, _)| name)
+                .collect();
+            modules.insert(module_path.to_vec(), public_items);
+            for item in items {
+                if let Item::Use(the_use) = item {
+                    if the_use.leading_colon.is_none()
+                        && self.options.visibility.allows(&the_use.vis)
+                        && self.inactive_cfg(&the_use.attrs).is_none()
+                    {
+                        index.add(module_path, &the_use.tree, &mut vec![]);
+                    }
+                }
+            }
+        };
+        self.walk_file_modules(lib_file, &mut vec![], &mut visit)
+            .context("Looking for re-exports")?;
+        index.expand_globs(&modules, self.options.reexport_depth);
+        let index = Rc::new(index);
+        self.reexport_cache
+            .borrow_mut()
+            .insert(lib_file.to_owned(), Rc::clone(&index));
+        Ok(index)
+    }
+
+    /// Load the README of the crate called `crate_name`, as described by
+    /// [`CrateRoot::readme`], or return `None` if it doesn't have one.
+    ///
+    /// # Errors
+    /// If the crate isn't configured or its README can't be read, a descriptive [`anyhow`] will be
+    /// returned.
+    pub fn resolve_readme(&self, crate_name: &str) -> Result<Option<String>> {
+        let readme = match self.crate_root(crate_name)?.readme() {
+            Some(readme) => readme.to_owned(),
+            None => return Ok(None),
+        };
+        self.cached(format!("readme {}", crate_name), || {
+            let contents = read_text(&readme).context(format!(
+                "Reading the README of {} at {}",
+                crate_name,
+                readme.to_string_lossy()
+            ))?;
+            self.read_files.borrow_mut().insert(readme.clone());
+            Ok(Some(contents))
+        })
+    }
+
+    /// Load the docstrings of every public item directly inside the module given by `path`, each
+    /// under a `###` heading with the item's name.
+    ///
+    /// Items marked `#[doc(hidden)]` are skipped. A path with only a crate name lists the items at
+    /// the root of that crate.
+    ///
+    /// # Errors
+    /// If the module cannot be found, a descriptive [`anyhow`] will be returned. If it is left out
+    /// under the configured `cfg` options, the error is a
+    /// [`ResolveError::UnavailableUnderCfg`].
+    pub fn resolve_module_items(&self, path: &RustPath) -> Result<Option<String>> {
+        self.cached(format!("items {}", path), || {
+            self.find_module_docs(&self.crate_relative(path)?, None)
+        })
+    }
+
+    /// Like [`resolve_module_items`](Self::resolve_module_items), but only list the items that
+    /// have an attribute with the path `attr`, like `stable` for `#[stable(since = "1.2")]` or
+    /// `my_macros::since` for `#[my_macros::since("1.2")]`.
+    ///
+    /// If no items in the module have the attribute, the listing is empty.
+    ///
+    /// # Errors
+    /// If the module cannot be found, a descriptive [`anyhow`] will be returned.
+    pub fn resolve_marked_module_items(
+        &self,
+        path: &RustPath,
+        attr: &str,
+    ) -> Result<Option<String>> {
+        self.cached(format!("items {} attr {}", path, attr), || {
+            self.find_module_docs(&self.crate_relative(path)?, Some(attr))
+        })
+    }
+
+    fn find_module_docs(&self, path: &RustPath, marker: Option<&str>) -> Result<Option<String>> {
+        let (crate_name, module_path) = path.head_tail();
+        let crate_root = self.crate_root(crate_name)?;
+        self.cfg_skipped.replace(None);
+        let mut found = self.find_module_items(crate_root.lib_file(), &module_path)?;
+        if found.is_none() {
+            if let Some((target_file, target_path)) =
+                self.find_target_file(crate_root, &module_path)
+            {
+                found = self.find_module_items(&target_file, &target_path)?;
+            }
+        }
+        let found = match found {
+            Some(found) => found,
+            None => match self.cfg_skipped.take() {
+                Some((module, condition)) => bail!(ResolveError::UnavailableUnderCfg {
+                    path: path.to_string(),
+                    module,
+                    condition,
+                }),
+                None => return Ok(None),
+            },
+        };
+        let (file_path, items) = found;
+
+        let mut sections = vec![];
+        for item in &items {
+            let (name, attrs, is_public) = match listable_item(item) {
+                Some(listable) => listable,
+                None => continue,
+            };
+            if !is_public || is_doc_hidden(attrs) || self.inactive_cfg(attrs).is_some() {
+                continue;
+            }
+            if marker.is_some_and(|marker| !has_attr(attrs, marker)) {
+                continue;
+            }
+            let mut attrs = attrs.to_vec();
+            let mut docs = self.read_docs(&attrs, &file_path)?;
+            // The docs of out-of-line modules are mostly inner attributes at the top of their file.
+            if let Item::Mod(the_mod @ ItemMod { content: None, .. }) = item {
+                let mod_path = self.mod_file_path(&file_path, the_mod)?;
+                let inner_attrs = &self.parse_file(&mod_path)?.attrs;
+                docs.extend(self.read_docs(inner_attrs, &mod_path)?);
+                attrs.extend(inner_attrs.iter().cloned());
+            }
+            let signature = item_signature(item).map(|sig| signature_to_string(&sig));
+            let docs = self.item_docs(&attrs, &docs, signature.as_deref());
+            sections.push(format!("### {}\n\n{}", name, docs));
+        }
+        Ok(Some(sections.join("\n\n")))
+    }
+
+    fn crate_root(&self, crate_name: &str) -> Result<&CrateRoot> {
+        self.crates.get(crate_name).ok_or_else(|| {
+            // A common mistake is to leave the crate name off of a path, so look for items
+            // with the name that was used instead.
+            let suggestions = self.find_items_named(crate_name);
+            anyhow!(ResolveError::CrateNotFound {
+                name: crate_name.to_string(),
+                suggestions: suggestions.iter().map(RustPath::to_string).collect(),
+            })
+        })
+    }
+
+    /// Find the paths of every item named `name`, in any module of any of the configured crates.
+    ///
+    /// Crates that can't be read are skipped, since this is only used to make suggestions.
+    fn find_items_named(&self, name: &str) -> Vec<RustPath> {
+        let mut found = vec![];
+        for (crate_name, crate_root) in self.crates.iter() {
+            let mut visit = |_: &Path, module_path: &[String], items: &[Item]| {
+                for item in items {
+                    if let Some((item_name, _, _)) = listable_item(item) {
+                        if item_name == name {
+                            let module = module_path[1..]
+                                .iter()
+                                .fold(RustPath::new(&module_path[0]), RustPath::push);
+                            found.push(module.push(item_name));
+                        }
+                    }
+                }
+            };
+            let _ = self.walk_file_modules(
+                crate_root.lib_file(),
+                &mut vec![crate_name.to_string()],
+                &mut visit,
+            );
+        }
+        found
+    }
+
+    /// Call `visit` with the file, path, and items of the module in the file at `file_path`, and then
+    /// of each module nested inside of it.
+    ///
+    /// Nested module files that can't be read or parsed, like generated files or ones using syntax
+    /// that syn doesn't support, are skipped with a warning so that one bad file doesn't stop a scan
+    /// of the whole crate.
+    fn walk_file_modules(
+        &self,
+        file_path: &Path,
+        module_path: &mut Vec<String>,
+        visit: &mut ModuleVisitor,
+    ) -> Result<()> {
+        let ast = self.parse_file(file_path)?;
+        self.walk_modules(file_path, &ast.items, module_path, visit)
+    }
+
+    fn walk_modules(
+        &self,
+        file_path: &Path,
+        items: &[Item],
+        module_path: &mut Vec<String>,
+        visit: &mut ModuleVisitor,
+    ) -> Result<()> {
+        visit(file_path, module_path, items);
+        for item in items {
+            if let Item::Mod(the_mod) = item {
+                if self.inactive_cfg(&the_mod.attrs).is_some() {
+                    continue;
+                }
+                module_path.push(the_mod.ident.to_string());
+                match &the_mod.content {
+                    Some((_, items)) => self.walk_modules(file_path, items, module_path, visit)?,
+                    None => {
+                        let parsed = self.mod_file_path(file_path, the_mod).and_then(|mod_path| {
+                            let ast = self.parse_file(&mod_path)?;
+                            Ok((mod_path, ast))
+                        });
+                        match parsed {
+                            Ok((mod_path, ast)) => {
+                                self.walk_modules(&mod_path, &ast.items, module_path, visit)?;
+                            }
+                            // Going over the scan limit stops the whole scan, rather than
+                            // leaving out the rest of the crate.
+                            Err(err)
+                                if matches!(
+                                    err.downcast_ref(),
+                                    Some(ResolveError::ScanLimitReached { .. })
+                                ) =>
+                            {
+                                return Err(err);
+                            }
+                            Err(err) => self.warn_skipped(module_path, &err),
+                        }
+                    }
+                }
+                module_path.pop();
+            }
+        }
+        Ok(())
+    }
+
+    fn warn_skipped(&self, module_path: &[String], err: &anyhow::Error) {
+        let message = format!("{:#}", err);
+        if self.skipped_modules.borrow_mut().insert(message.clone()) {
+            warn!(
+                "Skipping module {} while searching its crate: {}",
+                module_path.join("::"),
+                message
+            );
+        }
+    }
+
+    /// Find the file that holds the contents of the out-of-line module `the_mod`, declared in the
+    /// file at `parent_path`.
+    ///
+    /// Modules declared in a crate root or a `mod.rs` live next to it, and modules declared in
+    /// any other file live in a directory named after that file. Either way the module can be a
+    /// `name.rs` file or a `name/mod.rs` file.
+    ///
+    /// A `#[path = "..."]` attribute, or one inside a `#[cfg_attr(...)]` whose condition is active
+    /// in the configured `cfg` options, names the file instead, relative to the directory of the
+    /// file it is in.
+    fn mod_file_path(&self, parent_path: &Path, the_mod: &ItemMod) -> Result<PathBuf> {
+        if let Some(path) = mod_path_attr(&the_mod.attrs, &self.options.cfg) {
+            let dir = parent_path.parent().unwrap_or_else(|| Path::new(""));
+            return Ok(join_portable(dir, &path));
+        }
+        let is_crate_root = parent_path.file_stem().is_some_and(|n| n == "lib")
+            || self
+                .crates
+                .iter()
+                .any(|(_, root)| root.lib_file() == parent_path);
+        let dir = if is_crate_root || parent_path.file_name().is_some_and(|n| n == "mod.rs") {
+            parent_path
+                .parent()
+                .unwrap_or_else(|| Path::new(""))
+                .to_owned()
+        } else {
+            parent_path.with_extension("")
+        };
+        let candidates = [
+            dir.join(format!("{}.rs", the_mod.ident)),
+            dir.join(the_mod.ident.to_string()).join("mod.rs"),
+        ];
+        match candidates.iter().find(|file| file.is_file()) {
+            Some(file) => Ok(file.clone()),
+            // Report the usual path, so that the error says which file is missing.
+            None => Ok(candidates[0].clone()),
+        }
+    }
+
+    /// The docs from `attrs`, like [`doc_fragments`], with each `#[doc = include_str!("...")]`
+    /// replaced by the contents of the file it names.
+    ///
+    /// Like `include_str!` itself, the path is relative to `file_path`, the source file that the
+    /// attributes are in, so a module in `net/mod.rs` that includes `"tcp.md"` reads `net/tcp.md`.
+    fn read_docs(&self, attrs: &[Attribute], file_path: &Path) -> Result<Vec<DocFragment>> {
+        let dir = file_path.parent().unwrap_or_else(|| Path::new(""));
+        let mut fragments = vec![];
+        for attr in attrs {
+            let include = match doc_include_path(attr) {
+                Some(include) => join_portable(dir, &include),
+                None => {
+                    fragments.extend(doc_fragments(std::slice::from_ref(attr), &self.options.cfg));
+                    continue;
+                }
+            };
+            let contents = read_text(&include).context(format!(
+                "Reading {} included by the docs in {}",
+                include.to_string_lossy(),
+                file_path.to_string_lossy()
+            ))?;
+            self.read_files.borrow_mut().insert(include);
+            fragments.push(included_fragment(&contents));
+        }
+        Ok(fragments)
+    }
+
+    /// Read and parse the source file at `file_path`, or return the cached result of doing so
+    /// earlier.
+    fn parse_file(&self, file_path: &Path) -> Result<Rc<syn::File>> {
+        self.read_files.borrow_mut().insert(file_path.to_owned());
+        if let Some(ast) = self.parse_cache.borrow().get(file_path) {
+            return Ok(Rc::clone(ast));
+        }
+
+        if let Some(parsed) = self.files_parsed.get() {
+            if parsed >= self.options.max_scan_files {
+                bail!(ResolveError::ScanLimitReached {
+                    limit: self.options.max_scan_files,
+                    path: file_path.to_owned(),
+                });
+            }
+            self.files_parsed.set(Some(parsed + 1));
+        }
+        let file_text = read_text(file_path).map_err(|source| ResolveError::FileNotFound {
+            path: file_path.to_owned(),
+            source,
+        })?;
+        let ast = Rc::new(syn::parse_file(&file_text).map_err(|source| {
+            ResolveError::ParseError {
+                path: file_path.to_owned(),
+                source,
+                edition: self
+                    .crates
+                    .containing(file_path)
+                    .and_then(CrateRoot::edition)
+                    .map(String::from),
+            }
+        })?);
+        self.parse_cache
+            .borrow_mut()
+            .insert(file_path.to_owned(), Rc::clone(&ast));
+        Ok(ast)
+    }
+
+    fn find_attrs_in_crate(
+        &self,
+        lib_file: &Path,
+        remaining_path: &Option<RustPath>,
+    ) -> Result<Option<FoundItem>> {
+        self.find_item_in_file(lib_file, remaining_path)
+    }
+
+    fn find_item_in_file(
+        &self,
+        file_path: &Path,
+        remaining_path: &Option<RustPath>,
+    ) -> Result<Option<FoundItem>> {
+        let ast = self.parse_file(file_path)?;
+
+        if let Some(remaining_path) = remaining_path {
+            let found = self
+                .find_attrs_in_items(file_path, &ast.items, remaining_path)
+                .context(format!(
+                    "Error finding {} in file {}",
+                    remaining_path,
+                    file_path.to_string_lossy()
+                ))?;
+            // Items in out-of-line modules have their file filled in by the search of that file.
+            Ok(found.map(|mut found| {
+                found.file.get_or_insert_with(|| file_path.to_owned());
+                found
+            }))
+        } else {
+            let mut found = FoundItem {
+                line: 1,
+                extent: Some((LineColumn { line: 1, column: 0 }, ast.span().end())),
+                kind: Some("mod"),
+                ..FoundItem::new(ast.attrs.clone(), Span::call_site())
+            };
+            found.file = Some(file_path.to_owned());
+            Ok(Some(found))
+        }
+    }
+
+    /// Find `remaining_path` among `items`, the items of one module or block.
+    ///
+    /// Modules left out by their `#[cfg(...)]` are skipped, and noted for the error if the item
+    /// isn't found, unless another module with the same name is active, like the one for the other
+    /// platform.
+    fn find_attrs_in_items<'a>(
+        &self,
+        parent_path: &Path,
+        items: impl IntoIterator<Item = &'a Item>,
+        remaining_path: &RustPath,
+    ) -> Result<Option<FoundItem>> {
+        let (head, tail) = remaining_path.head_tail();
+        let visibility = self.options.visibility;
+        let mut skipped = None;
+        let mut active_mod = false;
+        let mut found = None;
+        for item in items {
+            if let Item::Mod(m) = item {
+                if visibility.allows(&m.vis) && name_matches(&m.ident, &m.attrs, head, &tail) {
+                    if let Some(condition) = self.inactive_cfg(&m.attrs) {
+                        let condition = tokens_to_string(&condition.to_token_stream());
+                        skipped = Some((m.ident.to_string(), condition));
+                        continue;
+                    }
+                    active_mod = true;
+                }
+            }
+            let item_found = self
+                .find_attrs_in_item(parent_path, item, remaining_path)
+                .context(format!("Looking for {} in {:?}", remaining_path, item))?;
+            found = found.or(item_found);
+        }
+        if let (None, Some(skipped), false) = (&found, skipped, active_mod) {
+            self.cfg_skipped.replace(Some(skipped));
+        }
+        Ok(found)
+    }
+
+    fn find_attrs_in_item(
+        &self,
+        parent_path: &Path,
+        item: &Item,
+        remaining_path: &RustPath,
+    ) -> Result<Option<FoundItem>> {
+        let (head, tail) = remaining_path.head_tail();
+        let visibility = self.options.visibility;
+
+        let found = match item {
+            Item::Struct(s) => {
+                if visibility.allows(&s.vis) && name_matches(&s.ident, &s.attrs, head, &tail) {
+                    find_attrs_in_struct(s, &tail, visibility)
+                        .context(format!("Looking inside struct {}", s.ident))
+                } else {
+                    Ok(None)
+                }
+            }
+            Item::Enum(e) => {
+                if visibility.allows(&e.vis) && name_matches(&e.ident, &e.attrs, head, &tail) {
+                    find_attrs_in_enum(e, &tail).context(format!("Looking inside enum {}", e.ident))
+                } else {
+                    Ok(None)
+                }
+            }
+            Item::Mod(m) => {
+                // Modules left out by their `#[cfg(...)]` are skipped by `find_attrs_in_items`.
+                if !visibility.allows(&m.vis) || !name_matches(&m.ident, &m.attrs, head, &tail) {
+                    Ok(None)
+                } else {
+                    self.find_attrs_in_mod(parent_path, m, &tail)
+                        .context(format!("Looking inside mod {}", m.ident))
+                }
+            }
+            Item::Impl(i) => {
+                if !type_has_name(&i.self_ty, head) {
+                    Ok(None)
+                } else if let Some(found) = self.find_attrs_in_method_body(parent_path, i, &tail)? {
+                    Ok(Some(found))
+                } else {
+                    find_attrs_in_impl(i, &tail, visibility)
+                }
+            }
+
+            Item::Trait(t) => {
+                if visibility.allows(&t.vis) && name_matches(&t.ident, &t.attrs, head, &tail) {
+                    find_attrs_in_trait(t, &tail)
+                } else {
+                    Ok(None)
+                }
+            }
+
+            Item::Const(c) => {
+                if tail.is_none()
+                    && visibility.allows(&c.vis)
+                    && name_matches(&c.ident, &c.attrs, head, &tail)
+                {
+                    Ok(Some(FoundItem {
+                        const_value: Some((c.ident.clone(), (*c.ty).clone(), (*c.expr).clone())),
+                        ..FoundItem::new(c.attrs.clone(), c.ident.span())
+                    }))
+                } else {
+                    Ok(None)
+                }
+            }
+
+            Item::Static(s) => {
+                if tail.is_none()
+                    && visibility.allows(&s.vis)
+                    && name_matches(&s.ident, &s.attrs, head, &tail)
+                {
+                    Ok(Some(FoundItem {
+                        const_value: Some((s.ident.clone(), (*s.ty).clone(), (*s.expr).clone())),
+                        ..FoundItem::new(s.attrs.clone(), s.ident.span())
+                    }))
+                } else {
+                    Ok(None)
+                }
+            }
+
+            Item::Type(t) => {
+                if tail.is_none()
+                    && visibility.allows(&t.vis)
+                    && name_matches(&t.ident, &t.attrs, head, &tail)
+                {
+                    Ok(Some(FoundItem {
+                        alias_of: Some((*t.ty).clone()),
+                        ..FoundItem::new(t.attrs.clone(), t.ident.span())
+                    }))
+                } else {
+                    Ok(None)
+                }
+            }
+
+            Item::Union(u) => {
+                if visibility.allows(&u.vis) && name_matches(&u.ident, &u.attrs, head, &tail) {
+                    find_attrs_in_union(u, &tail, visibility)
+                        .context(format!("Looking inside union {}", u.ident))
+                } else {
+                    Ok(None)
+                }
+            }
+
+            Item::Fn(f) => {
+                if !visibility.allows(&f.vis) || !name_matches(&f.sig.ident, &f.attrs, head, &tail)
+                {
+                    Ok(None)
+                } else {
+                    match &tail {
+                        None => Ok(Some(FoundItem::new(f.attrs.clone(), f.sig.ident.span()))),
+                        Some(tail) if self.options.fn_body_items => self
+                            .find_attrs_in_block(parent_path, &f.block, tail)
+                            .context(format!("Looking inside fn {}", f.sig.ident)),
+                        Some(_) => Ok(None),
+                    }
+                }
+            }
+
+            Item::Use(_) | Item::ForeignMod(_) | Item::ExternCrate(_) => Ok(None),
+
+            Item::Macro(m) => match &m.ident {
+                // `macro_rules!` macros are only public if they are exported.
+                Some(ident)
+                    if tail.is_none()
+                        && (visibility == VisibilityFilter::All
+                            || m.attrs
+                                .iter()
+                                .any(|attr| attr.path.is_ident("macro_export")))
+                        && name_matches(ident, &m.attrs, head, &tail) =>
+                {
+                    Ok(Some(FoundItem::new(m.attrs.clone(), ident.span())))
+                }
+                // Other macro invocations, like `lazy_static! { ... }`, don't name an item.
+                _ => Ok(None),
+            },
+            Item::Macro2(_) => bail!(ResolveError::UnsupportedItemKind {
+                kind: "macro 2.0".to_string()
+            }),
+            Item::TraitAlias(_) => bail!(ResolveError::UnsupportedItemKind {
+                kind: "trait alias".to_string()
+            }),
+
+            _ => bail!("Unexpected AST item {:?}", item),
+        }?;
+        // If the path ends here, the item that was found is this one rather than one inside of it.
+        Ok(match (found, item_signature(item)) {
+            (Some(found), Some(signature)) if tail.is_none() => Some(
+                found
+                    .with_signature(signature)
+                    .with_extent(item.span())
+                    .with_kind(item_kind(item)),
+            ),
+            (found, _) => found,
+        })
+    }
+
+    fn find_attrs_in_mod(
+        &self,
+        parent_path: &Path,
+        the_mod: &ItemMod,
+        remaining_path: &Option<RustPath>,
+    ) -> Result<Option<FoundItem>> {
+        if let Some((_, items)) = &the_mod.content {
+            if let Some(remaining_path) = &remaining_path {
+                self.find_attrs_in_items(parent_path, items, remaining_path)
+            } else {
+                // For inline modules syn places the inner attributes (`//!` docs) after the outer
+                // ones, so both are included in source order.
+                Ok(Some(FoundItem::new(
+                    the_mod.attrs.clone(),
+                    the_mod.ident.span(),
+                )))
+            }
+        } else {
+            let mod_path = self.mod_file_path(parent_path, the_mod)?;
+            let found = self.find_item_in_file(&mod_path, remaining_path)?;
+            if remaining_path.is_some() {
+                return Ok(found);
+            }
+            // The module's own docs also include the outer attributes of its declaration, like a
+            // `#[doc(cfg(...))]`. Included docs are left out, since they'd be read relative to the
+            // module's file instead of the declaration's.
+            Ok(found.map(|mut found| {
+                let outer = the_mod
+                    .attrs
+                    .iter()
+                    .filter(|attr| doc_include_path(attr).is_none())
+                    .cloned();
+                found.attrs = outer.chain(found.attrs).collect();
+                found
+            }))
+        }
+    }
+
+    /// Find `remaining_path` among the items declared directly in the function body `block`.
+    /// Items inside nested blocks, like the branches of an `if`, aren't found.
+    fn find_attrs_in_block(
+        &self,
+        parent_path: &Path,
+        block: &Block,
+        remaining_path: &RustPath,
+    ) -> Result<Option<FoundItem>> {
+        let items = block.stmts.iter().filter_map(|stmt| match stmt {
+            Stmt::Item(item) => Some(item),
+            _ => None,
+        });
+        self.find_attrs_in_items(parent_path, items, remaining_path)
+    }
+
+    /// Find the rest of `remaining_path` in the body of the method of `the_impl` named by its
+    /// first segment.
+    fn find_attrs_in_method_body(
+        &self,
+        parent_path: &Path,
+        the_impl: &ItemImpl,
+        remaining_path: &Option<RustPath>,
+    ) -> Result<Option<FoundItem>> {
+        if !self.options.fn_body_items {
+            return Ok(None);
+        }
+        let (method_name, rest) = match remaining_path.as_ref().map(RustPath::head_tail) {
+            Some((method_name, Some(rest))) => (method_name, rest),
+            _ => return Ok(None),
+        };
+        let allows = |vis| the_impl.trait_.is_some() || self.options.visibility.allows(vis);
+        for item in &the_impl.items {
+            if let syn::ImplItem::Method(m) = item {
+                if m.sig.ident == method_name && allows(&m.vis) {
+                    return self
+                        .find_attrs_in_block(parent_path, &m.block, &rest)
+                        .context(format!("Looking inside method {}", m.sig.ident));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    fn find_module_items(
+        &self,
+        file_path: &Path,
+        remaining_path: &Option<RustPath>,
+    ) -> Result<Option<(PathBuf, Vec<Item>)>> {
+        let ast = self.parse_file(file_path)?;
+        self.find_module_items_in(file_path, &ast.items, remaining_path)
+    }
+
+    fn find_module_items_in(
+        &self,
+        parent_path: &Path,
+        items: &[Item],
+        remaining_path: &Option<RustPath>,
+    ) -> Result<Option<(PathBuf, Vec<Item>)>> {
+        let remaining_path = match remaining_path {
+            Some(remaining_path) => remaining_path,
+            None => return Ok(Some((parent_path.to_owned(), items.to_vec()))),
+        };
+        let (head, tail) = remaining_path.head_tail();
+        let mods: Vec<&ItemMod> = items
+            .iter()
+            .filter_map(|item| match item {
+                Item::Mod(m) if m.ident == head => Some(m),
+                _ => None,
+            })
+            .collect();
+        let the_mod = mods
+            .iter()
+            .find(|m| self.inactive_cfg(&m.attrs).is_none())
+            .copied();
+        if let (None, Some(skipped)) = (the_mod, mods.first()) {
+            if let Some(condition) = self.inactive_cfg(&skipped.attrs) {
+                let condition = tokens_to_string(&condition.to_token_stream());
+                self.cfg_skipped
+                    .replace(Some((head.to_string(), condition)));
+            }
+        }
+        match the_mod {
+            None => Ok(None),
+            Some(ItemMod {
+                content: Some((_, items)),
+                ..
+            }) => self.find_module_items_in(parent_path, items, &tail),
+            Some(the_mod) => {
+                let mod_path = self.mod_file_path(parent_path, the_mod)?;
+                self.find_module_items(&mod_path, &tail)
+            }
+        }
+    }
+}
+
+/// The path inside its crate of what the `use` path `path` in the module at `module_path` refers
+/// to, or `None` if it goes above the crate root.
+///
+/// Paths that don't start with `crate`, `self`, or `super` are taken to be relative to the
+/// module, so ones that name other crates lead to items that won't be found.
+fn use_target(module_path: &[String], path: &[String]) -> Option<Vec<String>> {
+    let (mut target, rest) = match path.first().map(String::as_str) {
+        Some("crate") => (vec![], &path[1..]),
+        Some("self") => (module_path.to_vec(), &path[1..]),
+        _ => (module_path.to_vec(), path),
+    };
+    let supers = rest
+        .iter()
+        .take_while(|segment| *segment == "super")
+        .count();
+    for _ in 0..supers {
+        target.pop()?;
+    }
+    target.extend_from_slice(&rest[supers..]);
+    Some(target)
+}
+
+/// The traits named in the `#[derive(...)]` attributes in `attrs`.
+fn derived_traits(attrs: &[Attribute]) -> Vec<syn::Path> {
+    attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("derive"))
+        .filter_map(|attr| match attr.parse_meta() {
+            Ok(Meta::List(list)) => Some(list.nested),
+            _ => None,
+        })
+        .flatten()
+        .filter_map(|nested| match nested {
+            NestedMeta::Meta(Meta::Path(path)) => Some(path),
+            _ => None,
+        })
+        .collect()
+}
+
+/// A link to the standard library's docs for the trait at `trait_path`, if it is one: either a
+/// path into `std`, `core`, or `alloc`, or the bare name of one of its common traits.
+fn std_trait_url(trait_path: &syn::Path) -> Option<String> {
+    const STD_TRAITS: &[&str] = &[
+        "AsMut",
+        "AsRef",
+        "Borrow",
+        "Clone",
+        "Copy",
+        "Debug",
+        "Default",
+        "Deref",
+        "DerefMut",
+        "Display",
+        "DoubleEndedIterator",
+        "Drop",
+        "Eq",
+        "Error",
+        "ExactSizeIterator",
+        "Extend",
+        "From",
+        "FromIterator",
+        "FromStr",
+        "Hash",
+        "Into",
+        "IntoIterator",
+        "Iterator",
+        "Ord",
+        "PartialEq",
+        "PartialOrd",
+        "Send",
+        "Sync",
+        "ToOwned",
+        "ToString",
+        "TryFrom",
+        "TryInto",
+    ];
+    let segments: Vec<_> = trait_path
+        .segments
+        .iter()
+        .map(|segment| segment.ident.to_string())
+        .collect();
+    let path = match segments.first().map(String::as_str) {
+        Some("std" | "core" | "alloc") => RustPath::from_str(&segments.join("::")).ok()?,
+        Some(name) if segments.len() == 1 && STD_TRAITS.contains(&name) => {
+            RustPath::new("std").push(name)
+        }
+        _ => return None,
+    };
+    Some(docs_url(&path))
+}
+
+/// Read the text file at `path`, with Windows line endings turned into plain `\n`s, so that
+/// crates written on Windows don't leave stray `\r`s in the docs.
+fn read_text(path: &Path) -> std::io::Result<String> {
+    std::fs::read_to_string(path).map(|text| normalize_line_endings(&text))
+}
+
+fn normalize_line_endings(text: &str) -> String {
+    text.replace("\r\n", "\n")
+}
+
+/// The name, attributes, and public-ness of items that can be listed as part of a module.
+///
+/// Items without a name of their own, such as `impl` blocks and `use` declarations, are not
+/// listable.
+fn listable_item(item: &Item) -> Option<(String, &[Attribute], bool)> {
+    let is_public = |vis: &Visibility| matches!(vis, Visibility::Public(_));
+    let rv = match item {
+        Item::Const(i) => (i.ident.to_string(), &i.attrs, is_public(&i.vis)),
+        Item::Enum(i) => (i.ident.to_string(), &i.attrs, is_public(&i.vis)),
+        Item::Fn(i) => (i.sig.ident.to_string(), &i.attrs, is_public(&i.vis)),
+        Item::Macro2(i) => (i.ident.to_string(), &i.attrs, is_public(&i.vis)),
+        Item::Mod(i) => (i.ident.to_string(), &i.attrs, is_public(&i.vis)),
+        Item::Static(i) => (i.ident.to_string(), &i.attrs, is_public(&i.vis)),
+        Item::Struct(i) => (i.ident.to_string(), &i.attrs, is_public(&i.vis)),
+        Item::Trait(i) => (i.ident.to_string(), &i.attrs, is_public(&i.vis)),
+        Item::TraitAlias(i) => (i.ident.to_string(), &i.attrs, is_public(&i.vis)),
+        Item::Type(i) => (i.ident.to_string(), &i.attrs, is_public(&i.vis)),
+        Item::Union(i) => (i.ident.to_string(), &i.attrs, is_public(&i.vis)),
+        Item::Macro(i) => {
+            // `macro_rules!` macros are only public if they are exported.
+            let is_exported = i
+                .attrs
+                .iter()
+                .any(|attr| attr.path.is_ident("macro_export"));
+            (i.ident.as_ref()?.to_string(), &i.attrs, is_exported)
+        }
+        _ => return None,
+    };
+    Some((rv.0, rv.1.as_slice(), rv.2))
+}
+
+/// Whether an item called `ident` with `attrs` is the one named by the head of a path.
+///
+/// The last segment of a path can also use any of the item's `#[doc(alias = "...")]` names, like
+/// rustdoc's search does.
+fn name_matches(ident: &Ident, attrs: &[Attribute], head: &str, tail: &Option<RustPath>) -> bool {
+    ident == head || (tail.is_none() && doc_aliases(attrs).iter().any(|alias| alias == head))
+}
+
+/// The names given by `#[doc(alias = "...")]` and `#[doc(alias("...", ...))]` in `attrs`.
+fn doc_aliases(attrs: &[Attribute]) -> Vec<String> {
+    let mut aliases = vec![];
+    let doc_lists = attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("doc"))
+        .filter_map(|attr| match attr.parse_meta() {
+            Ok(Meta::List(list)) => Some(list),
+            _ => None,
+        });
+    for list in doc_lists {
+        for nested in &list.nested {
+            match nested {
+                NestedMeta::Meta(Meta::NameValue(name_value))
+                    if name_value.path.is_ident("alias") =>
+                {
+                    if let Lit::Str(alias) = &name_value.lit {
+                        aliases.push(alias.value());
+                    }
+                }
+                NestedMeta::Meta(Meta::List(alias_list)) if alias_list.path.is_ident("alias") => {
+                    aliases.extend(alias_list.nested.iter().filter_map(|alias| match alias {
+                        NestedMeta::Lit(Lit::Str(alias)) => Some(alias.value()),
+                        _ => None,
+                    }));
+                }
+                _ => {}
+            }
+        }
+    }
+    aliases
+}
+
+/// Whether `attrs` contains `#[doc(hidden)]`.
+fn is_doc_hidden(attrs: &[Attribute]) -> bool {
+    attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("doc"))
+        .filter_map(|attr| attr.parse_meta().ok())
+        .any(|meta| match meta {
+            Meta::List(list) => list.nested.iter().any(
+                |nested| matches!(nested, NestedMeta::Meta(Meta::Path(p)) if p.is_ident("hidden")),
+            ),
+            _ => false,
+        })
+}
+
+/// Whether any of `attrs` has the path `name`, written like `stable` or `my_macros::since`.
+fn has_attr(attrs: &[Attribute], name: &str) -> bool {
+    attrs.iter().any(|attr| {
+        let path: Vec<_> = attr
+            .path
+            .segments
+            .iter()
+            .map(|segment| segment.ident.to_string())
+            .collect();
+        path.join("::") == name
+    })
+}
+
+/// Find the associated item named by `remaining_path` in `the_impl`.
+///
+/// Impls are matched on the name of their self type alone, so generic, lifetime, and const
+/// parameters on the impl don't matter. A path that ends at the type itself refers to the type's
+/// definition rather than to any of its impls, so it is never found here.
+///
+/// The items of trait impls are as visible as the trait, so only the items of inherent impls are
+/// checked against `visibility`.
+fn find_attrs_in_impl(
+    the_impl: &ItemImpl,
+    remaining_path: &Option<RustPath>,
+    visibility: VisibilityFilter,
+) -> Result<Option<FoundItem>> {
+    let allows = |vis: &Visibility| the_impl.trait_.is_some() || visibility.allows(vis);
+    let remaining_path = match remaining_path {
+        Some(remaining_path) => remaining_path,
+        None => return Ok(None),
+    };
+    let (head, tail) = remaining_path.head_tail();
+    let found = the_impl.items.iter().find_map(|item| match item {
+        syn::ImplItem::Const(c)
+            if allows(&c.vis) && name_matches(&c.ident, &c.attrs, head, &tail) =>
+        {
+            let (vis, ident, ty) = (&c.vis, &c.ident, &c.ty);
+            Some(
+                FoundItem {
+                    const_value: Some((c.ident.clone(), c.ty.clone(), c.expr.clone())),
+                    ..FoundItem::new(c.attrs.clone(), c.ident.span())
+                }
+                .with_signature(quote!(#vis const #ident: #ty))
+                .with_extent(c.span())
+                .with_kind("const"),
+            )
+        }
+        syn::ImplItem::Method(m)
+            if allows(&m.vis) && name_matches(&m.sig.ident, &m.attrs, head, &tail) =>
+        {
+            let (vis, sig) = (&m.vis, &m.sig);
+            Some(
+                FoundItem::new(m.attrs.clone(), m.sig.ident.span())
+                    .with_signature(quote!(#vis #sig))
+                    .with_extent(m.span())
+                    .with_kind("method"),
+            )
+        }
+        syn::ImplItem::Type(t)
+            if allows(&t.vis) && name_matches(&t.ident, &t.attrs, head, &tail) =>
+        {
+            // Generic associated types are found by their name alone, like other generic items.
+            let (vis, ident, generics) = (&t.vis, &t.ident, &t.generics);
+            let where_clause = &generics.where_clause;
+            Some(
+                FoundItem::new(t.attrs.clone(), t.ident.span())
+                    .with_signature(quote!(#vis type #ident #generics #where_clause))
+                    .with_extent(t.span())
+                    .with_kind("type"),
+            )
+        }
+        _ => None,
+    });
+    ensure_associated_item_is_leaf(found.as_ref(), head, &tail)?;
+    Ok(found)
+}
+
+fn find_attrs_in_trait(
+    the_trait: &ItemTrait,
+    remaining_path: &Option<RustPath>,
+) -> Result<Option<FoundItem>> {
+    let remaining_path = match remaining_path {
+        Some(remaining_path) => remaining_path,
+        None => {
+            return Ok(Some(FoundItem::new(
+                the_trait.attrs.clone(),
+                the_trait.ident.span(),
+            )))
+        }
+    };
+    let (head, tail) = remaining_path.head_tail();
+    let found = the_trait.items.iter().find_map(|item| match item {
+        TraitItem::Const(c) if name_matches(&c.ident, &c.attrs, head, &tail) => {
+            let (ident, ty) = (&c.ident, &c.ty);
+            let mut found = FoundItem::new(c.attrs.clone(), c.ident.span())
+                .with_signature(quote!(const #ident: #ty))
+                .with_extent(c.span())
+                .with_kind("const");
+            // A default value is the const's value wherever implementors don't override it.
+            if let Some((_, expr)) = &c.default {
+                found.const_value = Some((ident.clone(), ty.clone(), expr.clone()));
+                found = found.with_default(expr_to_string(expr));
+            }
+            Some(found)
+        }
+        TraitItem::Method(m) if name_matches(&m.sig.ident, &m.attrs, head, &tail) => {
+            let sig = &m.sig;
+            Some(
+                FoundItem::new(m.attrs.clone(), m.sig.ident.span())
+                    .with_signature(quote!(#sig))
+                    .with_extent(m.span())
+                    .with_kind("method"),
+            )
+        }
+        TraitItem::Type(t) if name_matches(&t.ident, &t.attrs, head, &tail) => {
+            let (ident, generics) = (&t.ident, &t.generics);
+            let (colon, bounds) = (&t.colon_token, &t.bounds);
+            let where_clause = &generics.where_clause;
+            let mut found = FoundItem::new(t.attrs.clone(), t.ident.span())
+                .with_signature(quote!(type #ident #generics #colon #bounds #where_clause))
+                .with_extent(t.span())
+                .with_kind("type");
+            if let Some((_, default)) = &t.default {
+                found = found.with_default(signature_to_string(&default.to_token_stream()));
+            }
+            Some(found)
+        }
+        _ => None,
+    });
+    ensure_associated_item_is_leaf(found.as_ref(), head, &tail)?;
+    Ok(found)
+}
+
+/// Associated items have no items inside of them, so a path that goes past one that was found is
+/// a mistake rather than a missing item.
+fn ensure_associated_item_is_leaf(
+    found: Option<&FoundItem>,
+    name: &str,
+    tail: &Option<RustPath>,
+) -> Result<()> {
+    if let (Some(found), Some(tail)) = (found, tail) {
+        bail!(
+            "{} is an associated {} with no subitems, so `{}::{}` is not a valid path",
+            name,
+            found.kind.unwrap_or("item"),
+            name,
+            tail
+        );
+    }
+    Ok(())
+}
+
+/// The docs of the method `name` of `the_trait`, if it has a default body that implementors can
+/// inherit.
+fn find_trait_default_method(the_trait: &ItemTrait, name: &str) -> Option<FoundItem> {
+    the_trait.items.iter().find_map(|item| match item {
+        TraitItem::Method(m) if m.default.is_some() && m.sig.ident == name => {
+            let sig = &m.sig;
+            Some(
+                FoundItem::new(m.attrs.clone(), m.sig.ident.span())
+                    .with_signature(quote!(#sig))
+                    .with_extent(m.span())
+                    .with_kind("method"),
+            )
+        }
+        _ => None,
+    })
+}
+
+fn find_attrs_in_struct(
+    the_struct: &ItemStruct,
+    remaining_path: &Option<RustPath>,
+    visibility: VisibilityFilter,
+) -> Result<Option<FoundItem>> {
+    if let Some(remaining_path) = remaining_path {
+        let (head, tail) = remaining_path.head_tail();
+        let owner = format!("struct {}", the_struct.ident);
+        let attrs = find_attrs_in_fields(&owner, &the_struct.fields, head, visibility)?;
+        if attrs.is_some() {
+            ensure_field_is_leaf(&owner, head, &tail)?;
+        }
+        Ok(attrs)
+    } else {
+        Ok(Some(FoundItem::new(
+            the_struct.attrs.clone(),
+            the_struct.ident.span(),
+        )))
+    }
+}
+
+fn find_attrs_in_union(
+    the_union: &ItemUnion,
+    remaining_path: &Option<RustPath>,
+    visibility: VisibilityFilter,
+) -> Result<Option<FoundItem>> {
+    if let Some(remaining_path) = remaining_path {
+        let (head, tail) = remaining_path.head_tail();
+        let owner = format!("union {}", the_union.ident);
+        let fields = Fields::Named(the_union.fields.clone());
+        let attrs = find_attrs_in_fields(&owner, &fields, head, visibility)?;
+        if attrs.is_some() {
+            ensure_field_is_leaf(&owner, head, &tail)?;
+        }
+        Ok(attrs)
+    } else {
+        Ok(Some(FoundItem::new(
+            the_union.attrs.clone(),
+            the_union.ident.span(),
+        )))
+    }
+}
+
+fn find_attrs_in_enum(
+    the_enum: &ItemEnum,
+    remaining_path: &Option<RustPath>,
+) -> Result<Option<FoundItem>> {
+    remaining_path.as_ref().map_or_else(
+        || {
+            Ok(Some(FoundItem::new(
+                the_enum.attrs.clone(),
+                the_enum.ident.span(),
+            )))
+        },
+        |remaining_path| {
+            let (head, tail) = remaining_path.head_tail();
+            let rv = the_enum
+                .variants
+                .iter()
+                .find(|v| name_matches(&v.ident, &v.attrs, head, &tail))
+                .map(|v| find_attrs_in_enum_variant(v, &tail));
+            match rv {
+                Some(Ok(Some(v))) => Ok(Some(v)),
+                Some(Err(err)) => Err(err),
+                Some(Ok(None)) | None => Ok(None),
+            }
+        },
+    )
+}
+
+fn find_attrs_in_enum_variant(
+    the_variant: &Variant,
+    remaining_path: &Option<RustPath>,
+) -> Result<Option<FoundItem>> {
+    if let Some(remaining_path) = remaining_path {
+        let (head, tail) = remaining_path.head_tail();
+        let owner = format!("enum variant {}", the_variant.ident);
+        // The fields of enum variants are as visible as the enum.
+        let attrs = find_attrs_in_fields(&owner, &the_variant.fields, head, VisibilityFilter::All)?;
+        if attrs.is_some() {
+            ensure_field_is_leaf(&owner, head, &tail)?;
+        }
+        Ok(attrs)
+    } else {
+        let mut variant = the_variant.clone();
+        variant.attrs.clear();
+        variant
+            .fields
+            .iter_mut()
+            .for_each(|field| field.attrs.clear());
+        Ok(Some(
+            FoundItem::new(the_variant.attrs.clone(), the_variant.ident.span())
+                .with_signature(variant.to_token_stream())
+                .with_extent(the_variant.span())
+                .with_kind("variant"),
+        ))
+    }
+}
+
+/// Fields don't contain other items, so a path that continues past a field can never be found.
+fn ensure_field_is_leaf(owner: &str, field: &str, tail: &Option<RustPath>) -> Result<()> {
+    if let Some(tail) = tail {
+        bail!(
+            "Fields of {} have no subitems, so `{}::{}` is not a valid path",
+            owner,
+            field,
+            tail
+        );
+    }
+    Ok(())
+}
+
+/// Find the field called `name` in the fields of `owner`, which for tuple fields is its index.
+///
+/// An index that is past the end of a tuple is an error rather than a missing item, since it
+/// can only be a typo, and the number of fields helps to fix it.
+fn find_attrs_in_fields(
+    owner: &str,
+    the_fields: &Fields,
+    name: &str,
+    visibility: VisibilityFilter,
+) -> Result<Option<FoundItem>> {
+    let rv = match the_fields {
+        Fields::Named(FieldsNamed { named, .. }) => named
+            .iter()
+            .find(|f| f.ident.as_ref().map(ToString::to_string) == Some(name.to_string()))
+            .filter(|field| visibility.allows(&field.vis))
+            .map(|field| {
+                let span = field
+                    .ident
+                    .as_ref()
+                    .map_or_else(|| field.ty.span(), Ident::span);
+                FoundItem::new(field.attrs.clone(), span)
+                    .with_signature(field_signature(field))
+                    .with_extent(field.span())
+                    .with_kind("field")
+                    .with_field_type(field.ty.clone())
+            }),
+
+        Fields::Unnamed(FieldsUnnamed { unnamed, .. }) => {
+            ensure!(
+                !name.starts_with('-'),
+                "Tuple fields are numbered from 0, so `{}` is not a valid field of {}",
+                name,
+                owner
+            );
+            // Generated code often names tuple fields like `_0`, so that works too.
+            let digits = name.strip_prefix('_').unwrap_or(name);
+            // `parse` accepts a leading `+`, which isn't valid in a path.
+            ensure!(
+                !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()),
+                "Invalid field name {} for tuple {}, expected a number like `0` or `_0`",
+                name,
+                owner
+            );
+            let index: usize = digits.parse().map_err(|err| {
+                anyhow!(
+                    "Invalid field name {} for tuple {}, expected a number: {}",
+                    name,
+                    owner,
+                    err
+                )
+            })?;
+            let field = unnamed.iter().nth(index).ok_or_else(|| {
+                let valid = match unnamed.len() {
+                    0 => String::new(),
+                    1 => ", the only field is 0".to_string(),
+                    len => format!(", the fields are 0 to {}", len - 1),
+                };
+                anyhow!(
+                    "The tuple {} has {} field(s), index {} is out of range{}",
+                    owner,
+                    unnamed.len(),
+                    index,
+                    valid
+                )
+            })?;
+            visibility.allows(&field.vis).then(|| {
+                FoundItem::new(field.attrs.clone(), field.ty.span())
+                    .with_signature(field_signature(field))
+                    .with_extent(field.span())
+                    .with_kind("field")
+                    .with_field_type(field.ty.clone())
+            })
+        }
+        Fields::Unit => None,
+    };
+    Ok(rv)
+}
+
+/// Extract the docs from `attrs`, including docs from `#[cfg_attr(...)]` attributes whose
+/// condition is active in `cfg`. The value of a `#[doc = concat!("...", ...)]` attribute is put
+/// together from its literals.
+///
+/// Files included with `#[doc = include_str!("...")]` are only read in by
+/// [`Resolver::read_docs`], and are left out otherwise.
+fn doc_fragments(attrs: &[Attribute], cfg: &CfgSet) -> Vec<DocFragment> {
+    let mut fragments = vec![];
+    for attr in attrs {
+        if let Some(text) = doc_concat_text(attr) {
+            let text = normalize_line_endings(&text);
+            fragments.push(DocFragment::Comment(beautify_block_comment(&text)));
+        } else if attr.path.is_ident("doc") || attr.path.is_ident("cfg_attr") {
+            if let Ok(meta) = attr.parse_meta() {
+                collect_docs(&meta, cfg, &mut fragments);
+            }
+        }
+    }
+    fragments
+}
+
+/// Put the docstring together from `fragments`.
+///
+/// Like rustdoc, the lines are dedented together by their common indentation, so that indented
+/// content like nested lists keeps its shape, and each attribute's text is a line of its own, so
+/// that adjacent `#[doc = "..."]` attributes written by a macro read the same as `///` comments.
+fn docs_to_string(fragments: &[DocFragment]) -> String {
+    unindent_fragments(fragments).join("\n")
+}
+
+/// A Markdown quote saying that the item with `attrs` is deprecated, including the version and
+/// note from its `#[deprecated(since = "...", note = "...")]` attribute, if it has one.
+fn deprecation_notice(attrs: &[Attribute]) -> Option<String> {
+    let meta = attrs
+        .iter()
+        .find(|attr| attr.path.is_ident("deprecated"))?
+        .parse_meta()
+        .ok()?;
+    let string_value = |lit: &Lit| match lit {
+        Lit::Str(s) => Some(s.value()),
+        _ => None,
+    };
+    let (mut since, mut note) = (None, None);
+    match &meta {
+        Meta::NameValue(nv) => note = string_value(&nv.lit),
+        Meta::List(list) => {
+            for nested in &list.nested {
+                if let NestedMeta::Meta(Meta::NameValue(nv)) = nested {
+                    if nv.path.is_ident("since") {
+                        since = string_value(&nv.lit);
+                    } else if nv.path.is_ident("note") {
+                        note = string_value(&nv.lit);
+                    }
+                }
+            }
+        }
+        Meta::Path(_) => {}
+    }
+    let label = match since {
+        Some(since) => format!("Deprecated since {}", since),
+        None => "Deprecated".to_string(),
+    };
+    Some(match note {
+        Some(note) => format!("> **{}:** {}", label, note),
+        None => format!("> **{}**", label),
+    })
+}
+
+/// A Markdown quote saying that the result of the item with `attrs` should be used, including the
+/// message from its `#[must_use = "..."]` attribute, if it has one.
+fn must_use_notice(attrs: &[Attribute]) -> Option<String> {
+    let meta = attrs
+        .iter()
+        .find(|attr| attr.path.is_ident("must_use"))?
+        .parse_meta()
+        .ok()?;
+    Some(match meta {
+        Meta::NameValue(nv) => match nv.lit {
+            Lit::Str(message) => format!("> **Must be used:** {}", message.value()),
+            _ => "> **Must be used**".to_string(),
+        },
+        _ => "> **Must be used**".to_string(),
+    })
+}
+
+/// A Markdown quote saying which `cfg` options the item with `attrs` is available under, from its
+/// `#[doc(cfg(...))]` attributes, including those inside `#[cfg_attr(...)]` attributes whose
+/// condition is active in `cfg`.
+fn cfg_notice(attrs: &[Attribute], cfg: &CfgSet) -> Option<String> {
+    let mut conditions = vec![];
+    for attr in attrs {
+        if attr.path.is_ident("doc") || attr.path.is_ident("cfg_attr") {
+            if let Ok(meta) = attr.parse_meta() {
+                collect_doc_cfgs(&meta, cfg, &mut conditions);
+            }
+        }
+    }
+    if conditions.is_empty() {
+        return None;
+    }
+    let described: Vec<String> = conditions.iter().map(cfg::describe).collect();
+    Some(format!(
+        "> Available on **{}** only.",
+        described.join(" and ")
+    ))
+}
+
+/// Collect the conditions of `doc(cfg(...))` attributes, like [`collect_docs`] does for docs.
+fn collect_doc_cfgs(meta: &Meta, cfg: &CfgSet, conditions: &mut Vec<Meta>) {
+    match meta {
+        Meta::List(list) if list.path.is_ident("doc") => {
+            for nested in &list.nested {
+                if let NestedMeta::Meta(Meta::List(doc_cfg)) = nested {
+                    if let (true, Some(NestedMeta::Meta(condition))) =
+                        (doc_cfg.path.is_ident("cfg"), doc_cfg.nested.first())
+                    {
+                        conditions.push(condition.clone());
+                    }
+                }
+            }
+        }
+        Meta::List(list) if list.path.is_ident("cfg_attr") => {
+            let mut nested = list.nested.iter();
+            if let Some(NestedMeta::Meta(condition)) = nested.next() {
+                if cfg.is_active(condition) {
+                    for attr in nested {
+                        if let NestedMeta::Meta(meta) = attr {
+                            collect_doc_cfgs(meta, cfg, conditions);
+                        }
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// The path given to `include_str!` by a `#[doc = include_str!("...")]` attribute, if `attr` is
+/// one.
+fn doc_include_path(attr: &Attribute) -> Option<String> {
+    if !attr.path.is_ident("doc") {
+        return None;
+    }
+    let parse_value = |input: ParseStream| {
+        input.parse::<syn::Token![=]>()?;
+        input.parse::<Expr>()
+    };
+    match parse_value.parse2(attr.tokens.clone()).ok()? {
+        Expr::Macro(expr) if expr.mac.path.is_ident("include_str") => expr
+            .mac
+            .parse_body::<LitStr>()
+            .ok()
+            .map(|path| path.value()),
+        _ => None,
+    }
+}
+
+/// The file named by a module's `#[path = "..."]` attribute, or by a `path = "..."` inside a
+/// `#[cfg_attr(...)]` whose condition is active in `cfg`.
+fn mod_path_attr(attrs: &[Attribute], cfg: &CfgSet) -> Option<String> {
+    fn path_value(meta: &Meta, cfg: &CfgSet) -> Option<String> {
+        match meta {
+            Meta::NameValue(name_value) if name_value.path.is_ident("path") => {
+                match &name_value.lit {
+                    Lit::Str(path) => Some(path.value()),
+                    _ => None,
+                }
+            }
+            Meta::List(list) if list.path.is_ident("cfg_attr") => {
+                let mut nested = list.nested.iter();
+                match nested.next() {
+                    Some(NestedMeta::Meta(condition)) if cfg.is_active(condition) => nested
+                        .find_map(|attr| match attr {
+                            NestedMeta::Meta(meta) => path_value(meta, cfg),
+                            NestedMeta::Lit(_) => None,
+                        }),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+    attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("path") || attr.path.is_ident("cfg_attr"))
+        .filter_map(|attr| attr.parse_meta().ok())
+        .find_map(|meta| path_value(&meta, cfg))
+}
+
+/// The text of a `#[doc = concat!(...)]` attribute, if `attr` is one whose arguments are all
+/// literals, joined like `concat!` does.
+fn doc_concat_text(attr: &Attribute) -> Option<String> {
+    if !attr.path.is_ident("doc") {
+        return None;
+    }
+    let parse_value = |input: ParseStream| {
+        input.parse::<syn::Token![=]>()?;
+        input.parse::<Expr>()
+    };
+    let mac = match parse_value.parse2(attr.tokens.clone()).ok()? {
+        Expr::Macro(expr) if expr.mac.path.is_ident("concat") => expr.mac,
+        _ => return None,
+    };
+    let literals = mac
+        .parse_body_with(Punctuated::<Lit, syn::Token![,]>::parse_terminated)
+        .ok()?;
+    literals
+        .iter()
+        .map(|lit| match lit {
+            Lit::Str(s) => Some(s.value()),
+            Lit::Char(c) => Some(c.value().to_string()),
+            Lit::Int(i) => Some(i.base10_digits().to_string()),
+            Lit::Float(f) => Some(f.base10_digits().to_string()),
+            Lit::Bool(b) => Some(b.value.to_string()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// A piece of an item's docs, from one attribute.
+#[derive(Debug, Clone)]
+enum DocFragment {
+    /// A doc comment or `#[doc = "..."]` attribute.
+    Comment(String),
+    /// The contents of a file included with `#[doc = include_str!("...")]`, which are Markdown
+    /// as written rather than indented comment text.
+    Included(String),
+}
+
+/// The piece of an item's docs made of `contents`, the text of a file included with
+/// `#[doc = include_str!("...")]`.
+fn included_fragment(contents: &str) -> DocFragment {
+    let contents = normalize_line_endings(contents);
+    DocFragment::Included(contents.trim_end_matches('\n').to_string())
+}
+
+/// Collect the docs from a `doc = "..."` attribute, or from the attributes inside
+/// `cfg_attr(condition, attrs...)` if the condition is active.
+///
+/// Other forms, like `doc(hidden)`, don't contribute to the docstring.
+fn collect_docs(meta: &Meta, cfg: &CfgSet, fragments: &mut Vec<DocFragment>) {
+    match meta {
+        Meta::NameValue(name_value) if name_value.path.is_ident("doc") => {
+            if let Lit::Str(s) = &name_value.lit {
+                let text = normalize_line_endings(&s.value());
+                fragments.push(DocFragment::Comment(beautify_block_comment(&text)));
+            }
+        }
+        Meta::List(list) if list.path.is_ident("cfg_attr") => {
+            let mut nested = list.nested.iter();
+            if let Some(NestedMeta::Meta(condition)) = nested.next() {
+                if cfg.is_active(condition) {
+                    for attr in nested {
+                        if let NestedMeta::Meta(meta) = attr {
+                            collect_docs(meta, cfg, fragments);
+                        }
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Clean up the text of a `/** ... */` block comment, removing the blank first and last lines
+/// and the ` * ` decoration down the left side, if every line has one.
+///
+/// Line comments are a single line, so they are returned unchanged.
+fn beautify_block_comment(text: &str) -> String {
+    if !text.contains('\n') {
+        return text.to_string();
+    }
+    let mut lines: Vec<&str> = text.lines().collect();
+    if lines.first().is_some_and(|line| line.trim().is_empty()) {
+        lines.remove(0);
+    }
+    if lines.last().is_some_and(|line| line.trim().is_empty()) {
+        lines.pop();
+    }
+    if lines.iter().all(|line| line.trim_start().starts_with('*')) {
+        lines = lines.iter().map(|line| &line.trim_start()[1..]).collect();
+    }
+    lines.join("\n")
+}
+
+/// Split `fragments` into lines and remove the indentation they all share.
+///
+/// Doc comments usually have a space after the `///`, while `#[doc = "..."]` attributes usually
+/// don't. Lines with no indentation at all don't count towards the shared indentation, so that
+/// the space after the `///` is still removed when both are mixed.
+///
+/// Once some lines have no indentation, the indentation of the others is relative to them, like
+/// that of a nested list item or an indented code block, so only the single space a doc comment
+/// starts with is removed. More than one space would change what the Markdown means. Lines inside
+/// fenced code blocks aren't Markdown, so they don't hold back the rest of the docs, and keep
+/// what is left of their indentation whatever the fence's language.
+///
+/// Trailing whitespace is removed, except for the two spaces that end a line with a Markdown hard
+/// line break. Included files are left as they are, since their indentation is part of the
+/// Markdown.
+fn unindent_fragments(fragments: &[DocFragment]) -> Vec<String> {
+    let indent = |line: &str| line.len() - line.trim_start().len();
+    let lines: Vec<_> = fragments
+        .iter()
+        .flat_map(|fragment| {
+            let (text, is_comment) = match fragment {
+                DocFragment::Comment(text) => (text, true),
+                DocFragment::Included(text) => (text, false),
+            };
+            text.split('\n').map(move |line| (line, is_comment))
+        })
+        .collect();
+
+    let comment_lines = || {
+        lines
+            .iter()
+            .filter(|&&(line, is_comment)| is_comment && !line.trim().is_empty())
+    };
+    let min_indent = comment_lines()
+        .map(|(line, _)| indent(line))
+        .filter(|&indent| indent > 0)
+        .min()
+        .unwrap_or(0);
+    let mut fence = None;
+    let has_flush_markdown = lines.iter().any(|&(line, is_comment)| {
+        let in_fence = fence.is_some();
+        update_fence(&mut fence, line);
+        is_comment && !in_fence && !line.trim().is_empty() && indent(line) == 0
+    });
+    let min_indent = match (has_flush_markdown, min_indent) {
+        (false, min_indent) => min_indent,
+        (true, 1) => 1,
+        (true, _) => 0,
+    };
+
+    lines
+        .into_iter()
+        .map(|(line, is_comment)| {
+            let removed = if is_comment { min_indent } else { 0 };
+            let unindented = line[removed.min(indent(line))..].trim_end();
+            if !unindented.is_empty() && line.ends_with("  ") {
+                format!("{}  ", unindented)
+            } else {
+                unindented.to_string()
+            }
+        })
+        .collect()
+}
+
+/// Keep track of whether `line` opens or closes a fenced code block, where `fence` holds the
+/// character and length of the open fence, if there is one.
+fn update_fence(fence: &mut Option<(char, usize)>, line: &str) {
+    let trimmed = line.trim_start();
+    let marker = match trimmed.chars().next() {
+        Some(c @ ('`' | '~')) => c,
+        _ => return,
+    };
+    let len = trimmed.chars().take_while(|&c| c == marker).count();
+    match *fence {
+        None if len >= 3 => *fence = Some((marker, len)),
+        // A closing fence has nothing after it, and is at least as long as the opening one.
+        Some((open, open_len))
+            if marker == open && len >= open_len && trimmed[len..].trim().is_empty() =>
+        {
+            *fence = None;
+        }
+        _ => {}
+    }
+}
+
+/// Format an expression the way it would usually be written in source code.
+///
+/// Literals are shown as written. Other expressions are printed from their tokens, with the
+/// spaces that token printing puts around punctuation like `::` and `(` removed.
+fn expr_to_string(expr: &Expr) -> String {
+    if let Expr::Lit(lit) = expr {
+        return lit.lit.to_token_stream().to_string();
+    }
+    tokens_to_string(&expr.to_token_stream())
+}
+
+/// Format the signature of an item the way it would usually be written in source code.
+///
+/// This is like [`expr_to_string`], but the spaces around `<`, `>`, `&`, and `:` are fixed too,
+/// since in signatures they are parts of types rather than operators.
+fn signature_to_string(signature: &TokenStream) -> String {
+    lazy_static! {
+        static ref SPACE_AROUND_OPEN_ANGLE: Regex = Regex::new(r"(\w) ?< ?").unwrap();
+        // `->` and `=>` aren't closing brackets.
+        static ref SPACE_BEFORE_CLOSE_ANGLE: Regex = Regex::new(r"([^-= ]) >").unwrap();
+        static ref SPACE_AFTER_CLOSE_ANGLE: Regex = Regex::new(r"([^-])> \(").unwrap();
+        static ref SPACE_AFTER_REF: Regex = Regex::new(r"& ").unwrap();
+        static ref SPACE_BEFORE_COLON: Regex = Regex::new(r"(\w|>|\)) : ").unwrap();
+    }
+    let mut tokens = tokens_to_string(signature);
+    tokens = SPACE_AROUND_OPEN_ANGLE
+        .replace_all(&tokens, "$1<")
+        .into_owned();
+    // Nested generics like `Vec<Option<T> >` need more than one pass, since matches can't overlap.
+    while SPACE_BEFORE_CLOSE_ANGLE.is_match(&tokens) {
+        tokens = SPACE_BEFORE_CLOSE_ANGLE
+            .replace_all(&tokens, "$1>")
+            .into_owned();
+    }
+    let tokens = SPACE_AFTER_CLOSE_ANGLE.replace_all(&tokens, "$1>(");
+    let tokens = SPACE_AFTER_REF.replace_all(&tokens, "&");
+    SPACE_BEFORE_COLON.replace_all(&tokens, "$1: ").into_owned()
+}
+
+/// The keyword that declares `item`, like `struct` or `fn`, used as the kind of item it is.
+fn item_kind(item: &Item) -> &'static str {
+    match item {
+        Item::Const(_) => "const",
+        Item::Enum(_) => "enum",
+        Item::Fn(_) => "fn",
+        Item::Macro(_) => "macro",
+        Item::Mod(_) => "mod",
+        Item::Static(_) => "static",
+        Item::Struct(_) => "struct",
+        Item::Trait(_) => "trait",
+        Item::Type(_) => "type",
+        Item::Union(_) => "union",
+        _ => "item",
+    }
+}
+
+/// The signature of an item, without its docs or body, for items that have a name.
+fn item_signature(item: &Item) -> Option<TokenStream> {
+    let rv = match item {
+        Item::Const(i) => {
+            let (vis, ident, ty) = (&i.vis, &i.ident, &i.ty);
+            quote!(#vis const #ident: #ty)
+        }
+        Item::Enum(i) => {
+            let (vis, ident, generics) = (&i.vis, &i.ident, &i.generics);
+            quote!(#vis enum #ident #generics)
+        }
+        Item::Fn(i) => {
+            let (vis, sig) = (&i.vis, &i.sig);
+            quote!(#vis #sig)
+        }
+        Item::Macro(i) => {
+            let ident = i.ident.as_ref()?;
+            quote!(macro_rules! #ident)
+        }
+        Item::Mod(i) => {
+            let (vis, ident) = (&i.vis, &i.ident);
+            quote!(#vis mod #ident)
+        }
+        Item::Static(i) => {
+            let (vis, mutability, ident, ty) = (&i.vis, &i.mutability, &i.ident, &i.ty);
+            quote!(#vis static #mutability #ident: #ty)
+        }
+        Item::Struct(i) => {
+            let (vis, ident, generics) = (&i.vis, &i.ident, &i.generics);
+            quote!(#vis struct #ident #generics)
+        }
+        Item::Trait(i) => {
+            let (vis, ident, generics) = (&i.vis, &i.ident, &i.generics);
+            quote!(#vis trait #ident #generics)
+        }
+        Item::Type(i) => {
+            let (vis, ident, generics, ty) = (&i.vis, &i.ident, &i.generics, &i.ty);
+            quote!(#vis type #ident #generics = #ty)
+        }
+        Item::Union(i) => {
+            let (vis, ident, generics) = (&i.vis, &i.ident, &i.generics);
+            quote!(#vis union #ident #generics)
+        }
+        _ => return None,
+    };
+    Some(rv)
+}
+
+fn field_signature(field: &Field) -> TokenStream {
+    let mut field = field.clone();
+    field.attrs.clear();
+    field.to_token_stream()
+}
+
+/// Print `tokens`, without the spaces that token printing puts around punctuation like `::` and
+/// `(`.
+fn tokens_to_string(tokens: &TokenStream) -> String {
+    lazy_static! {
+        static ref SPACE_BEFORE: Regex = Regex::new(r" ([,;.)\]]|::)").unwrap();
+        static ref SPACE_AFTER: Regex = Regex::new(r"([(\[.]|::) ").unwrap();
+        // Calls, indexing, and macro invocations directly follow the thing they apply to.
+        static ref SPACE_IN_CALL: Regex = Regex::new(r"(\w) ([!(\[])").unwrap();
+        static ref SPACE_AFTER_BANG: Regex = Regex::new(r"! ([(\[{])").unwrap();
+    }
+    let tokens = tokens.to_string();
+    let tokens = SPACE_BEFORE.replace_all(&tokens, "$1");
+    let tokens = SPACE_AFTER.replace_all(&tokens, "$1");
+    let tokens = SPACE_IN_CALL.replace_all(&tokens, "$1$2");
+    SPACE_AFTER_BANG.replace_all(&tokens, "!$1").into_owned()
+}
+
+fn type_has_name(the_type: &Type, name: &str) -> bool {
+    type_name(the_type).is_some_and(|type_name| type_name == name)
+}
+
+/// The name of the type that `the_type` refers to, without its module path or generics.
+///
+/// Wrappers like references, parentheses, slices, and arrays are looked through, so `&[Crab]`
+/// and `[Crab; 8]` are both named `Crab`. Trait objects are named after their first trait, so
+/// `dyn Speak + Send` is named `Speak`.
+fn type_name(the_type: &Type) -> Option<String> {
+    match the_type {
+        Type::Path(p) => p
+            .path
+            .segments
+            .last()
+            .map(|segment| segment.ident.to_string()),
+        Type::TraitObject(trait_object) => {
+            trait_object.bounds.iter().find_map(|bound| match bound {
+                TypeParamBound::Trait(bound) => bound
+                    .path
+                    .segments
+                    .last()
+                    .map(|segment| segment.ident.to_string()),
+                TypeParamBound::Lifetime(_) => None,
+            })
+        }
+        Type::Reference(reference) => type_name(&reference.elem),
+        Type::Paren(paren) => type_name(&paren.elem),
+        Type::Group(group) => type_name(&group.elem),
+        Type::Slice(slice) => type_name(&slice.elem),
+        Type::Array(array) => type_name(&array.elem),
+        _ => None,
+    }
+}
+
+/// The path that `the_type` refers to, like `["crustaceans", "Crab"]` for `&crustaceans::Crab`,
+/// without its generics.
+///
+/// References, parentheses, slices, arrays, and trait objects are looked through like in
+/// [`type_name`], but generic wrappers like `Box<Crab>` are named after the wrapper.
+fn type_path(the_type: &Type) -> Option<Vec<String>> {
+    match the_type {
+        Type::Path(p) if p.qself.is_none() => Some(path_segments(&p.path)),
+        Type::TraitObject(trait_object) => {
+            trait_object.bounds.iter().find_map(|bound| match bound {
+                TypeParamBound::Trait(bound) => Some(path_segments(&bound.path)),
+                TypeParamBound::Lifetime(_) => None,
+            })
+        }
+        Type::Reference(reference) => type_path(&reference.elem),
+        Type::Paren(paren) => type_path(&paren.elem),
+        Type::Group(group) => type_path(&group.elem),
+        Type::Slice(slice) => type_path(&slice.elem),
+        Type::Array(array) => type_path(&array.elem),
+        _ => None,
+    }
+}
+
+/// The names of the segments of `path`, without their generic arguments.
+fn path_segments(path: &syn::Path) -> Vec<String> {
+    path.segments
+        .iter()
+        .map(|segment| segment.ident.to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        deprecation_notice, doc_fragments, docs_to_string, expr_to_string, included_fragment,
+        item_signature, must_use_notice, signature_to_string, type_name, use_target, MethodOrder,
+        ResolveOptions, Resolver, VisibilityFilter,
+    };
+    use crate::{
+        expand_chapter, test_support::TempDir, CfgSet, CrateRoots, ExpandOptions, ResolveError,
+        RustPath,
+    };
+    use std::path::Path;
+    use std::{convert::TryFrom, str::FromStr};
+    use syn::{parse_quote, Attribute, Expr, Item, ItemFn, ItemMod, ItemStruct};
+
+    fn test_resolver() -> Resolver {
+        let test_crate = format!("test_crate={}/test-crate", env!("CARGO_MANIFEST_DIR"));
+        Resolver::new(CrateRoots::try_from(vec![test_crate]).unwrap())
+    }
+
+    fn resolve(path: &str) -> Option<String> {
+        test_resolver()
+            .resolve(&RustPath::from_str(path).unwrap())
+            .unwrap()
+    }
+
+    fn resolve_error(path: &str) -> String {
+        let err = test_resolver()
+            .resolve(&RustPath::from_str(path).unwrap())
+            .unwrap_err();
+        err.root_cause().to_string()
+    }
+
+    /// The docstring from `attrs`, leaving out any files they include.
+    fn attrs_to_string(attrs: &[Attribute], cfg: &CfgSet) -> String {
+        docs_to_string(&doc_fragments(attrs, cfg))
+    }
+
+    #[test]
+    fn test_enum_variant_named_field() {
+        assert_eq!(
+            resolve("test_crate::crustaceans::LobsterColor::SplitColored::primary"),
+            Some("The color that is more prevalent on the lobster.".to_string())
+        );
+        assert_eq!(
+            resolve("test_crate::crustaceans::LobsterColor::SplitColored::secondary"),
+            Some("The color that is less prevalent on the lobster.".to_string())
+        );
+        assert_eq!(
+            resolve("test_crate::crustaceans::LobsterColor::SplitColored::tertiary"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_tuple_struct_fields() {
+        assert_eq!(
+            resolve("test_crate::crustaceans::CookedCrab::1"),
+            Some("A description of how it was cooked.".to_string())
+        );
+        assert_eq!(
+            resolve("test_crate::crustaceans::CookedCrab::_1"),
+            resolve("test_crate::crustaceans::CookedCrab::1")
+        );
+        assert_eq!(
+            resolve("test_crate::crustaceans::CookedCrab::_0"),
+            Some("The crab that was cooked.".to_string())
+        );
+    }
+
+    #[test]
+    fn test_enum_variant_tuple_field() {
+        assert_eq!(
+            resolve("test_crate::crustaceans::LobsterColor::Red::0"),
+            Some("A description of the intensity of the red".to_string())
+        );
+        assert_eq!(
+            resolve_error("test_crate::crustaceans::LobsterColor::Red::1"),
+            "The tuple enum variant Red has 1 field(s), index 1 is out of range, \
+             the only field is 0"
+        );
+        assert_eq!(
+            resolve_error("test_crate::crustaceans::CookedCrab::2"),
+            "The tuple struct CookedCrab has 2 field(s), index 2 is out of range, \
+             the fields are 0 to 1"
+        );
+        assert_eq!(
+            resolve_error("test_crate::crustaceans::CookedCrab::-1"),
+            "Tuple fields are numbered from 0, so `-1` is not a valid field of struct CookedCrab"
+        );
+        assert_eq!(
+            resolve_error("test_crate::crustaceans::CookedCrab::+1"),
+            "Invalid field name +1 for tuple struct CookedCrab, expected a number like `0` or `_0`"
+        );
+        assert_eq!(
+            resolve_error("test_crate::crustaceans::CookedCrab::_"),
+            "Invalid field name _ for tuple struct CookedCrab, expected a number like `0` or `_0`"
+        );
+    }
+
+    #[test]
+    fn test_path_past_struct_field() {
+        let path = RustPath::from_str("test_crate::crustaceans::Crab::num_legs::count").unwrap();
+        let err = test_resolver().resolve(&path).unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            "Fields of struct Crab have no subitems, so `num_legs::count` is not a valid path"
+        );
+    }
+
+    #[test]
+    fn test_path_past_enum_variant_field() {
+        let path =
+            RustPath::from_str("test_crate::crustaceans::LobsterColor::SplitColored::primary::0")
+                .unwrap();
+        let err = test_resolver().resolve(&path).unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            "Fields of enum variant SplitColored have no subitems, so `primary::0` is not a valid path"
+        );
+    }
+
+    #[test]
+    fn test_cfg_attr_docs() {
+        let item: ItemStruct = parse_quote! {
+            /// Everywhere.
+            #[cfg_attr(unix, doc = "On unix.")]
+            #[deprecated]
+            #[cfg_attr(windows, doc = "On windows.")]
+            #[cfg_attr(all(), cfg_attr(unix, doc = "Nested."))]
+            /// Also everywhere.
+            struct Shell;
+        };
+        let cfg = CfgSet::try_from(vec!["unix".to_string()]).unwrap();
+        assert_eq!(
+            attrs_to_string(&item.attrs, &cfg),
+            "Everywhere.\nOn unix.\nNested.\nAlso everywhere."
+        );
+    }
+
+    #[test]
+    fn test_cfg_gated_modules() {
+        let test_crate = format!("test_crate={}/test-crate", env!("CARGO_MANIFEST_DIR"));
+        let resolver_with = |cfg: &[&str]| {
+            let options = ResolveOptions {
+                cfg: CfgSet::try_from(cfg.iter().map(ToString::to_string).collect::<Vec<_>>())
+                    .unwrap(),
+                cfg_notes: true,
+                ..ResolveOptions::default()
+            };
+            Resolver::with_options(
+                CrateRoots::try_from(vec![test_crate.clone()]).unwrap(),
+                options,
+            )
+        };
+        let hermit_crab = RustPath::from_str("test_crate::shellfish::HermitCrab").unwrap();
+        let shellfish = RustPath::from_str("test_crate::shellfish").unwrap();
+
+        // With no cfg options configured, every module is entered.
+        let resolver = resolver_with(&[]);
+        assert_eq!(
+            resolver.resolve(&hermit_crab).unwrap().unwrap(),
+            "A crab that lives in a borrowed shell."
+        );
+        assert_eq!(
+            resolver.resolve(&shellfish).unwrap().unwrap(),
+            "Crabs that need the `shellfish` feature."
+        );
+
+        let resolver = resolver_with(&["docsrs", r#"feature = "shellfish""#]);
+        assert_eq!(
+            resolver.resolve(&hermit_crab).unwrap().unwrap(),
+            "A crab that lives in a borrowed shell."
+        );
+        assert_eq!(
+            resolver.resolve(&shellfish).unwrap().unwrap(),
+            "> Available on **crate feature `shellfish`** only.\n\n\
+             Crabs that need the `shellfish` feature."
+        );
+
+        let resolver = resolver_with(&["unix"]);
+        let err = resolver.resolve(&hermit_crab).unwrap_err();
+        assert_eq!(
+            err.root_cause().to_string(),
+            "test_crate::shellfish::HermitCrab is not available under the current cfg, since \
+             module shellfish needs `cfg(feature = \"shellfish\")`"
+        );
+        let err = resolver.resolve_module_items(&shellfish).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<ResolveError>(),
+            Some(ResolveError::UnavailableUnderCfg { module, .. }) if module == "shellfish"
+        ));
+        assert_eq!(
+            err.root_cause().to_string(),
+            "test_crate::shellfish is not available under the current cfg, since module \
+             shellfish needs `cfg(feature = \"shellfish\")`"
+        );
+    }
+
+    #[test]
+    fn test_cfg_selected_module_files() {
+        let crate_dir = TempDir::new("sys");
+        crate_dir.write(
+            "src/lib.rs",
+            "#[cfg(unix)]\n#[path = \"platform/unix.rs\"]\npub mod sys;\n\
+             #[cfg(windows)]\n#[path = \"platform/win.rs\"]\npub mod sys;\n\
+             #[cfg_attr(unix, path = \"platform/unix_io.rs\")]\n\
+             #[cfg_attr(windows, path = \"platform/win_io.rs\")]\npub mod io;",
+        );
+        crate_dir.write(
+            "src/platform/unix.rs",
+            "/// A file descriptor.\npub struct Handle;",
+        );
+        crate_dir.write(
+            "src/platform/win.rs",
+            "/// A Windows `HANDLE`.\npub struct Handle;",
+        );
+        crate_dir.write(
+            "src/platform/unix_io.rs",
+            "/// Unix pipes.\npub struct Pipe;",
+        );
+        crate_dir.write(
+            "src/platform/win_io.rs",
+            "/// Named pipes.\npub struct Pipe;",
+        );
+        let root = format!("platform={}", crate_dir.path().to_string_lossy());
+        let resolver_with = |cfg: &str| {
+            let options = ResolveOptions {
+                cfg: CfgSet::try_from(vec![cfg.to_string()]).unwrap(),
+                ..ResolveOptions::default()
+            };
+            Resolver::with_options(CrateRoots::try_from(vec![root.clone()]).unwrap(), options)
+        };
+        let path = |path: &str| RustPath::from_str(path).unwrap();
+
+        let unix = resolver_with("unix");
+        assert_eq!(
+            unix.resolve(&path("platform::sys::Handle")).unwrap(),
+            Some("A file descriptor.".to_string())
+        );
+        assert_eq!(
+            unix.resolve(&path("platform::io::Pipe")).unwrap(),
+            Some("Unix pipes.".to_string())
+        );
+        let windows = resolver_with("windows");
+        assert_eq!(
+            windows.resolve(&path("platform::sys::Handle")).unwrap(),
+            Some("A Windows `HANDLE`.".to_string())
+        );
+        assert_eq!(
+            windows.resolve(&path("platform::io::Pipe")).unwrap(),
+            Some("Named pipes.".to_string())
+        );
+        assert_eq!(
+            windows
+                .resolve_module_items(&path("platform::sys"))
+                .unwrap(),
+            Some("### Handle\n\nA Windows `HANDLE`.".to_string())
+        );
+        // The active `sys` was searched, so a missing item is just missing, even though the other
+        // `sys` is left out.
+        assert_eq!(unix.resolve(&path("platform::sys::Missing")).unwrap(), None);
+    }
+
+    #[test]
+    fn test_crlf_docs() {
+        let item: ItemStruct = parse_quote! {
+            #[doc = " First line.\r\n Second line."]
+            #[doc = "\r\n * A block comment.\r\n *\r\n * With two paragraphs.\r\n "]
+            struct Shell;
+        };
+        let mut docs = doc_fragments(&item.attrs, &CfgSet::new());
+        docs.push(included_fragment("# Included\r\n\r\nText.\r\n"));
+        assert_eq!(
+            docs_to_string(&docs),
+            "First line.\nSecond line.\nA block comment.\n\nWith two paragraphs.\n# Included\n\nText."
+        );
+    }
+
+    #[test]
+    fn test_inline_mod_inner_and_outer_docs() {
+        let the_mod: ItemMod =
+            syn::parse_str("/// Outer docs.\nmod shells {\n    //! Inner docs.\n}").unwrap();
+        let found = test_resolver()
+            .find_attrs_in_mod(Path::new("lib.rs"), &the_mod, &None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            attrs_to_string(&found.attrs, &CfgSet::new()),
+            "Outer docs.\nInner docs."
+        );
+    }
+
+    #[test]
+    fn test_nested_inline_modules() {
+        assert_eq!(
+            resolve("test_crate::habitats"),
+            Some("Places crabs live.".to_string())
+        );
+        assert_eq!(
+            resolve("test_crate::habitats::shore"),
+            Some("Where the sea meets the land.\nCrabs spend most of their time here.".to_string())
+        );
+        assert_eq!(
+            resolve("test_crate::habitats::shore::TidePool"),
+            Some("A pool left behind by the tide.".to_string())
+        );
+        assert_eq!(
+            test_resolver()
+                .resolve_module_items(&RustPath::from_str("test_crate::habitats::shore").unwrap())
+                .unwrap(),
+            Some("### TidePool\n\nA pool left behind by the tide.".to_string())
+        );
+    }
+
+    #[test]
+    fn test_missing_crate_suggests_items() {
+        let path = RustPath::from_str("CookedCrab::0").unwrap();
+        let err = test_resolver().resolve(&path).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Crate CookedCrab not found. Paths must start with a crate name, \
+             did you mean `test_crate::crustaceans::CookedCrab`?"
+        );
+
+        let path = RustPath::from_str("Shrimp").unwrap();
+        let err = test_resolver().resolve(&path).unwrap_err();
+        assert_eq!(err.to_string(), "Crate Shrimp not found");
+    }
+
+    #[test]
+    fn test_module_items() {
+        let path = RustPath::from_str("test_crate::crustaceans").unwrap();
+        assert_eq!(
+            test_resolver()
+                .resolve_module_items(&path)
+                .unwrap()
+                .unwrap(),
+            "### Crab\n\nA crab.\n\n\
+             ### CookedCrab\n\nSome people eat crabs\n\n\
+             ### LobsterColor\n\nLobster colors, according to Wikipedia.\n\n\
+             ### BOIL_SECONDS\n\nHow long to boil a lobster for.\n\n\
+             ### cook\n\nCook a crab."
+        );
+    }
+
+    #[test]
+    fn test_module_items_skips_private_and_hidden() {
+        let path = RustPath::from_str("test_crate").unwrap();
+        assert_eq!(
+            test_resolver()
+                .resolve_module_items(&path)
+                .unwrap()
+                .unwrap(),
+            "### crustaceans\n\nAll sorts of crustaceans.\n\n\
+             ### boil_crab\n\nBoil a crab the old way."
+        );
+    }
+
+    #[test]
+    fn test_const_values() {
+        let resolver = test_resolver();
+        let value = |path: &str| {
+            resolver
+                .resolve_const_value(&RustPath::from_str(path).unwrap())
+                .unwrap()
+        };
+        assert_eq!(
+            value("test_crate::crustaceans::Crab::MAX_LEGS"),
+            Some("MAX_LEGS = 10".to_string())
+        );
+        assert_eq!(
+            value("test_crate::crustaceans::BOIL_SECONDS"),
+            Some("BOIL_SECONDS = 12 * 60".to_string())
+        );
+        assert_eq!(
+            resolve("test_crate::crustaceans::BOIL_SECONDS"),
+            Some("How long to boil a lobster for.".to_string())
+        );
+
+        let path = RustPath::from_str("test_crate::crustaceans::Crab").unwrap();
+        let err = resolver.resolve_const_value(&path).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "test_crate::crustaceans::Crab is not a const or static, so it has no value to show"
+        );
+    }
+
+    #[test]
+    fn test_static_values() {
+        assert_eq!(
+            resolve("test_crate::crab_behavior::CRAB_COUNT"),
+            Some("How many crabs there are in this crate.".to_string())
+        );
+        let path = RustPath::from_str("test_crate::crab_behavior::CRAB_COUNT").unwrap();
+        assert_eq!(
+            test_resolver().resolve_const_value(&path).unwrap(),
+            Some("CRAB_COUNT = 1".to_string())
+        );
+    }
+
+    #[test]
+    fn test_typed_const_values() {
+        let test_crate = format!("test_crate={}/test-crate", env!("CARGO_MANIFEST_DIR"));
+        let options = ResolveOptions {
+            signature_fallback: true,
+            ..ResolveOptions::default()
+        };
+        let resolver =
+            Resolver::with_options(CrateRoots::try_from(vec![test_crate]).unwrap(), options);
+        let value = |path: &str| {
+            resolver
+                .resolve_const_value(&RustPath::from_str(path).unwrap())
+                .unwrap()
+        };
+        assert_eq!(
+            value("test_crate::crustaceans::Crab::MAX_LEGS"),
+            Some("MAX_LEGS: u8 = 10".to_string())
+        );
+        assert_eq!(
+            value("test_crate::crab_behavior::CRAB_COUNT"),
+            Some("CRAB_COUNT: usize = 1".to_string())
+        );
+    }
+
+    #[test]
+    fn test_expr_to_string() {
+        let expr: Expr = parse_quote!(Duration::from_secs(12 * (60 + 1)));
+        assert_eq!(expr_to_string(&expr), "Duration::from_secs(12 * (60 + 1))");
+        let expr: Expr = parse_quote!(vec![1, 2, 3].len());
+        assert_eq!(expr_to_string(&expr), "vec![1, 2, 3].len()");
+        let expr: Expr = parse_quote!("a \"string\"");
+        assert_eq!(expr_to_string(&expr), r#""a \"string\"""#);
+    }
+
+    #[test]
+    fn test_block_doc_comment() {
+        let item: ItemStruct = syn::parse_str(
+            "/**\n     * A shell.\n     *\n     * - Hard\n     *   - Very hard\n     */\n\
+             struct Shell;",
+        )
+        .unwrap();
+        assert_eq!(
+            attrs_to_string(&item.attrs, &CfgSet::new()),
+            "A shell.\n\n- Hard\n  - Very hard"
+        );
+
+        let item: ItemStruct = syn::parse_str(
+            "/**\n        A claw.\n\n            let pinch = true;\n    */\nstruct Claw;",
+        )
+        .unwrap();
+        assert_eq!(
+            attrs_to_string(&item.attrs, &CfgSet::new()),
+            "A claw.\n\n    let pinch = true;"
+        );
+    }
+
+    #[test]
+    fn test_adjacent_doc_attributes() {
+        let item: ItemStruct = syn::parse_str(
+            r##"
+            #[doc = "A generated crab."]
+            #[doc = ""]
+            #[doc = "It says \"hello\" and\nwaves."]
+            #[doc = r"Raw text keeps \n as written."]
+            #[doc = " Mixed with"]
+            /// a comment.
+            #[doc = concat!("It has ", 8, " legs", '.')]
+            struct Crab;
+            "##,
+        )
+        .unwrap();
+        assert_eq!(
+            attrs_to_string(&item.attrs, &CfgSet::new()),
+            "A generated crab.\n\n\
+             It says \"hello\" and\nwaves.\n\
+             Raw text keeps \\n as written.\n\
+             Mixed with\n\
+             a comment.\n\
+             It has 8 legs."
+        );
+
+        // Macros other than `concat!`, and arguments that aren't literals, can't be read.
+        let item: ItemStruct = syn::parse_str(
+            "#[doc = concat!(\"A \", stringify!(Crab))]\n#[doc = env!(\"CRAB\")]\nstruct Crab;",
+        )
+        .unwrap();
+        assert_eq!(attrs_to_string(&item.attrs, &CfgSet::new()), "");
+    }
+
+    #[test]
+    fn test_line_doc_comment_keeps_nested_indentation() {
+        let item: ItemStruct =
+            syn::parse_str("/// A leg:\n///\n/// - Jointed\n///   - Many times\nstruct Leg;")
+                .unwrap();
+        assert_eq!(
+            attrs_to_string(&item.attrs, &CfgSet::new()),
+            "A leg:\n\n- Jointed\n  - Many times"
+        );
+    }
+
+    #[test]
+    fn test_unindent_keeps_markdown_structure() {
+        let docs = |source: &str| {
+            let item: ItemStruct = syn::parse_str(source).unwrap();
+            attrs_to_string(&item.attrs, &CfgSet::new())
+        };
+        // Doc comments lose the space after `///`, and everything else keeps its shape.
+        assert_eq!(
+            docs(
+                "/// A crab:\n///\n/// - Claws\n///   - Left\n///\n///     let pinch = true;\n\
+                  /// ```text\n///   $ pinch\n/// ```\nstruct Crab;"
+            ),
+            "A crab:\n\n- Claws\n  - Left\n\n    let pinch = true;\n```text\n  $ pinch\n```"
+        );
+        // Without that space, the nested item and the indented code block stay as they are.
+        assert_eq!(
+            docs(
+                r#"
+                #[doc = "A crab:"]
+                #[doc = ""]
+                #[doc = "- Claws"]
+                #[doc = "    - Left"]
+                #[doc = ""]
+                #[doc = "    let pinch = true;"]
+                struct Crab;
+            "#
+            ),
+            "A crab:\n\n- Claws\n    - Left\n\n    let pinch = true;"
+        );
+        assert_eq!(
+            docs(
+                r#"
+                #[doc = "> A quote"]
+                /// >     with code
+                struct Crab;
+            "#
+            ),
+            "> A quote\n>     with code"
+        );
+        // Unindented lines in a fenced block don't stop the prose around it from being dedented.
+        assert_eq!(
+            docs(
+                r#"
+                #[doc = "    Pinch:"]
+                #[doc = "    ```console"]
+                #[doc = "$ pinch"]
+                #[doc = "      --hard"]
+                #[doc = "    ```"]
+                struct Crab;
+            "#
+            ),
+            "Pinch:\n```console\n$ pinch\n  --hard\n```"
+        );
+    }
+
+    #[test]
+    fn test_doc_aliases() {
+        assert_eq!(
+            resolve("test_crate::crustaceans::boil"),
+            Some("Cook a crab.".to_string())
+        );
+        assert_eq!(
+            resolve("test_crate::crustaceans::cook"),
+            Some("Cook a crab.".to_string())
+        );
+        assert_eq!(
+            resolve("test_crate::crustaceans::steam"),
+            Some("Cook a crab.".to_string())
+        );
+        assert_eq!(
+            resolve("test_crate::crustaceans::LobsterColor::Ghost"),
+            Some("Also called white; translucent; ghost; crystal.".to_string())
+        );
+        // Aliases only apply to the last segment of a path.
+        assert_eq!(
+            resolve("test_crate::crustaceans::Shellfish::num_legs"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_crate_alias() {
+        // The package is called `test-crate`, but paths use the alias from the config.
+        let crabs = format!("crabs={}/test-crate", env!("CARGO_MANIFEST_DIR"));
+        let resolver = Resolver::new(CrateRoots::try_from(vec![crabs]).unwrap());
+        let resolve = |path: &str| resolver.resolve(&RustPath::from_str(path).unwrap());
+        assert_eq!(
+            resolve("crabs::crustaceans::Crab").unwrap(),
+            Some("A crab.".to_string())
+        );
+        assert!(resolve("test_crate::crustaceans::Crab").is_err());
+    }
+
+    #[test]
+    fn test_custom_lib_target() {
+        let custom_lib = format!("{}/test-crate-custom-lib", env!("CARGO_MANIFEST_DIR"));
+        let resolver = Resolver::new(CrateRoots::try_from(vec![custom_lib]).unwrap());
+        let resolve = |path: &str| {
+            resolver
+                .resolve(&RustPath::from_str(path).unwrap())
+                .unwrap()
+        };
+        assert_eq!(
+            resolve("custom_lib"),
+            Some("A crate whose library target isn't at `src/lib.rs`.".to_string())
+        );
+        assert_eq!(
+            resolve("custom_lib::claws::Claw"),
+            Some("A claw, for pinching.".to_string())
+        );
+    }
+
+    #[test]
+    fn test_resolve_all() {
+        let paths = [
+            "test_crate::crustaceans::Crab",
+            "CookedCrab",
+            "test_crate::crustaceans::Shrimp",
+            "test_crate::crustaceans::Crab::num_legs::count",
+            "test_crate::crustaceans::CookedCrab",
+        ]
+        .iter()
+        .map(|path| RustPath::from_str(path).unwrap())
+        .collect::<Vec<_>>();
+        let results = test_resolver().resolve_all(&paths);
+
+        let returned_paths: Vec<_> = results.iter().map(|(path, _)| path.clone()).collect();
+        assert_eq!(returned_paths, paths);
+        let summary: Vec<_> = results
+            .into_iter()
+            .map(|(_, result)| match result {
+                Ok(Some(doc)) => doc,
+                Ok(None) => "not found".to_string(),
+                Err(_) => "error".to_string(),
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                "A crab.",
+                "error",
+                "not found",
+                "error",
+                "Some people eat crabs"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_impl_in_other_module() {
+        assert_eq!(
+            resolve("test_crate::crustaceans::Crab::scuttle"),
+            Some("Walk sideways.".to_string())
+        );
+        assert_eq!(resolve("test_crate::crustaceans::Crab::swim"), None);
+        // The type must exist for its impls to be searched.
+        assert_eq!(resolve("test_crate::Prawn::scuttle"), None);
+    }
+
+    #[test]
+    fn test_type_aliases() {
+        assert_eq!(
+            resolve("test_crate::crab_behavior::OldCrab"),
+            Some("A crab that has been around for a while.".to_string())
+        );
+        // Methods are found through one alias, including trait methods and their defaults.
+        assert_eq!(
+            resolve("test_crate::crab_behavior::OldCrab::scuttle"),
+            Some("Walk sideways.".to_string())
+        );
+        assert_eq!(
+            resolve("test_crate::crab_behavior::OldCrab::greet"),
+            Some("Say hello.".to_string())
+        );
+        // Types from other crates have no impls in this one.
+        assert_eq!(resolve("test_crate::crab_behavior::Shell::push"), None);
+    }
+
+    #[test]
+    fn test_fn_pointer_aliases() {
+        assert_eq!(
+            resolve("test_crate::crab_behavior::CrabHandler"),
+            Some(
+                "Called with each crab that is found, returning whether to keep looking."
+                    .to_string()
+            )
+        );
+        // Function pointers have no impls to search, so their methods are simply missing.
+        assert_eq!(
+            resolve("test_crate::crab_behavior::CrabHandler::call"),
+            None
+        );
+
+        let test_crate = format!("test_crate={}/test-crate", env!("CARGO_MANIFEST_DIR"));
+        let options = ResolveOptions {
+            signature_fallback: true,
+            ..ResolveOptions::default()
+        };
+        let resolver =
+            Resolver::with_options(CrateRoots::try_from(vec![test_crate]).unwrap(), options);
+        assert_eq!(
+            resolver
+                .resolve(&RustPath::from_str("test_crate::crab_behavior::MoltCallback").unwrap())
+                .unwrap(),
+            Some(
+                "```rust\npub type MoltCallback = for<'a> fn(&'a mut Crab, u32) -> Option<&'a Crab>\n```"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_impl_in_nested_inline_module() {
+        assert_eq!(
+            resolve("test_crate::Scuttle::left"),
+            Some("Scuttle to the left.".to_string())
+        );
+    }
+
+    #[test]
+    fn test_paths_past_impl_items() {
+        // Going further than a method is an error, wherever its impl is.
+        assert_eq!(
+            resolve_error("test_crate::crustaceans::Crab::name::extra"),
+            "name is an associated method with no subitems, so `name::extra` is not a valid path"
+        );
+        assert_eq!(
+            resolve_error("test_crate::crab_behavior::OldCrab::scuttle::extra::more"),
+            "scuttle is an associated method with no subitems, so `scuttle::extra` is not a \
+             valid path"
+        );
+        assert_eq!(
+            resolve("test_crate::crustaceans::Crab::missing::extra"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_impls_of_wrapped_types() {
+        let the_mod: ItemMod = syn::parse_str(
+            "mod wrapped {
+                pub struct Crab;
+                pub trait Speak {}
+
+                impl [Crab] {
+                    /// The crab with the most legs.
+                    pub fn leggiest(&self) {}
+                }
+
+                impl (dyn Speak + Send) {
+                    /// Speak twice.
+                    pub fn repeat(&self) {}
+                }
+            }",
+        )
+        .unwrap();
+        let resolver = test_resolver();
+        let resolve = |path: &str| {
+            resolver
+                .find_attrs_in_mod(
+                    Path::new("lib.rs"),
+                    &the_mod,
+                    &Some(RustPath::from_str(path).unwrap()),
+                )
+                .unwrap()
+                .map(|found| attrs_to_string(&found.attrs, &CfgSet::new()))
+        };
+
+        assert_eq!(
+            resolve("Crab::leggiest"),
+            Some("The crab with the most legs.".to_string())
+        );
+        assert_eq!(resolve("Speak::repeat"), Some("Speak twice.".to_string()));
+        assert_eq!(resolve("Crab::repeat"), None);
+    }
+
+    #[test]
+    fn test_type_names() {
+        let name = |source: &str| type_name(&syn::parse_str(source).unwrap());
+        assert_eq!(name("&'a mut [Crab]"), Some("Crab".to_string()));
+        assert_eq!(name("[crate::Crab; 8]"), Some("Crab".to_string()));
+        assert_eq!(name("(Crab)"), Some("Crab".to_string()));
+        assert_eq!(
+            name("dyn 'static + Speak + Send"),
+            Some("Speak".to_string())
+        );
+        assert_eq!(name("(u8, Crab)"), None);
+    }
+
+    #[test]
+    fn test_generic_impls() {
+        let the_mod: ItemMod = syn::parse_str(
+            "mod generic {
+                /// Not the parser's docs.
+                impl<'a> Parser<'a> {
+                    /// Parse the next token.
+                    pub fn next_token(&mut self) {}
+                }
+
+                /// A parser.
+                pub struct Parser<'a> {
+                    input: &'a str,
+                }
+
+                pub struct Matrix<const N: usize>;
+
+                impl<const N: usize> Matrix<N> {
+                    /// The number of rows.
+                    pub const ROWS: usize = N;
+                }
+
+                impl Matrix<2> {
+                    /// The determinant of a 2x2 matrix.
+                    pub fn determinant(&self) {}
+                }
+            }",
+        )
+        .unwrap();
+        let resolver = test_resolver();
+        let resolve = |path: &str| {
+            resolver
+                .find_attrs_in_mod(
+                    Path::new("lib.rs"),
+                    &the_mod,
+                    &Some(RustPath::from_str(path).unwrap()),
+                )
+                .unwrap()
+                .map(|found| attrs_to_string(&found.attrs, &CfgSet::new()))
+        };
+
+        assert_eq!(resolve("Parser"), Some("A parser.".to_string()));
+        assert_eq!(
+            resolve("Parser::next_token"),
+            Some("Parse the next token.".to_string())
+        );
+        assert_eq!(
+            resolve("Matrix::ROWS"),
+            Some("The number of rows.".to_string())
+        );
+        assert_eq!(
+            resolve("Matrix::determinant"),
+            Some("The determinant of a 2x2 matrix.".to_string())
+        );
+    }
+
+    #[test]
+    fn test_trait_impl_paths() {
+        assert_eq!(
+            resolve("test_crate::crab_behavior::Burrow#Speak"),
+            Some("Burrows echo whatever is said into them.".to_string())
+        );
+        assert_eq!(
+            resolve("test_crate::crab_behavior::Burrow#std::fmt::Display"),
+            Some(String::new())
+        );
+        assert_eq!(resolve("test_crate::crab_behavior::Burrow#Eat"), None);
+        assert_eq!(
+            resolve_error("test_crate::crab_behavior::Burrow#Debug"),
+            "Debug is derived for test_crate::crab_behavior::Burrow, so there is no impl block \
+             to read docs from"
+        );
+
+        let resolver = test_resolver();
+        let path = RustPath::from_str("test_crate::crab_behavior::Burrow#Speak").unwrap();
+        let info = resolver.resolve_info(&path).unwrap().unwrap();
+        assert_eq!(info.kind, "impl");
+        assert!(info.file.ends_with("crab_behavior.rs"));
+        assert!(resolver
+            .resolve_source(&path, None)
+            .unwrap()
+            .unwrap()
+            .starts_with("/// Burrows echo whatever is said into them.\nimpl Speak for Burrow {"));
+    }
+
+    #[test]
+    fn test_impls() {
+        let resolver = test_resolver();
+        let impls = |path: &str| {
+            resolver
+                .resolve_impls(&RustPath::from_str(path).unwrap())
+                .unwrap()
+                .unwrap()
+        };
+        assert_eq!(
+            impls("test_crate::crab_behavior::Burrow"),
+            "### Implementations\n\n\
+             - `impl Burrow`\n\n\
+             ### Trait Implementations\n\n\
+             - [`Debug`](https://doc.rust-lang.org/std/?search=Debug)\n\
+             - [`Default`](https://doc.rust-lang.org/std/?search=Default)\n\
+             - [`Display`](https://doc.rust-lang.org/std/?search=fmt::Display)\n\
+             - `Speak`: Things that can talk."
+        );
+        // Each inherent impl is only listed once, and ones with only private or hidden items
+        // aren't listed at all.
+        assert_eq!(
+            impls("test_crate::crustaceans::Crab"),
+            "### Implementations\n\n- `impl Crab`\n\n\
+             ### Trait Implementations\n\n- `Speak`: Things that can talk."
+        );
+        assert_eq!(impls("test_crate::crustaceans::CookedCrab"), "");
+
+        let err = resolver
+            .resolve_impls(&RustPath::from_str("test_crate::crab_behavior::Speak").unwrap())
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "test_crate::crab_behavior::Speak is a trait, not a type, so it has no impls to list"
+        );
+    }
+
+    #[test]
+    fn test_associated_types() {
+        let the_mod: ItemMod = syn::parse_str(
+            "mod containers {
+                pub trait Container {
+                    /// The things in the container.
+                    type Item: Clone + Default;
+                    /// Borrows each thing in turn.
+                    type Iter<'a>: Iterator<Item = &'a Self::Item>
+                    where
+                        Self: 'a;
+                }
+
+                pub struct Pot;
+
+                impl Container for Pot {
+                    /// Pots hold crabs.
+                    type Item = Crab;
+                    /// Crabs leave a pot one at a time.
+                    type Iter<'a> where Self: 'a = std::slice::Iter<'a, Crab>;
+                }
+            }",
+        )
+        .unwrap();
+        let resolver = test_resolver();
+        let find = |path: &str| {
+            resolver.find_attrs_in_mod(
+                Path::new("lib.rs"),
+                &the_mod,
+                &Some(RustPath::from_str(path).unwrap()),
+            )
+        };
+        let resolve = |path: &str| {
+            find(path).unwrap().map(|found| {
+                let docs = attrs_to_string(&found.attrs, &CfgSet::new());
+                (docs, found.signature.unwrap_or_default())
+            })
+        };
+
+        assert_eq!(
+            resolve("Container::Item"),
+            Some((
+                "The things in the container.".to_string(),
+                "type Item: Clone + Default".to_string()
+            ))
+        );
+        assert_eq!(
+            resolve("Container::Iter"),
+            Some((
+                "Borrows each thing in turn.".to_string(),
+                "type Iter<'a>: Iterator<Item = &'a Self::Item> where Self: 'a".to_string()
+            ))
+        );
+        assert_eq!(
+            resolve("Pot::Item"),
+            Some(("Pots hold crabs.".to_string(), "type Item".to_string()))
+        );
+        assert_eq!(
+            resolve("Pot::Iter"),
+            Some((
+                "Crabs leave a pot one at a time.".to_string(),
+                "type Iter<'a> where Self: 'a".to_string()
+            ))
+        );
+
+        // Associated items have nothing inside of them.
+        assert_eq!(
+            find("Pot::Item::count")
+                .unwrap_err()
+                .root_cause()
+                .to_string(),
+            "Item is an associated type with no subitems, so `Item::count` is not a valid path"
+        );
+        assert_eq!(
+            find("Container::Iter::next")
+                .unwrap_err()
+                .root_cause()
+                .to_string(),
+            "Iter is an associated type with no subitems, so `Iter::next` is not a valid path"
+        );
+        assert!(find("Pot::Missing::count").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_methods() {
+        let resolver = test_resolver();
+        let crab = RustPath::from_str("test_crate::crustaceans::Crab").unwrap();
+        let method_names = |order| {
+            let docs = resolver.resolve_methods(&crab, order).unwrap().unwrap();
+            docs.lines()
+                .filter_map(|line| line.strip_prefix("### "))
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+        };
+
+        // Hidden and private methods are skipped, and trait defaults are only included if they
+        // aren't overridden.
+        assert_eq!(
+            method_names(MethodOrder::Source),
+            vec!["scuttle", "molt", "wave", "name", "sign_off", "greet", "pinch"]
+        );
+        assert_eq!(
+            method_names(MethodOrder::Alphabetical),
+            vec!["greet", "molt", "name", "pinch", "scuttle", "sign_off", "wave"]
+        );
+
+        let docs = resolver
+            .resolve_methods(&crab, MethodOrder::Source)
+            .unwrap()
+            .unwrap();
+        assert!(docs
+            .starts_with("### scuttle\n\n```rust\npub fn scuttle(&self)\n```\n\nWalk sideways."));
+        assert!(docs.contains(
+            "### name\n\n```rust\nfn name(&self) -> String\n```\n\n### sign_off\n\n```rust\n\
+             fn sign_off(&self) -> String\n```\n\nCrabs wave a claw goodbye."
+        ));
+        assert!(docs.contains("### greet\n\n```rust\nfn greet(&self) -> String\n```\n\nSay hello."));
+
+        // Aliases have the methods of the type they name.
+        let old_crab = RustPath::from_str("test_crate::crab_behavior::OldCrab").unwrap();
+        assert_eq!(
+            resolver
+                .resolve_methods(&old_crab, MethodOrder::Source)
+                .unwrap(),
+            Some(docs)
+        );
+
+        let speak = RustPath::from_str("test_crate::crab_behavior::Speak").unwrap();
+        let err = resolver
+            .resolve_methods(&speak, MethodOrder::Source)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "test_crate::crab_behavior::Speak is a trait, not a type, so it has no methods to list"
+        );
+    }
+
+    #[test]
+    fn test_prelude_fallback() {
+        assert_eq!(resolve("test_crate::CrabNet"), None);
+
+        let test_crate = format!("test_crate={}/test-crate", env!("CARGO_MANIFEST_DIR"));
+        let mut options = ResolveOptions::default();
+        options.preludes.insert(
+            "test_crate".to_string(),
+            RustPath::from_str("prelude").unwrap(),
+        );
+        let resolver =
+            Resolver::with_options(CrateRoots::try_from(vec![test_crate]).unwrap(), options);
+        let resolve = |path: &str| {
+            resolver
+                .resolve(&RustPath::from_str(path).unwrap())
+                .unwrap()
+        };
+        assert_eq!(
+            resolve("test_crate::CrabNet"),
+            Some("A net for catching crabs.".to_string())
+        );
+        // Items found as written don't look in the prelude.
+        assert_eq!(
+            resolve("test_crate::crustaceans::Crab"),
+            Some("A crab.".to_string())
+        );
+        assert_eq!(resolve("test_crate::Lobster"), None);
+    }
+
+    #[test]
+    fn test_reexports() {
+        assert_eq!(resolve("test_crate::Crab"), Some("A crab.".to_string()));
+        assert_eq!(
+            resolve("test_crate::Crab::scuttle"),
+            Some("Walk sideways.".to_string())
+        );
+        // A re-exported module makes everything inside it available under its new name.
+        assert_eq!(
+            resolve("test_crate::coast::TidePool"),
+            Some("A pool left behind by the tide.".to_string())
+        );
+        // `RockPool` is a renamed re-export of a glob re-export.
+        assert_eq!(
+            resolve("test_crate::RockPool"),
+            Some("A pool left behind by the tide.".to_string())
+        );
+        assert_eq!(resolve("test_crate::coast::Crab"), None);
+
+        let test_crate = format!("test_crate={}/test-crate", env!("CARGO_MANIFEST_DIR"));
+        let options = ResolveOptions {
+            reexport_depth: 1,
+            ..ResolveOptions::default()
+        };
+        let resolver =
+            Resolver::with_options(CrateRoots::try_from(vec![test_crate]).unwrap(), options);
+        let resolve = |path: &str| {
+            resolver
+                .resolve(&RustPath::from_str(path).unwrap())
+                .unwrap()
+        };
+        assert_eq!(resolve("test_crate::Crab"), Some("A crab.".to_string()));
+        assert_eq!(resolve("test_crate::RockPool"), None);
+    }
+
+    #[test]
+    fn test_same_named_items_in_other_modules() {
+        let crate_dir = TempDir::new("same-names");
+        crate_dir.write(
+            "src/lib.rs",
+            "pub mod a {\n\
+                 /// An error from `a`.\n\
+                 pub struct Error;\n\
+                 impl Error {\n\
+                     /// Only on a::Error.\n\
+                     pub fn only_a(&self) {}\n\
+                 }\n\
+                 /// Things that speak like `a`.\n\
+                 pub trait Speak {\n\
+                     /// Speak like `a`.\n\
+                     fn greet(&self) {}\n\
+                 }\n\
+                 pub trait Move: Speak {}\n\
+             }\n\
+             pub mod b {\n\
+                 use crate::a;\n\
+                 /// An error from `b`.\n\
+                 pub struct Error;\n\
+                 /// Things that speak like `b`.\n\
+                 pub trait Speak {\n\
+                     /// Speak like `b`.\n\
+                     fn greet(&self) {}\n\
+                 }\n\
+                 pub trait Move: Speak {}\n\
+                 impl Speak for Error {}\n\
+                 impl a::Speak for a::Error {}\n\
+             }",
+        );
+        let root = format!("probe={}", crate_dir.path().to_string_lossy());
+        let resolver = Resolver::new(CrateRoots::try_from(vec![root]).unwrap());
+        let path = |path: &str| RustPath::from_str(path).unwrap();
+        let resolve = |p: &str| resolver.resolve(&path(p)).unwrap();
+
+        // Impls are matched on the whole path of their type, not just its name.
+        assert_eq!(
+            resolve("probe::a::Error::only_a"),
+            Some("Only on a::Error.".to_string())
+        );
+        assert_eq!(resolve("probe::b::Error::only_a"), None);
+        // Default methods come from the trait that the impl names, read from its module.
+        assert_eq!(
+            resolve("probe::a::Error::greet"),
+            Some("Speak like `a`.".to_string())
+        );
+        assert_eq!(
+            resolve("probe::b::Error::greet"),
+            Some("Speak like `b`.".to_string())
+        );
+        assert_eq!(
+            resolve("probe::a::Move::greet"),
+            Some("Speak like `a`.".to_string())
+        );
+        assert_eq!(
+            resolve("probe::b::Move::greet"),
+            Some("Speak like `b`.".to_string())
+        );
+
+        let methods = |p: &str| {
+            resolver
+                .resolve_methods(&path(p), MethodOrder::Source)
+                .unwrap()
+                .unwrap()
+        };
+        assert_eq!(
+            methods("probe::a::Error"),
+            "### only_a\n\n```rust\npub fn only_a(&self)\n```\n\nOnly on a::Error.\n\n\
+             ### greet\n\n```rust\nfn greet(&self)\n```\n\nSpeak like `a`."
+        );
+        assert_eq!(
+            methods("probe::b::Error"),
+            "### greet\n\n```rust\nfn greet(&self)\n```\n\nSpeak like `b`."
+        );
+
+        let impls = |p: &str| resolver.resolve_impls(&path(p)).unwrap().unwrap();
+        assert_eq!(
+            impls("probe::a::Error"),
+            "### Implementations\n\n- `impl Error`\n\n\
+             ### Trait Implementations\n\n- `Speak`: Things that speak like `a`."
+        );
+        assert_eq!(
+            impls("probe::b::Error"),
+            "### Trait Implementations\n\n- `Speak`: Things that speak like `b`."
+        );
+    }
+
+    #[test]
+    fn test_glob_reexports() {
+        let crate_dir = TempDir::new("globs");
+        crate_dir.write(
+            "src/lib.rs",
+            "pub use internal::*;\n\
+             mod internal {\n\
+                 /// Only exposed through a glob.\n\
+                 pub struct Foo;\n\
+                 /// Private, so the glob leaves it out.\n\
+                 struct Hidden;\n\
+                 pub use deeper::*;\n\
+                 pub use super::*;\n\
+                 mod deeper {\n\
+                     /// Two globs deep.\n\
+                     pub struct Bar;\n\
+                 }\n\
+             }",
+        );
+        let root = format!("globs={}", crate_dir.path().to_string_lossy());
+        let resolver = Resolver::new(CrateRoots::try_from(vec![root]).unwrap());
+        let resolve = |path: &str| {
+            resolver
+                .resolve(&RustPath::from_str(path).unwrap())
+                .unwrap()
+        };
+
+        assert_eq!(
+            resolve("globs::Foo"),
+            Some("Only exposed through a glob.".to_string())
+        );
+        assert_eq!(resolve("globs::Bar"), Some("Two globs deep.".to_string()));
+        assert_eq!(resolve("globs::Hidden"), None);
+        // The globs of `internal` and the crate root re-export each other, which ends.
+        assert_eq!(resolve("globs::Missing"), None);
+        assert_eq!(
+            resolve("globs::internal::Foo"),
+            Some("Only exposed through a glob.".to_string())
+        );
+    }
+
+    #[test]
+    fn test_fn_body_items() {
+        assert_eq!(
+            resolve("test_crate::crab_behavior::dig_burrow::Tunnel"),
+            None
+        );
+
+        let test_crate = format!("test_crate={}/test-crate", env!("CARGO_MANIFEST_DIR"));
+        let options = ResolveOptions {
+            fn_body_items: true,
+            ..ResolveOptions::default()
+        };
+        let resolver =
+            Resolver::with_options(CrateRoots::try_from(vec![test_crate]).unwrap(), options);
+        let resolve = |path: &str| {
+            resolver
+                .resolve(&RustPath::from_str(path).unwrap())
+                .unwrap()
+        };
+        assert_eq!(
+            resolve("test_crate::crab_behavior::dig_burrow::Tunnel"),
+            Some("A tunnel leading into the burrow.".to_string())
+        );
+        assert_eq!(
+            resolve("test_crate::crab_behavior::dig_burrow::Tunnel::length"),
+            Some("How long the tunnel is.".to_string())
+        );
+        assert_eq!(
+            resolve("test_crate::crab_behavior::Burrow::widen::MAX_WIDTH"),
+            Some("The widest a burrow can get.".to_string())
+        );
+        // Like anywhere else, private items are found unless the visibility says otherwise.
+        assert_eq!(
+            resolve("test_crate::crab_behavior::dig_burrow::Entrance"),
+            Some("The way into the burrow.".to_string())
+        );
+    }
+
+    #[test]
+    fn test_max_scan_files() {
+        let resolver_with_limit = |max_scan_files| {
+            let test_crate = format!("test_crate={}/test-crate", env!("CARGO_MANIFEST_DIR"));
+            let options = ResolveOptions {
+                max_scan_files,
+                ..ResolveOptions::default()
+            };
+            Resolver::with_options(CrateRoots::try_from(vec![test_crate]).unwrap(), options)
+        };
+        let path = RustPath::from_str("test_crate::crustaceans::Crab").unwrap();
+
+        // Finding the crab needs both lib.rs and crustaceans.rs.
+        let err = resolver_with_limit(1).resolve(&path).unwrap_err();
+        match err.downcast_ref::<ResolveError>() {
+            Some(ResolveError::ScanLimitReached { limit, path }) => {
+                assert_eq!(*limit, 1);
+                assert!(path.ends_with("crustaceans.rs"), "{:?}", path);
+            }
+            other => panic!("Unexpected error {:?}", other),
+        }
+        assert!(format!("{:#}", err).contains("raise `max_scan_files`"));
+
+        let resolver = resolver_with_limit(2);
+        assert_eq!(
+            resolver.resolve(&path).unwrap(),
+            Some("A crab.".to_string())
+        );
+        // The limit is per lookup, and files that are already parsed don't count again.
+        assert!(resolver.resolve(&path).unwrap().is_some());
+        assert!(resolver
+            .resolve(&RustPath::from_str("test_crate::crustaceans::CookedCrab").unwrap())
+            .unwrap()
+            .is_some());
+
+        // Searching every module for an impl stops at the limit instead of skipping the rest.
+        let err = resolver_with_limit(2)
+            .resolve(&RustPath::from_str("test_crate::crustaceans::Crab::scuttle").unwrap())
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<ResolveError>(),
+            Some(ResolveError::ScanLimitReached { .. })
+        ));
+    }
+
+    #[test]
+    fn test_use_target() {
+        let path = |path: &str| -> Vec<String> {
+            path.split("::")
+                .filter(|s| !s.is_empty())
+                .map(String::from)
+                .collect()
+        };
+        let module = path("a::b");
+        assert_eq!(use_target(&module, &path("c::D")), Some(path("a::b::c::D")));
+        assert_eq!(use_target(&module, &path("self::D")), Some(path("a::b::D")));
+        assert_eq!(use_target(&module, &path("super::D")), Some(path("a::D")));
+        assert_eq!(
+            use_target(&module, &path("super::super::D")),
+            Some(path("D"))
+        );
+        assert_eq!(
+            use_target(&module, &path("crate::x::D")),
+            Some(path("x::D"))
+        );
+        assert_eq!(use_target(&module, &path("super::super::super::D")), None);
+    }
+
+    #[test]
+    fn test_disk_cache_skips_parsing() {
+        let cache_dir = TempDir::new("resolver-cache");
+        let cache_path = cache_dir.path().join("cache.json");
+        let path = RustPath::from_str("test_crate::crustaceans::Crab").unwrap();
+
+        let mut resolver = test_resolver();
+        resolver.enable_disk_cache(cache_path.clone());
+        assert_eq!(
+            resolver.resolve(&path).unwrap(),
+            Some("A crab.".to_string())
+        );
+        resolver.save_disk_cache().unwrap();
+
+        let mut resolver = test_resolver();
+        resolver.enable_disk_cache(cache_path.clone());
+        assert_eq!(
+            resolver.resolve(&path).unwrap(),
+            Some("A crab.".to_string())
+        );
+        assert!(resolver.parse_cache.borrow().is_empty());
+    }
+
+    #[test]
+    fn test_disk_cache_reuses_chapters() {
+        let cache_dir = TempDir::new("chapter-cache");
+        let cache_path = cache_dir.path().join("cache.json");
+        let options = ExpandOptions::default();
+        let content = "{{ #rustdoc test_crate::crustaceans::Crab }}\n\n\
+                       {{ #rustdoc test_crate::crustaceans::Crab::name }}";
+
+        let mut resolver = test_resolver();
+        resolver.enable_disk_cache(cache_path.clone());
+        let expanded = expand_chapter(&resolver, &options, content).unwrap();
+        resolver.save_disk_cache().unwrap();
+        let counts = resolver.directive_counts();
+        assert_eq!(counts.expanded, 2);
+
+        let mut resolver = test_resolver();
+        resolver.enable_disk_cache(cache_path.clone());
+        assert_eq!(
+            expand_chapter(&resolver, &options, content).unwrap(),
+            expanded
+        );
+        assert!(resolver.parse_cache.borrow().is_empty());
+        // The reused chapter still counts towards the report.
+        assert_eq!(resolver.directive_counts(), counts);
+        assert_eq!(
+            resolver.undocumented(),
+            vec!["test_crate::crustaceans::Crab::name".to_string()]
+        );
+
+        // Different options render the chapter differently, so it is expanded again.
+        let options = ExpandOptions {
+            wrap_prefix: Some("<div>".to_string()),
+            ..ExpandOptions::default()
+        };
+        assert_ne!(
+            expand_chapter(&resolver, &options, content).unwrap(),
+            expanded
+        );
+        resolver.save_disk_cache().unwrap();
+
+        // Saving drops the entries a run didn't use, like chapters from before an edit, but
+        // keeps the ones a reused chapter was made from.
+        let saved_keys = || {
+            let saved: serde_json::Value =
+                serde_json::from_slice(&std::fs::read(&cache_path).unwrap()).unwrap();
+            let mut keys: Vec<String> = saved["entries"]
+                .as_object()
+                .unwrap()
+                .keys()
+                .cloned()
+                .collect();
+            keys.sort();
+            keys
+        };
+        let chapters = |keys: &[String]| keys.iter().filter(|k| k.starts_with("chapter ")).count();
+        let keys = saved_keys();
+        assert_eq!(chapters(&keys), 2);
+        assert!(keys.contains(&"doc test_crate::crustaceans::Crab".to_string()));
+        let mut resolver = test_resolver();
+        resolver.enable_disk_cache(cache_path.clone());
+        let edited = format!("{}\n\nMore crabs.", content);
+        expand_chapter(&resolver, &ExpandOptions::default(), &edited).unwrap();
+        resolver.save_disk_cache().unwrap();
+        let keys = saved_keys();
+        assert_eq!(chapters(&keys), 1);
+        assert!(keys.contains(&"doc test_crate::crustaceans::Crab".to_string()));
+    }
+
+    #[cfg(feature = "docs-rs")]
+    #[test]
+    fn test_disk_cache_skips_failed_docs_rs_chapters() {
+        let cache_dir = TempDir::new("docs-rs-cache");
+        let cache_path = cache_dir.path().join("cache.json");
+        let test_crate = format!("test_crate={}/test-crate", env!("CARGO_MANIFEST_DIR"));
+        let options = ResolveOptions {
+            docs_rs: vec!["serde=1.0.200".parse().unwrap()],
+            ..ResolveOptions::default()
+        };
+        let mut resolver =
+            Resolver::with_options(CrateRoots::try_from(vec![test_crate]).unwrap(), options);
+        resolver.enable_disk_cache(cache_path.clone());
+        // A failed download is remembered for the rest of the run, as `None`.
+        resolver
+            .docs_rs_cache
+            .borrow_mut()
+            .insert("serde".to_string(), None);
+        let content = "{{ #rustdoc test_crate::crustaceans::Crab }}\n\n\
+                       {{ #rustdoc serde::Serialize }}";
+        expand_chapter(&resolver, &ExpandOptions::default(), content).unwrap();
+        expand_chapter(
+            &resolver,
+            &ExpandOptions::default(),
+            "{{ #rustdoc test_crate::crustaceans::Crab }}",
+        )
+        .unwrap();
+        resolver.save_disk_cache().unwrap();
+
+        let saved: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(&cache_path).unwrap()).unwrap();
+        let chapters = saved["entries"]
+            .as_object()
+            .unwrap()
+            .keys()
+            .filter(|k| k.starts_with("chapter "))
+            .count();
+        assert_eq!(chapters, 1);
+    }
+
+    #[test]
+    fn test_supertrait_items() {
+        assert_eq!(
+            resolve("test_crate::crab_behavior::Dive::dive"),
+            Some("Go under.".to_string())
+        );
+        assert_eq!(
+            resolve("test_crate::crab_behavior::Dive::paddle"),
+            Some("Paddle through the water.".to_string())
+        );
+        // `step` is two supertraits up, from `Dive` through `Swim` to `Move`.
+        assert_eq!(
+            resolve("test_crate::crab_behavior::Dive::step"),
+            Some("Take a step.".to_string())
+        );
+        assert_eq!(resolve("test_crate::crab_behavior::Dive::fly"), None);
+        // Supertraits reached more than once are only searched once.
+        assert_eq!(
+            resolve("test_crate::crab_behavior::Glide::step"),
+            Some("Take a step.".to_string())
+        );
+        assert_eq!(resolve("test_crate::crab_behavior::Glide::soar"), None);
+    }
+
+    #[test]
+    fn test_trait_default_methods() {
+        assert_eq!(
+            resolve("test_crate::crab_behavior::Speak"),
+            Some("Things that can talk.".to_string())
+        );
+        assert_eq!(
+            resolve("test_crate::crab_behavior::Speak::greet"),
+            Some("Say hello.".to_string())
+        );
+        // Crab inherits `greet` from the trait, but has its own `sign_off`.
+        assert_eq!(
+            resolve("test_crate::crustaceans::Crab::greet"),
+            Some("Say hello.".to_string())
+        );
+        assert_eq!(
+            resolve("test_crate::crustaceans::Crab::sign_off"),
+            Some("Crabs wave a claw goodbye.".to_string())
+        );
+        // Methods that the impl defines are never looked up in the trait.
+        assert_eq!(
+            resolve("test_crate::crustaceans::Crab::name"),
+            Some(String::new())
+        );
+    }
+
+    #[test]
+    fn test_scans_skip_unparseable_files() {
+        let crate_dir = TempDir::new("broken");
+        crate_dir.write("src/lib.rs", "mod bad;\nmod walking;\npub struct Crab;");
+        crate_dir.write("src/bad.rs", b"/// Not UTF-8: \xff\npub struct Bad;");
+        crate_dir.write(
+            "src/walking.rs",
+            "impl crate::Crab {\n    /// Walk sideways.\n    pub fn walk(&self) {}\n}",
+        );
+        let root = format!("broken={}", crate_dir.path().to_string_lossy());
+        let resolver = Resolver::new(CrateRoots::try_from(vec![root]).unwrap());
+
+        let walk = RustPath::from_str("broken::Crab::walk").unwrap();
+        assert_eq!(
+            resolver.resolve(&walk).unwrap(),
+            Some("Walk sideways.".to_string())
+        );
+        // Looking inside the bad file itself is still an error.
+        let bad = RustPath::from_str("broken::bad::Bad").unwrap();
+        assert!(resolver.resolve(&bad).is_err());
+    }
+
+    #[test]
+    fn test_crate_relative_paths() {
+        assert_eq!(resolve("crustaceans::Crab"), Some("A crab.".to_string()));
+        assert_eq!(
+            resolve("crustaceans::Crab::num_legs"),
+            resolve("test_crate::crustaceans::Crab::num_legs")
+        );
+        assert_eq!(resolve("Crab::scuttle"), Some("Walk sideways.".to_string()));
+        let resolver = test_resolver();
+        let path = |path: &str| RustPath::from_str(path).unwrap();
+        assert_eq!(
+            resolver
+                .resolve_module_items(&path("habitats::shore"))
+                .unwrap(),
+            resolver
+                .resolve_module_items(&path("test_crate::habitats::shore"))
+                .unwrap()
+        );
+        assert_eq!(
+            resolver
+                .resolve_methods(&path("crustaceans::Crab"), MethodOrder::Source)
+                .unwrap(),
+            resolver
+                .resolve_methods(&path("test_crate::crustaceans::Crab"), MethodOrder::Source)
+                .unwrap()
+        );
+        // Paths that aren't in the crate either still fail for the missing crate.
+        let err = resolver.resolve(&path("Lobster::pinch")).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<ResolveError>(),
+            Some(ResolveError::CrateNotFound { .. })
+        ));
+
+        // With more than one crate, every path needs its crate name.
+        let roots = vec![
+            format!("test_crate={}/test-crate", env!("CARGO_MANIFEST_DIR")),
+            format!(
+                "custom_lib={}/test-crate-custom-lib",
+                env!("CARGO_MANIFEST_DIR")
+            ),
+        ];
+        let resolver = Resolver::new(CrateRoots::try_from(roots).unwrap());
+        assert!(resolver.resolve(&path("crustaceans::Crab")).is_err());
+    }
+
+    #[test]
+    fn test_marked_module_items() {
+        let crate_dir = TempDir::new("marked");
+        crate_dir.write(
+            "src/lib.rs",
+            "/// A crab.\n#[stable(since = \"1.0\")]\npub struct Crab;\n\
+             /// A lobster.\npub struct Lobster;\n\
+             /// A shrimp.\n#[tracking::since(\"1.2\")]\npub struct Shrimp;",
+        );
+        let root = format!("marked={}", crate_dir.path().to_string_lossy());
+        let resolver = Resolver::new(CrateRoots::try_from(vec![root]).unwrap());
+        let list = |attr| {
+            resolver
+                .resolve_marked_module_items(&RustPath::from_str("marked").unwrap(), attr)
+                .unwrap()
+                .unwrap()
+        };
+
+        assert_eq!(list("stable"), "### Crab\n\nA crab.");
+        assert!(list("tracking::since").starts_with("### Shrimp\n\n"));
+        // Only the whole path matches.
+        assert_eq!(list("since"), "");
+        assert_eq!(list("unstable"), "");
+    }
+
+    #[test]
+    fn test_public_visibility() {
+        let test_crate = format!("test_crate={}/test-crate", env!("CARGO_MANIFEST_DIR"));
+        let options = ResolveOptions {
+            visibility: VisibilityFilter::Public,
+            ..ResolveOptions::default()
+        };
+        let resolver =
+            Resolver::with_options(CrateRoots::try_from(vec![test_crate]).unwrap(), options);
+        let is_found = |path: &str| {
+            resolver
+                .resolve(&RustPath::from_str(path).unwrap())
+                .unwrap()
+                .is_some()
+        };
+
+        assert!(is_found("test_crate::crustaceans::Crab"));
+        assert!(is_found("test_crate::crustaceans::Crab::num_legs"));
+        assert!(is_found("test_crate::crustaceans::LobsterColor::Red::0"));
+        assert!(is_found("test_crate::crustaceans::Crab::scuttle"));
+        assert!(!is_found("test_crate::Scuttle"));
+        assert!(!is_found("test_crate::crab_behavior::Speak"));
+        assert!(!is_found("test_crate::crustaceans::CookedCrab::0"));
+    }
+
+    #[test]
+    fn test_deprecation_notice() {
+        let notice = |item: ItemStruct| deprecation_notice(&item.attrs);
+        assert_eq!(notice(parse_quote! { struct Crab; }), None);
+        assert_eq!(
+            notice(parse_quote! { #[deprecated] struct Crab; }),
+            Some("> **Deprecated**".to_string())
+        );
+        assert_eq!(
+            notice(parse_quote! { #[deprecated = "Use Lobster"] struct Crab; }),
+            Some("> **Deprecated:** Use Lobster".to_string())
+        );
+        assert_eq!(
+            notice(
+                parse_quote! { #[deprecated(since = "0.2.0", note = "Use Lobster")] struct Crab; }
+            ),
+            Some("> **Deprecated since 0.2.0:** Use Lobster".to_string())
+        );
+    }
+
+    #[test]
+    fn test_must_use_notice() {
+        let notice = |item: ItemFn| must_use_notice(&item.attrs);
+        assert_eq!(notice(parse_quote! { fn count() -> usize {} }), None);
+        assert_eq!(
+            notice(parse_quote! { #[must_use] fn count() -> usize {} }),
+            Some("> **Must be used**".to_string())
+        );
+
+        let test_crate = format!("test_crate={}/test-crate", env!("CARGO_MANIFEST_DIR"));
+        let options = ResolveOptions {
+            must_use_notices: true,
+            ..ResolveOptions::default()
+        };
+        let resolver =
+            Resolver::with_options(CrateRoots::try_from(vec![test_crate]).unwrap(), options);
+        let path = RustPath::from_str("test_crate::crab_behavior::count_crabs").unwrap();
+        assert_eq!(
+            resolver.resolve(&path).unwrap(),
+            Some(
+                "> **Must be used:** counting crabs takes a while, so use the count\n\n\
+                 Count the crabs living in a burrow."
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            resolve("test_crate::crab_behavior::count_crabs"),
+            Some("Count the crabs living in a burrow.".to_string())
+        );
+    }
+
+    #[test]
+    fn test_deprecation_notices_option() {
+        let test_crate = format!("test_crate={}/test-crate", env!("CARGO_MANIFEST_DIR"));
+        let options = ResolveOptions {
+            deprecation_notices: true,
+            ..ResolveOptions::default()
+        };
+        let resolver =
+            Resolver::with_options(CrateRoots::try_from(vec![test_crate]).unwrap(), options);
+        let path = RustPath::from_str("test_crate::boil_crab").unwrap();
+        assert_eq!(
+            resolver.resolve(&path).unwrap(),
+            Some(
+                "> **Deprecated since 0.2.0:** Use `crustaceans::cook` instead\n\n\
+                 Boil a crab the old way."
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            resolve("test_crate::boil_crab"),
+            Some("Boil a crab the old way.".to_string())
+        );
+    }
+
+    #[test]
+    fn test_generic_arguments_are_ignored() {
+        assert_eq!(
+            resolve("test_crate::crustaceans::Crab::<u8>::scuttle"),
+            resolve("test_crate::crustaceans::Crab::scuttle")
+        );
+        assert_eq!(
+            resolve("<test_crate::crustaceans::Crab as Speak>::greet"),
+            Some("Say hello.".to_string())
+        );
+    }
+
+    #[test]
+    fn test_signature_to_string() {
+        let item: Item = parse_quote! {
+            pub fn parse<'a, T: Clone>(input: &'a mut Vec<Option<T>>, n: usize) -> Result<&'a T, ()> {}
+        };
+        assert_eq!(
+            signature_to_string(&item_signature(&item).unwrap()),
+            "pub fn parse<'a, T: Clone>(input: &'a mut Vec<Option<T>>, n: usize) -> Result<&'a T, ()>"
+        );
+        let item: Item = parse_quote! { pub(crate) struct Wrapper<T> { inner: T } };
+        assert_eq!(
+            signature_to_string(&item_signature(&item).unwrap()),
+            "pub(crate) struct Wrapper<T>"
+        );
+    }
+
+    #[test]
+    fn test_signature_fallback() {
+        let test_crate = format!("test_crate={}/test-crate", env!("CARGO_MANIFEST_DIR"));
+        let options = ResolveOptions {
+            signature_fallback: true,
+            ..ResolveOptions::default()
+        };
+        let resolver =
+            Resolver::with_options(CrateRoots::try_from(vec![test_crate]).unwrap(), options);
+        let resolve = |path: &str| {
+            resolver
+                .resolve(&RustPath::from_str(path).unwrap())
+                .unwrap()
+                .unwrap()
+        };
+        assert_eq!(
+            resolve("test_crate::crustaceans::Crab::name"),
+            "```rust\nfn name(&self) -> String\n```"
+        );
+        assert_eq!(
+            resolve("test_crate::crustaceans::Crab::num_legs"),
+            "The number of legs this crab has. Probably 8, but there are some weird\n\
+             crabs out there!"
+        );
+    }
+
+    #[test]
+    fn test_expand_field_types() {
+        let test_crate = format!("test_crate={}/test-crate", env!("CARGO_MANIFEST_DIR"));
+        let options = ResolveOptions {
+            expand_field_types: true,
+            ..ResolveOptions::default()
+        };
+        let resolver =
+            Resolver::with_options(CrateRoots::try_from(vec![test_crate]).unwrap(), options);
+        let resolve = |path: &str| {
+            resolver
+                .resolve(&RustPath::from_str(path).unwrap())
+                .unwrap()
+        };
+        // Types are found through a relative path, or by name in the field's own module.
+        assert_eq!(
+            resolve("test_crate::crab_behavior::Order::crab"),
+            Some("The crab to cook.\n\n`Crab`: A crab.".to_string())
+        );
+        assert_eq!(
+            resolve("test_crate::crab_behavior::Order::on_ready"),
+            Some(
+                "What to call when the crab is ready.\n\n`CrabHandler`: Called with each crab \
+                 that is found, returning whether to keep looking."
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            resolve("test_crate::crustaceans::CookedCrab::0"),
+            Some("The crab that was cooked.\n\n`Crab`: A crab.".to_string())
+        );
+        // Types from other crates have nothing to add, and neither do items that aren't fields.
+        assert_eq!(
+            resolve("test_crate::crab_behavior::Order::note"),
+            Some("Anything else the kitchen should know.".to_string())
+        );
+        assert_eq!(
+            resolve("test_crate::crab_behavior::Order"),
+            Some("A crab to be cooked, and what to do once it is.".to_string())
+        );
+        // Off by default.
+        assert_eq!(
+            test_resolver()
+                .resolve(&RustPath::from_str("test_crate::crab_behavior::Order::crab").unwrap())
+                .unwrap(),
+            Some("The crab to cook.".to_string())
+        );
+    }
+
+    #[test]
+    fn test_trait_defaults() {
+        let crate_dir = TempDir::new("defaults");
+        crate_dir.write(
+            "src/lib.rs",
+            "pub trait Shape {\n\
+             /// How many sides the shape has.\nconst SIDES: u8 = 2 + 2;\n\
+             /// How wide the shape is.\nconst WIDTH: u32;\n\
+             /// What the shape is measured in.\ntype Unit = Vec<u32>;\n\
+             const COLOR: &'static str = \"red\";\n}",
+        );
+        let root = format!("shapes={}", crate_dir.path().to_string_lossy());
+        let path = |path: &str| RustPath::from_str(path).unwrap();
+
+        let resolver = Resolver::new(CrateRoots::try_from(vec![root.clone()]).unwrap());
+        assert_eq!(
+            resolver.resolve(&path("shapes::Shape::SIDES")).unwrap(),
+            Some("How many sides the shape has.".to_string())
+        );
+        assert_eq!(
+            resolver
+                .resolve_const_value(&path("shapes::Shape::SIDES"))
+                .unwrap(),
+            Some("SIDES = 2 + 2".to_string())
+        );
+        assert!(resolver
+            .resolve_const_value(&path("shapes::Shape::WIDTH"))
+            .is_err());
+
+        let options = ResolveOptions {
+            signature_fallback: true,
+            ..ResolveOptions::default()
+        };
+        let resolver = Resolver::with_options(CrateRoots::try_from(vec![root]).unwrap(), options);
+        let resolve = |p: &str| resolver.resolve(&path(p)).unwrap().unwrap();
+        assert_eq!(
+            resolve("shapes::Shape::SIDES"),
+            "How many sides the shape has.\n\nDefaults to `2 + 2`."
+        );
+        assert_eq!(
+            resolve("shapes::Shape::Unit"),
+            "What the shape is measured in.\n\nDefaults to `Vec<u32>`."
+        );
+        assert_eq!(resolve("shapes::Shape::WIDTH"), "How wide the shape is.");
+        assert_eq!(
+            resolve("shapes::Shape::COLOR"),
+            "```rust\nconst COLOR: &'static str\n```\n\nDefaults to `\"red\"`."
+        );
+    }
+
+    #[test]
+    fn test_list_items() {
+        let crate_dir = TempDir::new("list");
+        crate_dir.write(
+            "src/lib.rs",
+            "/// Documented.\n\
+             pub struct Documented;\n\
+             pub struct Bare;\n\
+             /// Hidden.\n\
+             #[doc(hidden)]\n\
+             pub struct Hidden;\n\
+             struct Private;\n\
+             pub mod outer;\n\
+             mod private_mod {\n\
+                 /// Only reachable inside the crate.\n\
+                 pub fn secret() {}\n\
+             }",
+        );
+        crate_dir.write(
+            "src/outer.rs",
+            "//! Documented in its own file.\n\npub fn inner() {}",
+        );
+        let root = format!("listed={}", crate_dir.path().to_string_lossy());
+        let list = |visibility| {
+            let options = ResolveOptions {
+                visibility,
+                ..ResolveOptions::default()
+            };
+            let crates = CrateRoots::try_from(vec![root.clone()]).unwrap();
+            let items = Resolver::with_options(crates, options)
+                .list_items("listed")
+                .unwrap();
+            items
+                .into_iter()
+                .map(|(path, has_docs)| (path.to_string(), has_docs))
+                .collect::<Vec<_>>()
+        };
+        let item = |path: &str, has_docs| (path.to_string(), has_docs);
+
+        assert_eq!(
+            list(VisibilityFilter::All),
+            vec![
+                item("listed::Documented", true),
+                item("listed::Bare", false),
+                item("listed::Private", false),
+                item("listed::outer", true),
+                item("listed::private_mod", false),
+                item("listed::outer::inner", false),
+                item("listed::private_mod::secret", true),
+            ]
+        );
+        assert_eq!(
+            list(VisibilityFilter::Public),
+            vec![
+                item("listed::Documented", true),
+                item("listed::Bare", false),
+                item("listed::outer", true),
+                item("listed::outer::inner", false),
+            ]
+        );
+        assert!(test_resolver().list_items("shrimp").is_err());
+    }
+
+    #[test]
+    fn test_undocumented_items_are_recorded() {
+        let resolver = test_resolver();
+        for path in &[
+            "test_crate::crustaceans::Crab",
+            "test_crate::crustaceans::Crab::name",
+            "test_crate::crustaceans::Crab::name",
+        ] {
+            resolver
+                .resolve(&RustPath::from_str(path).unwrap())
+                .unwrap();
+        }
+        assert_eq!(
+            resolver.undocumented(),
+            vec!["test_crate::crustaceans::Crab::name".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_resolve_source() {
+        let resolver = test_resolver();
+        let greet = RustPath::from_str("test_crate::crab_behavior::Speak::greet").unwrap();
+        assert_eq!(
+            resolver.resolve_source(&greet, None).unwrap().unwrap(),
+            "/// Say hello.\nfn greet(&self) -> String {\n    format!(\"Hello, I'm {}\", self.name())\n}"
+        );
+        assert_eq!(
+            resolver
+                .resolve_source(&greet, Some(26..=26))
+                .unwrap()
+                .unwrap(),
+            "format!(\"Hello, I'm {}\", self.name())"
+        );
+        // Ranges are limited to the lines of the item.
+        assert_eq!(
+            resolver
+                .resolve_source(&greet, Some(1..=25))
+                .unwrap()
+                .unwrap(),
+            "/// Say hello.\nfn greet(&self) -> String {"
+        );
+        let err = resolver
+            .resolve_source(&greet, Some(30..=40))
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            err,
+            "Lines 30:40 are outside of test_crate::crab_behavior::Speak::greet, which is on lines 24:27"
+        );
+    }
+
+    #[test]
+    fn test_blank_lines_around_docs() {
+        let pinch = RustPath::from_str("test_crate::crustaceans::Crab::pinch").unwrap();
+        assert_eq!(
+            test_resolver().resolve(&pinch).unwrap().as_deref(),
+            Some("Pinch something.\n\nCrabs only pinch when they are scared.")
+        );
+
+        let test_crate = format!("test_crate={}/test-crate", env!("CARGO_MANIFEST_DIR"));
+        let options = ResolveOptions {
+            keep_blank_lines: true,
+            ..ResolveOptions::default()
+        };
+        let resolver =
+            Resolver::with_options(CrateRoots::try_from(vec![test_crate]).unwrap(), options);
+        assert_eq!(
+            resolver.resolve(&pinch).unwrap().as_deref(),
+            Some("\nPinch something.\n\nCrabs only pinch when they are scared.\n")
+        );
+    }
+
+    #[test]
+    fn test_item_info() {
+        let info = |path: &str| {
+            test_resolver()
+                .resolve_info(&RustPath::from_str(path).unwrap())
+                .unwrap()
+                .unwrap()
+        };
+        let crab = info("test_crate::crustaceans::Crab");
+        assert_eq!(crab.kind, "struct");
+        assert_eq!(crab.docs, "A crab.");
+        assert!(crab.file.is_absolute());
+        assert!(crab.file.ends_with("test-crate/src/crustaceans.rs"));
+        assert_eq!((crab.start.line, crab.start.column), (3, 0));
+        assert_eq!((crab.end.line, crab.end.column), (9, 1));
+        assert_eq!(crab.edition.as_deref(), Some("2018"));
+
+        let num_legs = info("test_crate::crustaceans::Crab::num_legs");
+        assert_eq!(num_legs.kind, "field");
+        assert_eq!((num_legs.start.line, num_legs.start.column), (6, 4));
+        assert_eq!((num_legs.end.line, num_legs.end.column), (8, 20));
+
+        let scuttle = info("test_crate::crustaceans::Crab::scuttle");
+        assert_eq!(scuttle.kind, "method");
+        assert!(scuttle.file.ends_with("src/crab_behavior.rs"));
+        assert_eq!((scuttle.start.line, scuttle.end.line), (6, 7));
+
+        let module = info("test_crate::crustaceans");
+        assert_eq!(module.kind, "mod");
+        assert!(module.file.ends_with("src/crustaceans.rs"));
+        assert_eq!(module.start.line, 1);
+    }
+
+    #[test]
+    fn test_doc_includes_are_relative_to_their_file() {
+        let resolver = test_resolver();
+        let docs = resolver
+            .resolve(&RustPath::from_str("test_crate::net::Tcp").unwrap())
+            .unwrap();
+        let tcp_docs =
+            "Talks to other crabs over TCP.\n\n- Opens a connection\n    - and keeps it open";
+        assert_eq!(docs.as_deref(), Some(tcp_docs));
+
+        let listing = resolver
+            .resolve_module_items(&RustPath::from_str("test_crate::net").unwrap())
+            .unwrap();
+        assert_eq!(listing, Some(format!("### Tcp\n\n{}", tcp_docs)));
+    }
+
+    #[test]
+    fn test_target_dirs() {
+        let test_crate = format!("test_crate={}/test-crate", env!("CARGO_MANIFEST_DIR"));
+        let options = ResolveOptions {
+            target_dirs: vec!["examples".to_string()],
+            ..ResolveOptions::default()
+        };
+        let resolver =
+            Resolver::with_options(CrateRoots::try_from(vec![test_crate]).unwrap(), options);
+        let resolve_target = |path: &str| {
+            resolver
+                .resolve(&RustPath::from_str(path).unwrap())
+                .unwrap()
+        };
+        assert_eq!(
+            resolve_target("test_crate::examples::boiling"),
+            Some("Shows how to boil a crab.".to_string())
+        );
+        assert_eq!(
+            resolve_target("test_crate::examples::boiling::run"),
+            Some("Run the example.".to_string())
+        );
+        assert_eq!(resolve_target("test_crate::examples::missing::run"), None);
+        assert_eq!(
+            resolver
+                .resolve_module_items(&RustPath::from_str("test_crate::examples::boiling").unwrap())
+                .unwrap(),
+            Some("### run\n\nRun the example.".to_string())
+        );
+
+        // Without the option, target directories aren't searched.
+        assert_eq!(resolve("test_crate::examples::boiling::run"), None);
+    }
+
+    #[test]
+    fn test_source_locations() {
+        let location = |path: &str| {
+            test_resolver()
+                .resolve_location(&RustPath::from_str(path).unwrap())
+                .unwrap()
+                .map(|location| (location.file.to_string_lossy().into_owned(), location.line))
+        };
+        let crustaceans = "src/crustaceans.rs".to_string();
+        assert_eq!(location("test_crate"), Some(("src/lib.rs".to_string(), 1)));
+        assert_eq!(
+            location("test_crate::crustaceans"),
+            Some((crustaceans.clone(), 1))
+        );
+        assert_eq!(
+            location("test_crate::crustaceans::Crab"),
+            Some((crustaceans.clone(), 5))
+        );
+        assert_eq!(
+            location("test_crate::crustaceans::CookedCrab::1"),
+            Some((crustaceans.clone(), 21))
+        );
+        assert_eq!(
+            location("test_crate::crustaceans::LobsterColor::halloween"),
+            Some((crustaceans, 61))
+        );
+        assert_eq!(
+            location("test_crate::crustaceans::Crab::scuttle"),
+            Some(("src/crab_behavior.rs".to_string(), 7))
+        );
+    }
+}
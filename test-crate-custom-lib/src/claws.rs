@@ -0,0 +1,4 @@
+//! Things to pinch with.
+
+/// A claw, for pinching.
+pub struct Claw;
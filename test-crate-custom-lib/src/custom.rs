@@ -0,0 +1,3 @@
+//! A crate whose library target isn't at `src/lib.rs`.
+
+pub mod claws;
@@ -0,0 +1,8 @@
+//! Shows how to boil a crab.
+
+/// Run the example.
+pub fn run() {}
+
+fn main() {
+    run();
+}
@@ -0,0 +1,171 @@
+//! Crabs are defined in `crustaceans`, but what they do is defined here.
+
+use crate::crustaceans::Crab;
+
+impl Crab {
+    /// Walk sideways.
+    pub fn scuttle(&self) {}
+
+    /// Shed the old shell.
+    ///
+    /// This takes a few hours,  
+    /// and the crab hides until the new shell hardens.
+    pub fn molt(&mut self) {}
+
+    /// Wave a claw, like {{ #rustdoc test_crate::crustaceans::Crab::wave }} does.
+    pub fn wave(&self) {}
+}
+
+/// Things that can talk.
+pub trait Speak {
+    /// The name of the speaker.
+    fn name(&self) -> String;
+
+    /// Say hello.
+    fn greet(&self) -> String {
+        format!("Hello, I'm {}", self.name())
+    }
+
+    /// Say goodbye.
+    fn sign_off(&self) -> String {
+        "Goodbye".to_string()
+    }
+}
+
+impl Speak for Crab {
+    fn name(&self) -> String {
+        "Crab".to_string()
+    }
+
+    /// Crabs wave a claw goodbye.
+    fn sign_off(&self) -> String {
+        "*waves claw*".to_string()
+    }
+}
+
+impl Crab {
+    ///
+    /// Pinch something.
+    ///
+    /// Crabs only pinch when they are scared.
+    ///
+    pub fn pinch(&self) {}
+}
+
+/// A crab that has been around for a while.
+pub type OldCrab = Crab;
+
+/// The bytes of a crab's shell.
+pub type Shell = Vec<u8>;
+
+impl Crab {
+    /// Grow back a lost claw, which isn't part of the public API.
+    #[doc(hidden)]
+    pub fn regrow_claw(&self) {}
+
+    /// Rest in the sand.
+    #[allow(dead_code)]
+    fn rest(&self) {}
+}
+
+/// How many crabs there are in this crate.
+pub static CRAB_COUNT: usize = 1;
+
+/// # Burrows
+///
+/// Where a crab hides from the tide.
+#[derive(Debug, Default)]
+pub struct Burrow;
+
+/// Dig a new burrow.
+pub fn dig_burrow() -> Burrow {
+    /// A tunnel leading into the burrow.
+    pub struct Tunnel {
+        /// How long the tunnel is.
+        pub length: u32,
+    }
+
+    /// The way into the burrow.
+    #[allow(dead_code)]
+    struct Entrance;
+
+    let _tunnel = Tunnel { length: 3 };
+    Burrow
+}
+
+impl Burrow {
+    /// Make the burrow wider.
+    pub fn widen(&mut self) {
+        /// The widest a burrow can get.
+        pub const MAX_WIDTH: u32 = 5;
+    }
+}
+
+impl std::fmt::Display for Burrow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "a burrow")
+    }
+}
+
+/// Burrows echo whatever is said into them.
+impl Speak for Burrow {
+    fn name(&self) -> String {
+        "Burrow".to_string()
+    }
+}
+
+#[doc(hidden)]
+impl Clone for Burrow {
+    fn clone(&self) -> Self {
+        Burrow
+    }
+}
+
+/// Count the crabs living in a burrow.
+#[must_use = "counting crabs takes a while, so use the count"]
+pub fn count_crabs(_burrow: &Burrow) -> usize {
+    0
+}
+
+/// Things that can move.
+pub trait Move {
+    /// Take a step.
+    fn step(&self);
+}
+
+/// Things that can swim, which can also move.
+pub trait Swim: Move {
+    /// Paddle through the water.
+    fn paddle(&self) {}
+}
+
+/// Things that can dive, which can also swim.
+pub trait Dive: Swim + Send {
+    /// Go under.
+    fn dive(&self) {}
+}
+
+/// Things that can glide, which reach `Move` both directly and through `Swim`.
+pub trait Glide: Swim + Move {}
+
+/// A map of a burrow's tunnels.
+///
+/// ![The tunnels](../assets/tunnels.png) are explained in the [digging guide](guide.md#tunnels),
+/// on [the web](https://example.com/burrows), and [below](#tunnels), next to
+/// [`Burrow`](crate::crab_behavior::Burrow).
+pub struct BurrowMap;
+
+/// Called with each crab that is found, returning whether to keep looking.
+pub type CrabHandler = fn(&Crab) -> bool;
+
+pub type MoltCallback = for<'a> fn(&'a mut Crab, u32) -> Option<&'a Crab>;
+
+/// A crab to be cooked, and what to do once it is.
+pub struct Order {
+    /// The crab to cook.
+    pub crab: super::crustaceans::Crab,
+    /// What to call when the crab is ready.
+    pub on_ready: CrabHandler,
+    /// Anything else the kitchen should know.
+    pub note: String,
+}
@@ -1,12 +1,18 @@
 //! All sorts of crustaceans.
 
 /// A crab.
+#[doc(alias = "Shellfish")]
 pub struct Crab {
     /// The number of legs this crab has. Probably 8, but there are some weird
     /// crabs out there!
     pub num_legs: u8,
 }
 
+impl Crab {
+    /// The most legs a crab can have.
+    pub const MAX_LEGS: u8 = 10;
+}
+
 /// Some people eat crabs
 pub struct CookedCrab(
     /// The crab that was cooked.
@@ -18,6 +24,7 @@ pub struct CookedCrab(
 /// Lobster colors, according to Wikipedia.
 pub enum LobsterColor {
     /// Also called white; translucent; ghost; crystal.
+    #[doc(alias = "Ghost")]
     Albino,
     /// Also called pastel. Possibly a sub-type of albino
     CottonCandy,
@@ -47,6 +54,10 @@ pub enum LobsterColor {
 
 impl LobsterColor {
     /// A common split colored lobster.
+    ///
+    /// ```
+    /// let lobster = LobsterColor::halloween();
+    /// ```
     pub fn halloween() -> Self {
         Self::SplitColored {
             primary: Box::new(Self::Orange),
@@ -54,3 +65,12 @@ impl LobsterColor {
         }
     }
 }
+
+/// How long to boil a lobster for.
+pub const BOIL_SECONDS: u32 = 12 * 60;
+
+/// Cook a crab.
+#[doc(alias("boil", "steam"))]
+pub fn cook(crab: Crab) -> CookedCrab {
+    CookedCrab(crab, "boiled".to_string())
+}
@@ -0,0 +1,42 @@
+//! One documented item of each kind that can be embedded, for the test book.
+
+/// Add two sizes together.
+pub fn add(a: usize, b: usize) -> usize {
+    a + b
+}
+
+/// Things that have a size.
+pub trait Measure {
+    /// The size of the thing.
+    fn size(&self) -> usize;
+
+    /// Whether the thing has no size at all.
+    fn is_empty(&self) -> bool {
+        self.size() == 0
+    }
+}
+
+/// The largest size anything can be.
+pub const MAX_SIZE: usize = 1024;
+
+/// How many things there are to measure.
+pub static THINGS: usize = 3;
+
+/// A list of sizes.
+pub type Sizes = Vec<usize>;
+
+/// Either a whole size or a fraction of one, in the same bits.
+pub union Amount {
+    /// A whole size.
+    pub whole: u64,
+    /// A fraction of a size.
+    pub fraction: f64,
+}
+
+/// Make a list of sizes.
+#[macro_export]
+macro_rules! sizes {
+    ($($size:expr),*) => {
+        vec![$($size),*]
+    };
+}
@@ -1,5 +1,61 @@
 //! Test crate module docs here.
 
 pub mod crustaceans;
+mod crab_behavior;
+#[allow(dead_code)]
+mod kinds;
+#[allow(dead_code)]
+mod net;
 
 pub use crustaceans::Crab;
+pub use habitats::shore as coast;
+pub use habitats::TidePool as RockPool;
+
+/// Only used inside this crate.
+#[allow(dead_code)]
+struct Scuttle;
+
+mod scuttling {
+    impl super::Scuttle {
+        /// Scuttle to the left.
+        #[allow(dead_code)]
+        pub fn left(&self) {}
+    }
+}
+
+/// Not part of the public API.
+#[doc(hidden)]
+pub struct Molt;
+
+/// Boil a crab the old way.
+#[deprecated(since = "0.2.0", note = "Use `crustaceans::cook` instead")]
+pub fn boil_crab() {}
+
+/// Places crabs live.
+#[allow(dead_code)]
+mod habitats {
+    /// Where the sea meets the land.
+    pub mod shore {
+        //! Crabs spend most of their time here.
+
+        /// A pool left behind by the tide.
+        pub struct TidePool;
+    }
+
+    pub use shore::*;
+}
+
+/// Crabs that need the `shellfish` feature.
+#[cfg_attr(docsrs, doc(cfg(feature = "shellfish")))]
+#[cfg(feature = "shellfish")]
+mod shellfish {
+    /// A crab that lives in a borrowed shell.
+    pub struct HermitCrab;
+}
+
+/// The items that most users of this crate need.
+#[allow(dead_code)]
+mod prelude {
+    /// A net for catching crabs.
+    pub struct CrabNet;
+}
@@ -0,0 +1,4 @@
+//! Ways for crabs to talk to each other.
+
+#[doc = include_str!("tcp.md")]
+pub struct Tcp;
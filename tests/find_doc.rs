@@ -0,0 +1,47 @@
+//! Look up items in the test crate through the library's top level functions, so that finding a
+//! module's file and finding an item inside of it are tested together.
+
+use std::{convert::TryFrom, path::Path, str::FromStr};
+
+use mdbook_rust_doc::{find_doc_for_item, find_item_info, CrateRoots, ResolveError, RustPath};
+
+fn test_crates() -> CrateRoots {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let test_crate = format!("test_crate={}", root.join("test-crate").to_string_lossy());
+    CrateRoots::try_from(vec![test_crate]).unwrap()
+}
+
+fn find_doc(path: &str) -> Result<String, ResolveError> {
+    find_doc_for_item(&RustPath::from_str(path).unwrap(), &test_crates())
+}
+
+#[test]
+fn test_tuple_struct_fields_in_modules() {
+    // `CookedCrab` is in `crustaceans.rs`, so its fields are only found after reading that file.
+    assert_eq!(
+        find_doc("test_crate::crustaceans::CookedCrab::1").unwrap(),
+        "A description of how it was cooked."
+    );
+    assert_eq!(
+        find_doc("test_crate::crustaceans::CookedCrab::0").unwrap(),
+        "The crab that was cooked."
+    );
+    assert_eq!(
+        find_doc("crustaceans::CookedCrab::1").unwrap(),
+        "A description of how it was cooked."
+    );
+
+    let info = find_item_info(
+        &RustPath::from_str("test_crate::crustaceans::CookedCrab::1").unwrap(),
+        &test_crates(),
+    )
+    .unwrap();
+    assert_eq!(info.kind, "field");
+    assert!(info.file.ends_with("crustaceans.rs"));
+
+    // A field past the end is a mistake in the path, rather than a missing item.
+    let err = find_doc("test_crate::crustaceans::CookedCrab::2").unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("The tuple struct CookedCrab has 2 field(s), index 2 is out of range"));
+}
@@ -0,0 +1,36 @@
+//! Expand each chapter of the test book with the library, and compare it to the rendered chapter
+//! checked in to `test-book/expected`, so that changes to any kind of item's docs are caught by
+//! `cargo test` as well as by `bin/test-book.sh`.
+
+use std::{convert::TryFrom, fs, path::Path};
+
+use mdbook_rust_doc::{expand_chapter, CrateRoots, ExpandOptions, Resolver};
+
+#[test]
+fn test_book_chapters_match_expected() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    // Like `test-book/book.toml`, with the crate path relative to the repo's root.
+    let test_crate = format!("test_crate={}", root.join("test-crate").to_string_lossy());
+    let resolver = Resolver::new(CrateRoots::try_from(vec![test_crate]).unwrap());
+    let options = ExpandOptions::default();
+
+    let expected_dir = root.join("test-book/expected");
+    let mut chapters: Vec<_> = fs::read_dir(&expected_dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    chapters.sort();
+    assert!(!chapters.is_empty());
+
+    for chapter in chapters {
+        let source = fs::read_to_string(root.join("test-book/src").join(&chapter)).unwrap();
+        let expected = fs::read_to_string(expected_dir.join(&chapter)).unwrap();
+        let expanded = expand_chapter(&resolver, &options, &source).unwrap();
+        assert_eq!(
+            expanded.trim_end(),
+            expected.trim_end(),
+            "{} doesn't match the expected output",
+            chapter.to_string_lossy()
+        );
+    }
+}